@@ -0,0 +1,84 @@
+use bevy::prelude::*;
+use grid_util::point::Point;
+
+use crate::game::events::BoardingPointsReplaced;
+use crate::game::pirate::BOAT_POINT;
+use crate::game::tile::GRID_WIDTH;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BoardingKind {
+    // a ship pulls up alongside the hold, on the port side at `x` 0 or the starboard
+    // side at `x` GRID_WIDTH - 1
+    Ship,
+    // a hatch from the deck above, on a tile inside the hold
+    Hatch,
+}
+
+// somewhere pirates come aboard, and that they can escape through again
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoardingPoint {
+    pub point: Point,
+    pub kind: BoardingKind,
+}
+
+impl BoardingPoint {
+    pub fn is_starboard(&self) -> bool {
+        self.point.x == GRID_WIDTH - 1
+    }
+}
+
+pub const PORT_SHIP: BoardingPoint = BoardingPoint {
+    point: BOAT_POINT,
+    kind: BoardingKind::Ship,
+};
+
+// The boarding points of the current layout, in the order waves open them up
+#[derive(Resource, Debug)]
+pub struct BoardingPoints {
+    pub points: Vec<BoardingPoint>,
+    // how many of `points` the wave in progress is using
+    pub open: usize,
+}
+
+impl Default for BoardingPoints {
+    fn default() -> Self {
+        Self {
+            points: vec![PORT_SHIP],
+            open: 1,
+        }
+    }
+}
+
+impl BoardingPoints {
+    // a wave boards from the first `count` points, but always from at least one
+    pub fn open_for_wave(&mut self, count: usize) -> &[BoardingPoint] {
+        self.open = count.clamp(1, self.points.len());
+        self.open()
+    }
+
+    pub fn open(&self) -> &[BoardingPoint] {
+        &self.points[..self.open]
+    }
+}
+
+// pirates are shared out as evenly as possible, earlier points taking the remainder
+pub fn pirates_at_boarding_point(pirates: u32, boarding_points: usize, index: usize) -> u32 {
+    let boarding_points = boarding_points as u32;
+    let index = index as u32;
+    pirates / boarding_points + u32::from(index < pirates % boarding_points)
+}
+
+fn on_boarding_points_replaced(
+    mut evr_boarding_points_replaced: EventReader<BoardingPointsReplaced>,
+    mut boarding_points: ResMut<BoardingPoints>,
+) {
+    for event in evr_boarding_points_replaced.read() {
+        boarding_points.points = event.points.clone();
+        boarding_points.open = 1;
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<BoardingPoints>();
+    app.add_systems(Update, on_boarding_points_replaced);
+}
@@ -1,6 +1,9 @@
 use bevy::{prelude::*, render::camera::ScalingMode};
 
-pub const VERTICAL_RESOLUTION: f32 = 1080.0;
+// The ship, the hold and the inventory all fit in this, and all of it is kept on
+// screen and centred however the window is shaped. A wider or taller window
+// shows more around the edges rather than cutting any of it off.
+pub const PLAY_AREA: Vec2 = Vec2::new(1920.0, 1080.0);
 
 #[derive(Component)]
 pub struct MainCamera;
@@ -10,9 +13,10 @@ fn setup(mut commands: Commands) {
     commands.spawn((
         Camera2d,
         Camera { ..default() },
-        Projection::custom(OrthographicProjection {
-            scaling_mode: ScalingMode::FixedVertical {
-                viewport_height: VERTICAL_RESOLUTION,
+        Projection::Orthographic(OrthographicProjection {
+            scaling_mode: ScalingMode::AutoMin {
+                min_width: PLAY_AREA.x,
+                min_height: PLAY_AREA.y,
             },
             ..OrthographicProjection::default_2d()
         }),
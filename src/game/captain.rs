@@ -0,0 +1,271 @@
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+
+use crate::game::boarding::BoardingPoints;
+use crate::game::chain::{ChainSegment, break_chain_link};
+use crate::game::events::WaveReset;
+use crate::game::game_state::GameState;
+use crate::game::oxidation::RustedThrough;
+use crate::game::oxygen::Oxygen;
+use crate::game::pirate::{
+    OnGangplank, Pirate, SPAWN_INTERVAL, SpawnTimer, Spawner, WaveState, in_breathing_range,
+    pirate_movement_system, pirate_oxygen_system,
+};
+use crate::game::tile::TILE_SIZE;
+use crate::game::ui::{HUD_MARGIN, Placed, hud, label};
+
+// a captain comes aboard every this many waves in endless mode
+pub const BOSS_WAVE_INTERVAL: u32 = 5;
+
+pub const CAPTAIN_OXYGEN_MULTIPLIER: f32 = 5.0;
+pub const CAPTAIN_SPEED_MULTIPLIER: f32 = 0.8;
+pub const CAPTAIN_SCALE: f32 = 0.8;
+
+const ABILITY_INTERVAL: f32 = 6.0;
+const REINFORCEMENTS: u32 = 2;
+const OXYGEN_TANK_LIFETIME: f32 = 8.0;
+
+// along the top of the screen, in the middle
+const BOSS_BAR_SIZE: Vec2 = Vec2::new(600.0, 24.0);
+const BOSS_BAR_SPACING: f32 = 10.0;
+
+#[derive(Clone, Copy)]
+enum Ability {
+    // snap the nearest chain link it's standing next to
+    CutChain,
+    // send for more of the crew
+    CallReinforcements,
+    // leave a tank of air behind that anyone can breathe from
+    DropOxygenTank,
+}
+
+// the captain tries these in turn, skipping any that have nothing to act on
+const ABILITIES: [Ability; 3] = [
+    Ability::CutChain,
+    Ability::CallReinforcements,
+    Ability::DropOxygenTank,
+];
+
+#[derive(Component)]
+pub struct Captain {
+    starting_oxygen: f32,
+    ability_timer: Timer,
+    next_ability: usize,
+    // reinforcements come aboard like the rest of the wave's crew
+    crew_speed: f32,
+    crew_oxygen: f32,
+}
+
+impl Captain {
+    pub fn new(starting_oxygen: f32, crew_speed: f32, crew_oxygen: f32) -> Self {
+        Self {
+            starting_oxygen,
+            ability_timer: Timer::from_seconds(ABILITY_INTERVAL, TimerMode::Repeating),
+            next_ability: 0,
+            crew_speed,
+            crew_oxygen,
+        }
+    }
+}
+
+// a temporary breathing zone, working the same way a tree does
+#[derive(Component)]
+pub struct OxygenTank(Timer);
+
+#[derive(Component)]
+struct BossBar;
+
+#[derive(Component)]
+struct BossBarFill;
+
+fn use_captain_abilities(
+    mut commands: Commands,
+    time: Res<Time>,
+    asset_server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    boarding_points: Res<BoardingPoints>,
+    mut q_captains: Query<(&mut Captain, &Transform), (With<Pirate>, Without<OnGangplank>)>,
+    q_chain: Query<(Entity, &ChainSegment, &ChildOf, &Transform), Without<RustedThrough>>,
+) {
+    for (mut captain, transform) in q_captains.iter_mut() {
+        captain.ability_timer.tick(time.delta());
+        if !captain.ability_timer.just_finished() {
+            continue;
+        }
+
+        let location = transform.translation.xy();
+        for _ in 0..ABILITIES.len() {
+            let ability = ABILITIES[captain.next_ability];
+            captain.next_ability = (captain.next_ability + 1) % ABILITIES.len();
+
+            match ability {
+                Ability::CutChain => {
+                    let Some((segment, chain_segment, child_of, _)) = q_chain
+                        .iter()
+                        .filter(|(_, _, _, segment_transform)| {
+                            in_breathing_range(location, segment_transform.translation.xy())
+                        })
+                        .min_by(|(_, _, _, a), (_, _, _, b)| {
+                            let a = a.translation.xy().distance(location);
+                            let b = b.translation.xy().distance(location);
+                            a.total_cmp(&b)
+                        })
+                    else {
+                        continue;
+                    };
+                    break_chain_link(
+                        &mut commands,
+                        &asset_server,
+                        child_of.parent(),
+                        segment,
+                        chain_segment.tile,
+                    );
+                }
+                Ability::CallReinforcements => {
+                    commands.spawn((
+                        Spawner,
+                        SpawnTimer(Timer::from_seconds(SPAWN_INTERVAL, TimerMode::Repeating)),
+                        WaveState {
+                            pirates_per_wave: REINFORCEMENTS,
+                            pirates_spawned: 0,
+                            movement_speed: captain.crew_speed,
+                            oxygen: captain.crew_oxygen,
+                            boarding_point: boarding_points.open()[0],
+                            veterans: 0,
+                            captain: false,
+                        },
+                    ));
+                }
+                Ability::DropOxygenTank => {
+                    commands.spawn((
+                        OxygenTank(Timer::from_seconds(OXYGEN_TANK_LIFETIME, TimerMode::Once)),
+                        Mesh2d(meshes.add(Circle::new(TILE_SIZE * 0.3))),
+                        MeshMaterial2d(materials.add(Color::srgb(0.3, 0.8, 0.9))),
+                        Transform::from_translation(location.extend(3.0)),
+                    ));
+                }
+            }
+            break;
+        }
+    }
+}
+
+fn expire_oxygen_tanks(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut q_tanks: Query<(Entity, &mut OxygenTank)>,
+) {
+    for (entity, mut tank) in q_tanks.iter_mut() {
+        tank.0.tick(time.delta());
+        if tank.0.finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn on_wave_reset(
+    mut commands: Commands,
+    mut evr_wave_reset: EventReader<WaveReset>,
+    q_tanks: Query<Entity, With<OxygenTank>>,
+) {
+    if evr_wave_reset.is_empty() {
+        return;
+    }
+
+    evr_wave_reset.clear();
+
+    for entity in q_tanks.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn spawn_boss_bar(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    q_new_captains: Query<(), Added<Captain>>,
+    q_boss_bar: Query<(), With<BossBar>>,
+) {
+    if q_new_captains.is_empty() || !q_boss_bar.is_empty() {
+        return;
+    }
+
+    commands
+        .spawn((BossBar, hud(GameState::WaveInProgress)))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    Placed {
+                        anchor: Anchor::TopCenter,
+                        offset: Vec2::new(0.0, HUD_MARGIN),
+                    },
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        row_gap: Val::Px(BOSS_BAR_SPACING),
+                        ..default()
+                    },
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        label(&asset_server, "Captain", 30.0, Color::WHITE),
+                        TextShadow::default(),
+                    ));
+                    parent
+                        .spawn((
+                            Node {
+                                width: Val::Px(BOSS_BAR_SIZE.x),
+                                height: Val::Px(BOSS_BAR_SIZE.y),
+                                ..default()
+                            },
+                            BackgroundColor(Color::linear_rgba(0.1, 0.1, 0.1, 1.0)),
+                        ))
+                        .with_child((
+                            BossBarFill,
+                            Node {
+                                width: Val::Percent(100.0),
+                                height: Val::Percent(100.0),
+                                ..default()
+                            },
+                            BackgroundColor(Color::linear_rgba(1.0, 0.0, 0.0, 1.0)),
+                        ));
+                });
+        });
+}
+
+// the bar empties towards the left, and goes once no captain is left aboard
+fn update_boss_bar(
+    mut commands: Commands,
+    q_captains: Query<(&Captain, &Oxygen)>,
+    q_boss_bar: Query<Entity, With<BossBar>>,
+    mut q_fill: Query<&mut Node, With<BossBarFill>>,
+) {
+    if q_captains.is_empty() {
+        for entity in q_boss_bar.iter() {
+            commands.entity(entity).despawn();
+        }
+        return;
+    }
+
+    let remaining = q_captains
+        .iter()
+        .map(|(captain, oxygen)| (oxygen.0 / captain.starting_oxygen).clamp(0.0, 1.0))
+        .fold(0.0, f32::max);
+    for mut node in q_fill.iter_mut() {
+        node.width = Val::Percent(remaining * 100.0);
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(
+        FixedUpdate,
+        (use_captain_abilities, expire_oxygen_tanks)
+            .after(pirate_movement_system)
+            .before(pirate_oxygen_system)
+            .run_if(in_state(GameState::WaveInProgress)),
+    );
+    app.add_systems(
+        Update,
+        (on_wave_reset, (spawn_boss_bar, update_boss_bar).chain()),
+    );
+}
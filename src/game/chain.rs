@@ -1,19 +1,146 @@
 use bevy::prelude::*;
 
+use crate::game::boarding::BoardingPoints;
 use crate::game::events::{
-    ChainFinished, ChainPlaced, CrateSpawned, PlayClickSFX, TileMouseDown, TileMouseMove,
+    ChainFinished, ChainPlaced, CrateSpawned, InventoryReplaced, PlayClickSFX, RemoveChain,
+    RunReset, WaveReset,
 };
 use crate::game::game_state::GameState;
+use crate::game::goldbar::LooseGold;
+use crate::game::input::{GridCursor, InputAction, update_grid_cursor};
 use crate::game::mouse::MousePos;
-use crate::game::pirate::{BOAT_POINT, HOLD_POINT, get_pathing_grid};
+use crate::game::oxidation::{Oxidation, RustedThrough};
+use crate::game::pirate::{get_pathing_grid, hold_reachable};
 use crate::game::tile::{Direction, TILE_SIZE, Tile};
+use crate::game::touch::TouchScreen;
 
 pub const CHAIN_BUTTON_SIZE: f32 = 64.0;
 
+const INVENTORY_Y: f32 = -400.0;
+const INVENTORY_SPACING: f32 = 300.0;
+const INVENTORY_WIDTH: f32 = 1500.0;
+
+// What a chain is made of decides how hard it is to breathe next to it. Every
+// segment carries its chain's material.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ChainMaterial {
+    #[default]
+    Iron,
+    // rusting away, and taking the air with it
+    RustyIron,
+    // heavy links that drain from further away
+    Anchor,
+    // barely drains at all, but there's plenty of it going spare
+    Stainless,
+}
+
+impl ChainMaterial {
+    pub const ALL: [ChainMaterial; 4] = [
+        ChainMaterial::Iron,
+        ChainMaterial::RustyIron,
+        ChainMaterial::Anchor,
+        ChainMaterial::Stainless,
+    ];
+
+    pub fn drain_multiplier(&self) -> f32 {
+        match self {
+            ChainMaterial::Iron | ChainMaterial::Anchor => 1.0,
+            ChainMaterial::RustyIron => 1.6,
+            ChainMaterial::Stainless => 0.25,
+        }
+    }
+
+    // how quickly a segment rusts for the oxygen it takes in
+    pub fn rust_rate(&self) -> f32 {
+        match self {
+            ChainMaterial::Iron => 1.0,
+            ChainMaterial::RustyIron => 1.5,
+            ChainMaterial::Anchor => 0.6,
+            ChainMaterial::Stainless => 0.1,
+        }
+    }
+
+    // how far a segment reaches, in tiles
+    pub fn breathing_range(&self) -> f32 {
+        match self {
+            ChainMaterial::Anchor => 2.2,
+            _ => 1.2,
+        }
+    }
+
+    pub fn tint(&self) -> Color {
+        match self {
+            ChainMaterial::Iron => Color::WHITE,
+            ChainMaterial::RustyIron => Color::srgb(0.85, 0.5, 0.3),
+            ChainMaterial::Anchor => Color::srgb(0.45, 0.45, 0.6),
+            ChainMaterial::Stainless => Color::srgb(0.75, 0.9, 1.0),
+        }
+    }
+
+    // materials are written by name in puzzle files, in any case
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|material| material.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChainMaterial::Iron => "Iron",
+            ChainMaterial::RustyIron => "Rusty",
+            ChainMaterial::Anchor => "Anchor",
+            ChainMaterial::Stainless => "Stainless",
+        }
+    }
+}
+
+// inventories hold one entry per (material, length)
+#[derive(Clone, Debug)]
+pub struct InventoryEntry {
+    pub stock: u32,
+    pub length: u32,
+    pub material: ChainMaterial,
+}
+
+impl InventoryEntry {
+    pub fn is_same_kind(&self, other: &InventoryEntry) -> bool {
+        self.material == other.material && self.length == other.length
+    }
+}
+
+// add more stock of a kind of chain, making room for it if it's new
+pub fn add_to_inventory(inventory: &mut Vec<InventoryEntry>, entry: InventoryEntry) {
+    match inventory
+        .iter_mut()
+        .find(|existing| existing.is_same_kind(&entry))
+    {
+        Some(existing) => existing.stock += entry.stock,
+        None => inventory.push(entry),
+    }
+}
+
+pub const ENDLESS_INVENTORY: [InventoryEntry; 3] = [
+    InventoryEntry {
+        stock: 1,
+        length: 9,
+        material: ChainMaterial::Iron,
+    },
+    InventoryEntry {
+        stock: 2,
+        length: 4,
+        material: ChainMaterial::Iron,
+    },
+    InventoryEntry {
+        stock: 3,
+        length: 2,
+        material: ChainMaterial::Iron,
+    },
+];
+
 #[derive(Component, Debug)]
 pub struct Chain {
-    #[allow(unused)]
     length: u32,
+    material: ChainMaterial,
 }
 
 #[derive(Component, Debug)]
@@ -26,6 +153,17 @@ pub struct MainInventoryChainButton;
 pub struct ChainButton {
     pub stock: u32,
     pub length: u32,
+    pub material: ChainMaterial,
+}
+
+impl ChainButton {
+    pub fn entry(&self) -> InventoryEntry {
+        InventoryEntry {
+            stock: self.stock,
+            length: self.length,
+            material: self.material,
+        }
+    }
 }
 
 #[derive(Component, Debug)]
@@ -41,8 +179,19 @@ pub struct SelectedChain;
 pub struct DraggingChain {
     remaining_length: u32,
     e_chain: Entity,
+    // the segment a branch is being drawn out of, if it is one
+    branch_from: Option<Entity>,
 }
 
+// a chain drawn out of the side of another one
+#[derive(Component, Debug)]
+pub struct BranchOf(pub Entity);
+
+// an extra link stub on a segment that a branch comes out of, pointing the way
+// the branch goes, turning the segment into a T or cross junction
+#[derive(Component)]
+struct JunctionArm(Option<Direction>);
+
 #[derive(Component, Debug)]
 pub struct ChainSegment {
     pub prev_tile: Option<Tile>,
@@ -57,64 +206,117 @@ pub struct Obstacle {
 #[derive(Component)]
 pub struct Crate;
 
+// the rotation for a single link's stub to point `direction`
+fn stub_rotation(direction: Option<&Direction>) -> Quat {
+    let rot_degrees: f32 = match direction {
+        Some(Direction::Up) => 180.0,
+        Some(Direction::Down) => 0.0,
+        Some(Direction::Left) => 270.0,
+        Some(Direction::Right) => 90.0,
+        None => 0.0,
+    };
+    Quat::from_rotation_z(rot_degrees.to_radians())
+}
+
+fn spawn_junction_arm(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    segment: Entity,
+    tile: Tile,
+    branch_tile: Tile,
+) {
+    commands.entity(segment).with_child((
+        JunctionArm(tile.get_adjacent_tile_direction(&branch_tile)),
+        Sprite {
+            custom_size: Some(Vec2::splat(TILE_SIZE)),
+            ..Sprite::from_image(asset_server.load("images/chain-link-single-to-straight.png"))
+        },
+        Transform::from_xyz(0.0, 0.0, -0.01),
+    ));
+}
+
+// arms point the way their branch goes whichever way the segment under them
+// faces, and take on its colour so they rust and flash along with it
+fn update_junction_arms(
+    mut q_arms: Query<(
+        &JunctionArm,
+        &ChildOf,
+        &mut Sprite,
+        &mut Transform,
+        &mut Visibility,
+    )>,
+    q_segments: Query<(&Sprite, &Transform, Has<RustedThrough>), Without<JunctionArm>>,
+) {
+    for (arm, child_of, mut sprite, mut transform, mut visibility) in q_arms.iter_mut() {
+        let Ok((segment_sprite, segment_transform, rusted)) = q_segments.get(child_of.parent())
+        else {
+            continue;
+        };
+        sprite.color = segment_sprite.color;
+        transform.rotation = segment_transform.rotation.inverse() * stub_rotation(arm.0.as_ref());
+        *visibility = if rusted {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+    }
+}
+
+// the image and rotation for the last link of a chain, pointing back along it
+fn end_link(tile: Tile, prev_tile: Option<Tile>) -> (&'static str, Quat) {
+    match prev_tile {
+        Some(prev_tile) => (
+            "images/chain-link-single-to-straight.png",
+            stub_rotation(tile.get_adjacent_tile_direction(&prev_tile).as_ref()),
+        ),
+        None => ("images/chain-link-single.png", Quat::IDENTITY),
+    }
+}
+
 fn spawn_chain_segment(
     e_chain: Entity,
     commands: &mut Commands,
     prev_tile: Option<Tile>,
     tile: Tile,
-    asset_server: &ResMut<AssetServer>,
+    next_tile: Option<Tile>,
+    material: ChainMaterial,
+    asset_server: &AssetServer,
 ) {
-    let (sprite, rot_degrees) = if let Some(prev_tile) = prev_tile {
-        let direction = tile.get_adjacent_tile_direction(&prev_tile);
-        let sprite =
-            Sprite::from_image(asset_server.load("images/chain-link-single-to-straight.png"));
-        let rot_degrees = match direction {
-            Some(Direction::Up) => 180.0,
-            Some(Direction::Down) => 0.0,
-            Some(Direction::Left) => 270.0,
-            Some(Direction::Right) => 90.0,
-            None => 0.0,
-        };
-        (sprite, rot_degrees)
-    } else {
-        (
-            Sprite::from_image(asset_server.load("images/chain-link-single.png")),
-            0.0,
-        )
-    };
-    let rot = Quat::from_rotation_z((rot_degrees as f32).to_radians());
+    let (image, rot) = next_tile
+        .and_then(|next_tile| link_toward(tile, prev_tile, next_tile))
+        .unwrap_or_else(|| end_link(tile, prev_tile));
+    let sprite = Sprite::from_image(asset_server.load(image));
     commands.entity(e_chain).with_children(|parent| {
         parent.spawn((
             tile,
             Sprite {
                 custom_size: Some(Vec2::splat(TILE_SIZE)),
+                color: material.tint(),
                 ..sprite
             },
             ChainSegment { prev_tile, tile },
+            material,
+            Oxidation::default(),
             Obstacle { tile },
             tile.grid_coord_to_transform(3.0).with_rotation(rot),
         ));
     });
 }
 
-fn update_chain_segment(
-    chain_segment: &mut ChainSegment,
+// the image and rotation for a link with another one after it, or None if the
+// next tile isn't next to it
+fn link_toward(
+    tile: Tile,
+    prev_tile: Option<Tile>,
     next_tile: Tile,
-    sprite: &mut Sprite,
-    transform: &mut Transform,
-    asset_server: &ResMut<AssetServer>,
-) {
-    let direction_to_next = chain_segment.tile.get_adjacent_tile_direction(&next_tile);
-    let direction_to_prev = if chain_segment.prev_tile.is_none() {
-        None
-    } else {
-        let prev_tile = chain_segment.prev_tile.unwrap();
-        chain_segment.tile.get_adjacent_tile_direction(&prev_tile)
-    };
+) -> Option<(&'static str, Quat)> {
+    let direction_to_next = tile.get_adjacent_tile_direction(&next_tile);
+    let direction_to_prev =
+        prev_tile.and_then(|prev_tile| tile.get_adjacent_tile_direction(&prev_tile));
 
     // if direction_to_prev is none, we are the first in the chain
     if direction_to_prev.is_none() {
-        // we still need to update the transform, based on direction_to_next
+        // it still needs to point toward direction_to_next
         let rot_degrees = match direction_to_next {
             Some(Direction::Up) => 180.0,
             Some(Direction::Down) => 0.0,
@@ -123,18 +325,14 @@ fn update_chain_segment(
             None => 0.0,
         };
         let rot = Quat::from_rotation_z((rot_degrees as f32).to_radians());
-        *transform = transform.with_rotation(rot);
-        sprite.image = asset_server.load("images/chain-link-single-to-straight.png");
-        return;
+        return Some(("images/chain-link-single-to-straight.png", rot));
     }
 
     // we should only be here if both direction_to_next and direction_to_prev are some
-    if direction_to_next.is_none() || direction_to_prev.is_none() {
-        return;
-    }
-
-    let direction_to_next = direction_to_next.unwrap();
-    let direction_to_prev = direction_to_prev.unwrap();
+    let (Some(direction_to_next), Some(direction_to_prev)) = (direction_to_next, direction_to_prev)
+    else {
+        return None;
+    };
 
     // if direction_to_next is the opposite of direction_to_prev, we are on a straight
     if direction_to_next == direction_to_prev.opposite() {
@@ -146,9 +344,7 @@ fn update_chain_segment(
             Direction::Right => 90.0,
         };
         let rot = Quat::from_rotation_z((rot_degrees as f32).to_radians());
-        *transform = transform.with_rotation(rot);
-        sprite.image = asset_server.load("images/chain-link-link-to-straight.png");
-        return;
+        return Some(("images/chain-link-link-to-straight.png", rot));
     }
 
     // if we are here, we are on a corner
@@ -184,18 +380,66 @@ fn update_chain_segment(
     };
 
     let rot = Quat::from_rotation_z((rot_degrees as f32).to_radians());
-    *transform = transform.with_rotation(rot);
-    sprite.image = asset_server.load("images/chain-link-link-to-corner.png");
+    Some(("images/chain-link-link-to-corner.png", rot))
+}
+
+// lay a whole chain down at once, link by link along `tiles`
+pub fn spawn_chain(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    tiles: &[Tile],
+    material: ChainMaterial,
+) -> Entity {
+    let e_chain = commands
+        .spawn((
+            Chain {
+                length: tiles.len() as u32,
+                material,
+            },
+            Transform::from_xyz(0.0, 0.0, 0.0),
+            Visibility::Visible,
+        ))
+        .id();
+    for (i, tile) in tiles.iter().enumerate() {
+        spawn_chain_segment(
+            e_chain,
+            commands,
+            i.checked_sub(1).map(|prev| tiles[prev]),
+            *tile,
+            tiles.get(i + 1).copied(),
+            material,
+            asset_server,
+        );
+    }
+    e_chain
+}
+
+fn update_chain_segment(
+    chain_segment: &mut ChainSegment,
+    next_tile: Tile,
+    sprite: &mut Sprite,
+    transform: &mut Transform,
+    asset_server: &AssetServer,
+) {
+    if let Some((image, rot)) = link_toward(chain_segment.tile, chain_segment.prev_tile, next_tile)
+    {
+        *transform = transform.with_rotation(rot);
+        sprite.image = asset_server.load(image);
+    }
 }
 
 pub fn spawn_chain_button<T: Component + Default>(
     commands: &mut Commands,
     e_parent: Entity,
-    stock: u32,
-    length: u32,
+    entry: &InventoryEntry,
     asset_server: &ResMut<AssetServer>,
     pos: Vec2,
 ) {
+    let InventoryEntry {
+        stock,
+        length,
+        material,
+    } = *entry;
     let font = asset_server.load("fonts/FiraSans-Bold.ttf");
     let text_font = TextFont {
         font: font.clone(),
@@ -209,7 +453,11 @@ pub fn spawn_chain_button<T: Component + Default>(
             .spawn((
                 t,
                 Sprite::from_image(asset_server.load("images/chain.png")),
-                ChainButton { stock, length },
+                ChainButton {
+                    stock,
+                    length,
+                    material,
+                },
                 Transform::from_xyz(pos.x, pos.y, 0.0),
             ))
             .with_children(|parent| {
@@ -228,58 +476,121 @@ pub fn spawn_chain_button<T: Component + Default>(
                     Transform::from_xyz(-30.0, -30.0, 0.5),
                     TextColor(Color::linear_rgb(1.0, 0.0, 0.0)),
                 ));
+
+                parent.spawn((
+                    Text2d::new(material.name()),
+                    TextFont {
+                        font_size: 24.0,
+                        ..text_font.clone()
+                    },
+                    TextShadow::default(),
+                    Transform::from_xyz(0.0, -55.0, 0.5),
+                    TextColor(material.tint()),
+                ));
             });
     });
 }
 
-fn mouse_down_on_chain_button_in_inventory(
-    mut commands: Commands,
+// the inventory's buttons from left to right, which is how chains are numbered
+// when they're picked without the mouse
+fn inventory_order<'a, D>(
+    buttons: impl Iterator<Item = (Entity, &'a Transform, D)>,
+) -> Vec<(Entity, D)> {
+    let mut buttons = buttons.collect::<Vec<_>>();
+    buttons.sort_by(|(_, a, _), (_, b, _)| a.translation.x.total_cmp(&b.translation.x));
+    buttons
+        .into_iter()
+        .map(|(entity, _, data)| (entity, data))
+        .collect()
+}
+
+// clicking a chain button picks it, and hovering one lights it up
+fn click_chain_buttons(
     mouse_pos: Res<MousePos>,
     mouse_button: Res<ButtonInput<MouseButton>>,
-    mut q_unselected_chain: Query<
-        (Entity, &ChainButton, &mut Sprite, &GlobalTransform),
+    touch_screen: Res<TouchScreen>,
+    mut q_buttons: Query<
+        (Entity, &Transform, &GlobalTransform, &mut Sprite),
         (With<MainInventoryChainButton>, Without<SelectedChain>),
     >,
-    mut q_selected_chain: Query<
-        (
-            Entity,
-            &SelectedChain,
-            &mut Sprite,
-            &GlobalTransform,
-            &ChainButton,
-        ),
+    q_all_buttons: Query<(Entity, &Transform), With<MainInventoryChainButton>>,
+    mut evw_action: EventWriter<InputAction>,
+) {
+    for (entity, _, transform, mut sprite) in q_buttons.iter_mut() {
+        if !mouse_pos.is_in(
+            transform.translation().truncate(),
+            touch_screen.hit_size(Vec2::splat(CHAIN_BUTTON_SIZE)),
+        ) {
+            sprite.color = Color::linear_rgba(1.0, 1.0, 1.0, 1.0);
+            continue;
+        }
+
+        sprite.color = Color::linear_rgba(0.0, 0.0, 1.0, 1.0);
+        if mouse_button.just_pressed(MouseButton::Left) {
+            let order = inventory_order(q_all_buttons.iter().map(|(e, t)| (e, t, ())));
+            if let Some(n) = order.iter().position(|(button, _)| *button == entity) {
+                evw_action.write(InputAction::SelectChain(n));
+            }
+        }
+    }
+}
+
+fn select_chain(
+    mut commands: Commands,
+    mut evr_action: EventReader<InputAction>,
+    mut q_buttons: Query<
+        (Entity, &Transform, &mut Sprite, Has<SelectedChain>),
         With<MainInventoryChainButton>,
     >,
     mut evw_sfx: EventWriter<PlayClickSFX>,
 ) {
-    let mut new_chain_selected = false;
+    for action in evr_action.read() {
+        let InputAction::SelectChain(n) = *action else {
+            continue;
+        };
+        let order = inventory_order(
+            q_buttons
+                .iter()
+                .map(|(entity, transform, _, selected)| (entity, transform, selected)),
+        );
+        let Some((picked, already_selected)) = order.get(n).copied() else {
+            continue;
+        };
+        if already_selected {
+            continue;
+        }
 
-    for (entity, _, mut sprite, transform) in q_unselected_chain.iter_mut() {
-        if mouse_pos.is_in(
-            transform.translation().truncate(),
-            Vec2::splat(CHAIN_BUTTON_SIZE),
-        ) {
-            if mouse_button.just_pressed(MouseButton::Left) {
+        for (entity, _, mut sprite, selected) in q_buttons.iter_mut() {
+            if entity == picked {
                 sprite.color = Color::linear_rgba(0.0, 1.0, 0.0, 1.0);
                 commands.entity(entity).insert(SelectedChain);
-                evw_sfx.write(PlayClickSFX);
-                new_chain_selected = true;
-                break;
-            } else {
-                sprite.color = Color::linear_rgba(0.0, 0.0, 1.0, 1.0);
+            } else if selected {
+                sprite.color = Color::linear_rgba(1.0, 1.0, 1.0, 1.0);
+                commands.entity(entity).remove::<SelectedChain>();
             }
-        } else {
-            sprite.color = Color::linear_rgba(1.0, 1.0, 1.0, 1.0);
         }
+        evw_sfx.write(PlayClickSFX);
     }
+}
 
-    if !new_chain_selected {
-        return;
-    }
-
-    for (entity, _, mut sprite, _, _) in q_selected_chain.iter_mut() {
-        sprite.color = Color::linear_rgba(1.0, 1.0, 1.0, 1.0);
-        commands.entity(entity).remove::<SelectedChain>();
+fn spawn_inventory(
+    commands: &mut Commands,
+    e_main_inventory: Entity,
+    inventory: &[InventoryEntry],
+    asset_server: &ResMut<AssetServer>,
+) {
+    // lay the buttons out in a row centered under the hold, squeezing them up
+    // when there are a lot of them
+    let spacing = INVENTORY_SPACING.min(INVENTORY_WIDTH / inventory.len() as f32);
+    let first_x = -(inventory.len() as f32 - 1.0) * spacing / 2.0;
+    for (i, entry) in inventory.iter().enumerate() {
+        spawn_chain_button::<MainInventoryChainButton>(
+            commands,
+            e_main_inventory,
+            entry,
+            asset_server,
+            Vec2::new(first_x + i as f32 * spacing, INVENTORY_Y),
+        );
     }
 }
 
@@ -291,41 +602,257 @@ fn setup(mut commands: Commands, asset_server: ResMut<AssetServer>) {
             Visibility::Visible,
         ))
         .id();
-    spawn_chain_button::<MainInventoryChainButton>(
-        &mut commands,
-        e_main_inventory,
-        1,
-        9,
-        &asset_server,
-        Vec2::new(-300.0, -400.0),
-    );
-    spawn_chain_button::<MainInventoryChainButton>(
+    spawn_inventory(
         &mut commands,
         e_main_inventory,
-        2,
-        4,
+        &ENDLESS_INVENTORY,
         &asset_server,
-        Vec2::new(0.0, -400.0),
-    );
-    spawn_chain_button::<MainInventoryChainButton>(
-        &mut commands,
-        e_main_inventory,
-        3,
-        2,
-        &asset_server,
-        Vec2::new(300.0, -400.0),
     );
 }
 
+fn on_inventory_replaced(
+    mut commands: Commands,
+    mut evr_inventory_replaced: EventReader<InventoryReplaced>,
+    asset_server: ResMut<AssetServer>,
+    q_main_inventory: Query<Entity, With<MainInventory>>,
+    q_inventory_buttons: Query<Entity, With<MainInventoryChainButton>>,
+) {
+    for event in evr_inventory_replaced.read() {
+        for entity in q_inventory_buttons.iter() {
+            commands.entity(entity).despawn();
+        }
+
+        let e_main_inventory = q_main_inventory.single().unwrap();
+        spawn_inventory(
+            &mut commands,
+            e_main_inventory,
+            &event.entries,
+            &asset_server,
+        );
+    }
+}
+
+// put a chain back in the inventory, on the button for its kind if there is one
+fn return_to_inventory<'a>(
+    entry: InventoryEntry,
+    buttons: impl Iterator<Item = (Mut<'a, ChainButton>, &'a Children, &'a Transform)>,
+    q_stock_text: &mut Query<&mut Text2d, With<ChainButtonStock>>,
+    evw_inventory_replaced: &mut EventWriter<InventoryReplaced>,
+) {
+    let mut buttons = buttons.collect::<Vec<_>>();
+    match buttons
+        .iter_mut()
+        .find(|(button, _, _)| button.entry().is_same_kind(&entry))
+    {
+        Some((button, children, _)) => {
+            button.stock += entry.stock;
+            for child in children.iter() {
+                if let Ok(mut text) = q_stock_text.get_mut(child) {
+                    text.0 = format!("{}", button.stock);
+                }
+            }
+        }
+        None => {
+            // rebuild the inventory in its current order, with the new kind on the end
+            buttons.sort_by(|(_, _, a), (_, _, b)| a.translation.x.total_cmp(&b.translation.x));
+            let mut entries = buttons
+                .iter()
+                .map(|(button, _, _)| button.entry())
+                .collect::<Vec<_>>();
+            add_to_inventory(&mut entries, entry);
+            evw_inventory_replaced.write(InventoryReplaced { entries });
+        }
+    }
+}
+
+// take a single link of `material` out of the inventory, cut off the shortest
+// chain of it there is, with the rest of that chain going back in. False if
+// there's no chain of it left
+pub fn take_link(
+    material: ChainMaterial,
+    q_buttons: &mut Query<
+        (&mut ChainButton, &Children, &Transform),
+        With<MainInventoryChainButton>,
+    >,
+    q_stock_text: &mut Query<&mut Text2d, With<ChainButtonStock>>,
+    evw_inventory_replaced: &mut EventWriter<InventoryReplaced>,
+) -> bool {
+    let Some((mut button, children, _)) = q_buttons
+        .iter_mut()
+        .filter(|(button, _, _)| button.material == material && button.stock > 0)
+        .min_by_key(|(button, _, _)| button.length)
+    else {
+        return false;
+    };
+
+    button.stock -= 1;
+    for child in children.iter() {
+        if let Ok(mut text) = q_stock_text.get_mut(child) {
+            text.0 = format!("{}", button.stock);
+        }
+    }
+
+    if button.length > 1 {
+        let leftover = InventoryEntry {
+            stock: 1,
+            length: button.length - 1,
+            material,
+        };
+        return_to_inventory(
+            leftover,
+            q_buttons.iter_mut(),
+            q_stock_text,
+            evw_inventory_replaced,
+        );
+    }
+    true
+}
+
+// take a chain back off the hold and put what's left of it back in the inventory.
+// Links that were knocked out or rusted through are lost
+fn on_remove_chain(
+    mut commands: Commands,
+    mut evr_remove_chain: EventReader<RemoveChain>,
+    q_chains: Query<(&Chain, Option<&BranchOf>, Option<&Children>)>,
+    q_segments: Query<(&ChainSegment, Option<&Children>)>,
+    q_arms: Query<&JunctionArm>,
+    q_rusted: Query<(), With<RustedThrough>>,
+    mut q_buttons: Query<(&mut ChainButton, &Children, &Transform), With<MainInventoryChainButton>>,
+    mut q_stock_text: Query<&mut Text2d, With<ChainButtonStock>>,
+    mut evw_inventory_replaced: EventWriter<InventoryReplaced>,
+) {
+    for event in evr_remove_chain.read() {
+        let Ok((chain, branch_of, links)) = q_chains.get(event.chain) else {
+            continue;
+        };
+        commands.entity(event.chain).despawn();
+
+        // a branch takes the arm it came out of its trunk by with it
+        let first_link = links
+            .and_then(|links| links.iter().find_map(|link| q_segments.get(link).ok()))
+            .map(|(segment, _)| segment.tile);
+        let trunk_links = branch_of
+            .and_then(|branch_of| q_chains.get(branch_of.0).ok())
+            .and_then(|(_, _, links)| links);
+        if let (Some(first_link), Some(trunk_links)) = (first_link, trunk_links) {
+            for (segment, arms) in trunk_links
+                .iter()
+                .filter_map(|link| q_segments.get(link).ok())
+            {
+                let direction = segment.tile.get_adjacent_tile_direction(&first_link);
+                for arm in arms.iter().flat_map(|arms| arms.iter()) {
+                    if direction.is_some()
+                        && q_arms
+                            .get(arm)
+                            .is_ok_and(|junction| junction.0 == direction)
+                    {
+                        commands.entity(arm).despawn();
+                    }
+                }
+            }
+        }
+
+        let intact = links
+            .iter()
+            .flat_map(|links| links.iter())
+            .filter(|link| q_segments.contains(*link) && !q_rusted.contains(*link))
+            .count() as u32;
+        if intact == 0 {
+            continue;
+        }
+        return_to_inventory(
+            InventoryEntry {
+                stock: 1,
+                length: intact,
+                material: chain.material,
+            },
+            q_buttons.iter_mut(),
+            &mut q_stock_text,
+            &mut evw_inventory_replaced,
+        );
+    }
+}
+
+// what's left where a link was snapped, on the chain so it goes when the chain does
+#[derive(Component)]
+pub struct BrokenLink;
+
+// knock a single link out of `chain`, leaving a gap that air and pirates can get through
+pub fn break_chain_link(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    chain: Entity,
+    segment: Entity,
+    tile: Tile,
+) {
+    commands.entity(segment).despawn();
+    commands.entity(chain).with_child((
+        BrokenLink,
+        Sprite {
+            custom_size: Some(Vec2::splat(TILE_SIZE)),
+            ..Sprite::from_image(asset_server.load("images/broken-chain.png"))
+        },
+        tile.grid_coord_to_transform(2.0),
+    ));
+}
+
+fn on_wave_reset(
+    mut commands: Commands,
+    mut evr_wave_reset: EventReader<WaveReset>,
+    q_broken_links: Query<Entity, With<BrokenLink>>,
+) {
+    if evr_wave_reset.is_empty() {
+        return;
+    }
+
+    evr_wave_reset.clear();
+
+    for entity in q_broken_links.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+// clear everything the player (or a level) built in the hold
+fn on_run_reset(
+    mut commands: Commands,
+    mut evr_run_reset: EventReader<RunReset>,
+    q_chains: Query<Entity, With<Chain>>,
+    q_dragging_chain: Query<Entity, With<DraggingChain>>,
+    q_crates: Query<Entity, With<Crate>>,
+) {
+    if evr_run_reset.is_empty() {
+        return;
+    }
+
+    evr_run_reset.clear();
+
+    for entity in q_chains
+        .iter()
+        .chain(q_dragging_chain.iter())
+        .chain(q_crates.iter())
+    {
+        commands.entity(entity).despawn();
+    }
+}
+
 fn begin_chain(
     mut commands: Commands,
-    mut tile_clicked_events: EventReader<TileMouseDown>,
+    mut evr_action: EventReader<InputAction>,
+    cursor: Res<GridCursor>,
     mut evw_chain_placed: EventWriter<ChainPlaced>,
     asset_server: ResMut<AssetServer>,
     q_dragging_chain: Query<&DraggingChain>,
     q_selected_chain: Query<(&SelectedChain, &ChainButton), Without<DraggingChain>>,
-    q_chain_segments: Query<&ChainSegment>,
+    q_chain_segments: Query<(Entity, &ChainSegment, Option<&ChildOf>, Has<RustedThrough>)>,
+    q_loose_gold: Query<&Tile, With<LooseGold>>,
 ) {
+    if !evr_action
+        .read()
+        .any(|action| *action == InputAction::BeginChain)
+    {
+        return;
+    }
+
     // if there is a chain already being dragged, do nothing
     if q_dragging_chain.single().is_ok() {
         return;
@@ -343,46 +870,74 @@ fn begin_chain(
         return;
     }
 
-    // if there is a tile clicked, create a new chain
-    for event in tile_clicked_events.read() {
-        // if there is a chain segment at this position, do nothing
-        if q_chain_segments
-            .iter()
-            .any(|segment| segment.tile == event.0.tile)
-        {
-            continue;
-        }
+    // start a new chain on the cursor
+    let tile = cursor.tile;
 
-        let e_chain = commands
-            .spawn((
-                Chain {
-                    length: chain_in_inventory.length,
-                },
-                Transform::from_xyz(0.0, 0.0, 0.0),
-                Visibility::Visible,
-            ))
-            .id();
+    // if there is a chain segment at this position, draw a branch out of the side of it
+    let branch_from = q_chain_segments
+        .iter()
+        .find(|(_, segment, _, _)| segment.tile == tile);
+    let branch_of = match branch_from {
+        Some((_, _, Some(child_of), false)) => Some(child_of.parent()),
+        Some(_) => return,
+        None => None,
+    };
 
-        commands.spawn((
-            DraggingChain {
-                remaining_length: chain_in_inventory.length - 1,
-                e_chain,
-            },
-            // reference to current segment tile
-            ChainSegment {
-                prev_tile: None,
-                tile: event.0.tile,
-            },
-        ));
+    // clicking loose gold picks it up instead
+    if q_loose_gold.iter().any(|gold| *gold == tile) {
+        return;
+    }
 
-        evw_chain_placed.write(ChainPlaced);
-        spawn_chain_segment(e_chain, &mut commands, None, event.0.tile, &asset_server);
+    let mut e_chain = commands.spawn((
+        Chain {
+            length: chain_in_inventory.length,
+            material: chain_in_inventory.material,
+        },
+        Transform::from_xyz(0.0, 0.0, 0.0),
+        Visibility::Visible,
+    ));
+    if let Some(branch_of) = branch_of {
+        e_chain.insert(BranchOf(branch_of));
+    }
+    let e_chain = e_chain.id();
+
+    // a branch's first link goes next to the segment it comes out of, once it's dragged there
+    let remaining_length = if branch_of.is_some() {
+        chain_in_inventory.length
+    } else {
+        chain_in_inventory.length - 1
+    };
+    commands.spawn((
+        DraggingChain {
+            remaining_length,
+            e_chain,
+            branch_from: branch_from.map(|(segment, _, _, _)| segment),
+        },
+        // reference to current segment tile
+        ChainSegment {
+            prev_tile: None,
+            tile,
+        },
+    ));
+    if branch_of.is_some() {
+        return;
     }
+
+    evw_chain_placed.write(ChainPlaced);
+    spawn_chain_segment(
+        e_chain,
+        &mut commands,
+        None,
+        tile,
+        None,
+        chain_in_inventory.material,
+        &asset_server,
+    );
 }
 
 fn drag_chain(
     mut commands: Commands,
-    mut tile_mouse_move_events: EventReader<TileMouseMove>,
+    mut evr_action: EventReader<InputAction>,
     asset_server: ResMut<AssetServer>,
     mut q_dragging_chain: Query<(&mut DraggingChain, &mut ChainSegment)>,
     mut evw_chain_placed: EventWriter<ChainPlaced>,
@@ -390,8 +945,16 @@ fn drag_chain(
         (&mut ChainSegment, &mut Sprite, &mut Transform),
         Without<DraggingChain>,
     >,
-    q_chain: Query<(Entity, &Children), With<Chain>>,
+    q_chain: Query<(Entity, &Chain, Option<&Children>)>,
 ) {
+    let extends = evr_action
+        .read()
+        .filter_map(|action| match action {
+            InputAction::Extend(tile) => Some(*tile),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
     // get the dragging chain
     if q_dragging_chain.single().is_err() {
         return;
@@ -399,31 +962,71 @@ fn drag_chain(
 
     let (mut dragging_chain, mut current_chain_segment) = q_dragging_chain.single_mut().unwrap();
 
-    // if no remaining length, do nothing
-    if dragging_chain.remaining_length == 0 {
-        return;
-    }
-
-    // get the tile mouse move events
-    for event in tile_mouse_move_events.read() {
+    // follow the cursor from tile to tile
+    for tile in extends {
         // make sure the tile is in an adjacent tile to the current chain segment
-        if !current_chain_segment.tile.is_adjacent(&event.0.tile) {
+        if !current_chain_segment.tile.is_adjacent(&tile) {
+            continue;
+        }
+
+        let prev_chain_segs = q_chain
+            .iter()
+            .find(|(entity, _, _)| entity == &dragging_chain.e_chain);
+
+        // dragging back onto the link before takes the newest one off again
+        if Some(tile) == current_chain_segment.prev_tile {
+            let Some((_, _, Some(children))) = prev_chain_segs else {
+                continue;
+            };
+            let Some(last) = children.iter().find(|child| {
+                q_chain_segments
+                    .get(*child)
+                    .is_ok_and(|(segment, _, _)| segment.tile == current_chain_segment.tile)
+            }) else {
+                continue;
+            };
+            commands.entity(last).despawn();
+
+            // the link before becomes the end of the chain, unless it's the segment a
+            // branch is coming out of, which belongs to another chain
+            let mut prev_tile = None;
+            for child in children.iter() {
+                let Ok((segment, mut sprite, mut transform)) = q_chain_segments.get_mut(child)
+                else {
+                    continue;
+                };
+                if segment.tile != tile {
+                    continue;
+                }
+                let (image, rotation) = end_link(segment.tile, segment.prev_tile);
+                sprite.image = asset_server.load(image);
+                transform.rotation = rotation;
+                prev_tile = segment.prev_tile;
+            }
+
+            dragging_chain.remaining_length += 1;
+            current_chain_segment.tile = tile;
+            current_chain_segment.prev_tile = prev_tile;
+            continue;
+        }
+
+        // if no remaining length, do nothing
+        if dragging_chain.remaining_length == 0 {
             continue;
         }
 
         // make sure there isn't already a chain segment at this position
         if q_chain_segments
             .iter()
-            .any(|segment| segment.0.tile == event.0.tile)
+            .any(|segment| segment.0.tile == tile)
         {
             continue;
         }
 
-        let prev_chain_segs = q_chain
-            .iter()
-            .find(|(entity, _)| entity == &dragging_chain.e_chain);
+        let material =
+            prev_chain_segs.map_or(ChainMaterial::default(), |(_, chain, _)| chain.material);
 
-        if let Some((_, children)) = prev_chain_segs {
+        if let Some((_, _, Some(children))) = prev_chain_segs {
             for child in children.iter() {
                 let (mut segment, mut sprite, mut transform) =
                     q_chain_segments.get_mut(child).unwrap();
@@ -432,7 +1035,7 @@ fn drag_chain(
                 }
                 update_chain_segment(
                     &mut segment,
-                    event.0.tile,
+                    tile,
                     &mut sprite,
                     &mut transform,
                     &asset_server,
@@ -440,73 +1043,121 @@ fn drag_chain(
             }
         }
 
-        // update_chain_segment(
-        //     current_chain_segment.tile,
-        //     event.0.tile,
-        //     &mut sprite,
-        //     &mut transform,
-        //     &asset_server,
-        // );
-
         spawn_chain_segment(
             dragging_chain.e_chain,
             &mut commands,
             Some(current_chain_segment.tile),
-            event.0.tile,
+            tile,
+            None,
+            material,
             &asset_server,
         );
         evw_chain_placed.write(ChainPlaced);
 
         dragging_chain.remaining_length -= 1;
         current_chain_segment.prev_tile = Some(current_chain_segment.tile);
-        current_chain_segment.tile = event.0.tile;
+        current_chain_segment.tile = tile;
     }
 }
 
 fn end_chain(
     mut commands: Commands,
-    mouse_button: Res<ButtonInput<MouseButton>>,
+    mut evr_action: EventReader<InputAction>,
     q_dragging_chain: Query<(Entity, &DraggingChain)>,
-    mut q_selected_chain: Query<(&mut ChainButton, &Children), With<SelectedChain>>,
+    mut q_selected_chain: Query<(&mut ChainButton, &Children, &Transform), With<SelectedChain>>,
+    mut q_other_buttons: Query<
+        (&mut ChainButton, &Children, &Transform),
+        (With<MainInventoryChainButton>, Without<SelectedChain>),
+    >,
     mut q_stock_text: Query<&mut Text2d, With<ChainButtonStock>>,
     mut evw_chain_finished: EventWriter<ChainFinished>,
+    mut evw_inventory_replaced: EventWriter<InventoryReplaced>,
     q_obstacles: Query<&Obstacle>,
+    boarding_points: Res<BoardingPoints>,
+    asset_server: Res<AssetServer>,
+    mut q_chains: Query<(&mut Chain, Option<&Children>)>,
+    q_segments: Query<&ChainSegment>,
 ) {
-    if !mouse_button.just_released(MouseButton::Left) {
+    let mut commit = None;
+    let mut cancel = false;
+    for action in evr_action.read() {
+        match action {
+            InputAction::Commit { keep_short } => commit = Some(*keep_short),
+            InputAction::Cancel => cancel = true,
+            _ => {}
+        }
+    }
+    if commit.is_none() && !cancel {
         return;
     }
+    let keep_short = commit.unwrap_or_default();
 
     // remove any dragging chains (should only be one, but lets be safe)
     for (entity, dragging_chain) in q_dragging_chain.iter() {
         commands.entity(entity).despawn();
 
-        // if we didn't finish the chain, remove it
-        if dragging_chain.remaining_length > 0 {
+        let Ok((mut chain, links)) = q_chains.get_mut(dragging_chain.e_chain) else {
+            continue;
+        };
+
+        // if we didn't finish the chain, remove it, unless it's to be kept short. A
+        // cancelled chain is removed however far it got
+        let placed = chain.length - dragging_chain.remaining_length;
+        let unfinished = dragging_chain.remaining_length > 0 && (!keep_short || placed == 0);
+        if cancel || unfinished {
             commands.entity(dragging_chain.e_chain).despawn();
             continue;
         }
 
-        // do a naive pathfind to make sure we didn't block the ship hold
+        // do a naive pathfind to make sure every boarding point can still reach the hold
         let pathing_grid = get_pathing_grid(q_obstacles);
 
-        let start = BOAT_POINT;
-        let end = HOLD_POINT;
-
-        let path = pathing_grid.get_path_single_goal(start, end, false);
-
-        if path.is_none() {
+        if !hold_reachable(&pathing_grid, &boarding_points.points) {
             commands.entity(dragging_chain.e_chain).despawn();
             continue;
         }
 
         // We placed a chain, update the stock
-        let (mut chain_in_inventory, children) = q_selected_chain.single_mut().unwrap();
+        let (mut chain_in_inventory, children, _) = q_selected_chain.single_mut().unwrap();
         chain_in_inventory.stock -= 1;
         for child in children.iter() {
             if let Ok(mut text) = q_stock_text.get_mut(child) {
                 text.0 = format!("{}", chain_in_inventory.stock);
             }
         }
+
+        // what's left of a chain that was cut short goes back in the inventory as a shorter one
+        if dragging_chain.remaining_length > 0 {
+            let leftover = InventoryEntry {
+                stock: 1,
+                length: dragging_chain.remaining_length,
+                material: chain.material,
+            };
+            chain.length = placed;
+            return_to_inventory(
+                leftover,
+                q_selected_chain
+                    .iter_mut()
+                    .chain(q_other_buttons.iter_mut()),
+                &mut q_stock_text,
+                &mut evw_inventory_replaced,
+            );
+        }
+
+        // a finished branch turns the segment it came out of into a junction
+        if let Some(branch_from) = dragging_chain.branch_from {
+            let first_link =
+                links.and_then(|links| links.iter().find_map(|link| q_segments.get(link).ok()));
+            if let (Ok(segment), Some(first_link)) = (q_segments.get(branch_from), first_link) {
+                spawn_junction_arm(
+                    &mut commands,
+                    &asset_server,
+                    branch_from,
+                    segment.tile,
+                    first_link.tile,
+                );
+            }
+        }
         evw_chain_finished.write(ChainFinished);
     }
 }
@@ -537,12 +1188,25 @@ pub fn plugin(app: &mut App) {
     app.add_systems(
         Update,
         (
-            mouse_down_on_chain_button_in_inventory,
+            click_chain_buttons,
+            select_chain,
             begin_chain,
             drag_chain,
             end_chain,
         )
+            .chain()
+            .after(update_grid_cursor)
             .run_if(in_state(GameState::Building)),
     );
-    app.add_systems(Update, handle_crate_spawned);
+    app.add_systems(Update, update_junction_arms);
+    app.add_systems(
+        Update,
+        (
+            handle_crate_spawned,
+            on_inventory_replaced,
+            on_run_reset,
+            on_wave_reset,
+            on_remove_chain.run_if(in_state(GameState::Building)),
+        ),
+    );
 }
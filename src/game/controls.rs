@@ -1,13 +1,16 @@
 use bevy::prelude::*;
+use bevy::sprite::Anchor;
 
 use crate::game::{
     chain::DraggingChain,
-    events::{WaveComplete, WaveStarted},
+    events::{WaveComplete, WaveReset, WaveStarted},
     game_state::GameState,
-    mouse::MousePos,
+    input::{InputAction, update_grid_cursor},
+    ui::{ButtonClicked, HUD_MARGIN, Placed, button, hud, label},
 };
 
-pub const NEXT_WAVE_BUTTON_POS: Vec2 = Vec2::new(700.0, -400.0);
+// in from the bottom right corner of the screen
+pub const NEXT_WAVE_BUTTON_OFFSET: Vec2 = Vec2::splat(HUD_MARGIN);
 pub const NEXT_WAVE_BUTTON_SIZE: Vec2 = Vec2::new(280.0, 100.0);
 
 #[derive(Component)]
@@ -16,66 +19,81 @@ pub struct NextWaveButton;
 #[derive(Component)]
 pub struct NextWaveButtonText;
 
-fn setup(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    asset_server: ResMut<AssetServer>,
-) {
-    let rect = Rectangle::new(NEXT_WAVE_BUTTON_SIZE.x, NEXT_WAVE_BUTTON_SIZE.y);
-    let color = Color::linear_rgba(0.0, 0.0, 1.0, 1.0);
-    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
-    let text_font = TextFont {
-        font: font.clone(),
-        font_size: 30.0,
-        ..default()
-    };
-
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
     commands
-        .spawn((
-            NextWaveButton,
-            Transform::from_xyz(NEXT_WAVE_BUTTON_POS.x, NEXT_WAVE_BUTTON_POS.y, 5.0),
-            Mesh2d(meshes.add(rect)),
-            MeshMaterial2d(materials.add(color)),
-        ))
-        .with_child((
-            NextWaveButtonText,
-            Text2d::new("Next Wave"),
-            text_font.clone(),
-            TextColor(Color::linear_rgba(1.0, 1.0, 1.0, 1.0)),
-        ));
+        .spawn(hud(GameState::Building))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    NextWaveButton,
+                    button(NEXT_WAVE_BUTTON_SIZE),
+                    Placed {
+                        anchor: Anchor::BottomRight,
+                        offset: NEXT_WAVE_BUTTON_OFFSET,
+                    },
+                ))
+                .with_child((
+                    NextWaveButtonText,
+                    label(&asset_server, "Next Wave", 30.0, Color::WHITE),
+                ));
+        });
 }
 
 fn next_wave_button(
-    mut q_next_wave_button_text: Query<&mut Text2d, With<NextWaveButtonText>>,
-    mouse_pos: Res<MousePos>,
-    mouse_button: Res<ButtonInput<MouseButton>>,
+    mut evr_clicked: EventReader<ButtonClicked>,
+    q_next_wave_button: Query<(), With<NextWaveButton>>,
+    mut evw_action: EventWriter<InputAction>,
+) {
+    if evr_clicked
+        .read()
+        .any(|event| q_next_wave_button.contains(event.button))
+    {
+        evw_action.write(InputAction::StartWave);
+    }
+}
+
+fn start_wave(
+    mut evr_action: EventReader<InputAction>,
     mut game_state: ResMut<NextState<GameState>>,
     mut evw_wave_started: EventWriter<WaveStarted>,
     q_dragging_chain: Query<&DraggingChain>,
 ) {
-    // if we're dragging a chain, don't allow the next wave button to be pressed
-    if q_dragging_chain.iter().count() > 0 {
+    if !evr_action
+        .read()
+        .any(|action| *action == InputAction::StartWave)
+    {
         return;
     }
 
-    if mouse_button.just_pressed(MouseButton::Left)
-        && mouse_pos.is_in(NEXT_WAVE_BUTTON_POS, NEXT_WAVE_BUTTON_SIZE)
-    {
-        let mut text = q_next_wave_button_text.single_mut().unwrap();
-        text.0 = "Wave In Progress".to_string();
-        game_state.set(GameState::WaveInProgress);
-        evw_wave_started.write(WaveStarted);
+    // if we're dragging a chain, don't allow the next wave to be started
+    if q_dragging_chain.iter().count() > 0 {
+        return;
     }
+
+    game_state.set(GameState::WaveInProgress);
+    evw_wave_started.write(WaveStarted);
 }
 
-fn on_wave_complete(
+fn update_next_wave_button_text(
     mut evr_wave_complete: EventReader<WaveComplete>,
-    mut q_next_wave_button_text: Query<&mut Text2d, With<NextWaveButtonText>>,
+    mut evr_wave_reset: EventReader<WaveReset>,
+    mut evr_wave_started: EventReader<WaveStarted>,
+    mut q_next_wave_button_text: Query<&mut Text, With<NextWaveButtonText>>,
 ) {
-    for _ in evr_wave_complete.read() {
-        let mut text = q_next_wave_button_text.single_mut().unwrap();
+    if evr_wave_complete.is_empty() && evr_wave_reset.is_empty() && evr_wave_started.is_empty() {
+        return;
+    }
+
+    evr_wave_complete.clear();
+    evr_wave_reset.clear();
+
+    // a wave can be restarted in the same frame it was reset, so started wins
+    let mut text = q_next_wave_button_text.single_mut().unwrap();
+    if evr_wave_started.is_empty() {
         text.0 = "Next Wave".to_string();
+    } else {
+        evr_wave_started.clear();
+        text.0 = "Wave In Progress".to_string();
     }
 }
 
@@ -83,9 +101,11 @@ pub fn plugin(app: &mut App) {
     app.add_systems(Startup, setup);
     app.add_systems(
         Update,
-        (next_wave_button).run_if(in_state(GameState::Building)),
+        (next_wave_button, start_wave.after(update_grid_cursor))
+            .chain()
+            .run_if(in_state(GameState::Building)),
     );
 
     // This runs in all states, just to make sure the button is updated
-    app.add_systems(Update, on_wave_complete);
+    app.add_systems(Update, update_next_wave_button_text);
 }
@@ -0,0 +1,294 @@
+use bevy::prelude::*;
+
+use crate::game::chain::{ChainMaterial, ChainSegment};
+use crate::game::game_state::GameState;
+use crate::game::network::ChainNetwork;
+use crate::game::oxidation::{Oxidation, RustedThrough};
+use crate::game::oxygen::Oxygen;
+use crate::game::pirate::{OnGangplank, Pirate, pirate_movement_system, pirate_oxygen_system};
+use crate::game::status_effects::StatusEffects;
+use crate::game::tile::TILE_SIZE;
+
+// How chains take the air out of a pirate. The game, the wave simulation and the
+// drain overlay all go through these functions, so they always agree.
+//
+// Every segment drains fully up close and less the further away the pirate is,
+// until it's out of reach. A pirate walled in on more than one side loses extra,
+// so corridors and dead ends are worth building, but never more than the cap on
+// what all the segments can take together.
+
+// what a fresh iron segment takes each second from a pirate right next to it
+pub const CHAIN_OXYGEN_DRAIN: f32 = 10.0;
+
+// segments drain fully up to this many tiles away
+const FULL_DRAIN_DISTANCE: f32 = 1.0;
+// and nothing from this many tiles past their breathing range
+const FALLOFF_DISTANCE: f32 = 1.0;
+
+// the most all the segments together can take, in fresh iron segments' worth
+pub const DRAIN_CAP: f32 = 5.0;
+
+// extra drain from every segment for each other chain in its network
+const NETWORK_BONUS: f32 = 0.15;
+// up to this much in all
+const MAX_NETWORK_MULTIPLIER: f32 = 1.6;
+
+// a segment walls a pirate in on a side if it's this close along that side
+const ENCLOSURE_DISTANCE: f32 = 1.2;
+// and no further than this to either side of it
+const ENCLOSURE_SPREAD: f32 = 0.5;
+// extra drain for every side a pirate is walled in on, after the first
+const ENCLOSURE_BONUS: f32 = 0.25;
+
+#[derive(Clone, Copy, Debug)]
+pub struct DrainSource {
+    pub position: Vec2,
+    pub material: ChainMaterial,
+    pub oxidation: Oxidation,
+    // how many chains are in the network the segment's chain is part of
+    pub network_size: usize,
+}
+
+// how much of its full drain a segment has on a pirate this many tiles away
+pub fn falloff(distance: f32, material: ChainMaterial) -> f32 {
+    let reach = material.breathing_range() + FALLOFF_DISTANCE;
+    if distance <= FULL_DRAIN_DISTANCE {
+        1.0
+    } else if distance >= reach {
+        0.0
+    } else {
+        (reach - distance) / (reach - FULL_DRAIN_DISTANCE)
+    }
+}
+
+// what a single segment would take each second, on its own
+pub fn segment_drain(pirate_pos: Vec2, source: &DrainSource) -> f32 {
+    if source.oxidation.is_rusted_through() {
+        return 0.0;
+    }
+
+    let distance = pirate_pos.distance(source.position) / TILE_SIZE;
+    CHAIN_OXYGEN_DRAIN
+        * source.material.drain_multiplier()
+        * source.oxidation.drain_multiplier()
+        * network_multiplier(source.network_size)
+        * falloff(distance, source.material)
+}
+
+pub fn network_multiplier(network_size: usize) -> f32 {
+    (1.0 + NETWORK_BONUS * network_size.saturating_sub(1) as f32).min(MAX_NETWORK_MULTIPLIER)
+}
+
+// how many of the four sides have a segment right up against the pirate
+pub fn enclosed_sides(pirate_pos: Vec2, sources: &[DrainSource]) -> usize {
+    let sides = [Vec2::X, Vec2::NEG_X, Vec2::Y, Vec2::NEG_Y];
+    sides
+        .iter()
+        .filter(|side| {
+            sources.iter().any(|source| {
+                let offset = (source.position - pirate_pos) / TILE_SIZE;
+                let along = offset.dot(**side);
+                let across = offset.perp_dot(**side).abs();
+                !source.oxidation.is_rusted_through()
+                    && along > 0.0
+                    && along <= ENCLOSURE_DISTANCE
+                    && across < ENCLOSURE_SPREAD
+            })
+        })
+        .count()
+}
+
+pub fn enclosure_multiplier(sides: usize) -> f32 {
+    1.0 + ENCLOSURE_BONUS * sides.saturating_sub(1) as f32
+}
+
+// what each segment takes from the pirate each second, in the same order as
+// `sources`. They add up to the pirate's total drain
+pub fn drain_shares(pirate_pos: Vec2, sources: &[DrainSource]) -> Vec<f32> {
+    let shares = sources
+        .iter()
+        .map(|source| segment_drain(pirate_pos, source))
+        .collect::<Vec<_>>();
+    let uncapped: f32 = shares.iter().sum();
+    if uncapped <= 0.0 {
+        return shares;
+    }
+
+    let enclosed = uncapped * enclosure_multiplier(enclosed_sides(pirate_pos, sources));
+    let scale = enclosed.min(CHAIN_OXYGEN_DRAIN * DRAIN_CAP) / uncapped;
+    shares.into_iter().map(|share| share * scale).collect()
+}
+
+pub fn total_drain(pirate_pos: Vec2, sources: &[DrainSource]) -> f32 {
+    drain_shares(pirate_pos, sources).iter().sum()
+}
+
+fn chain_drain_system(
+    time: Res<Time>,
+    mut q_pirates: Query<(&Pirate, &mut Oxygen, &StatusEffects, &Transform), Without<OnGangplank>>,
+    mut q_chain: Query<
+        (
+            &Transform,
+            &ChainMaterial,
+            &mut Oxidation,
+            Option<&ChainNetwork>,
+        ),
+        (With<ChainSegment>, Without<RustedThrough>),
+    >,
+) {
+    let sources = q_chain
+        .iter()
+        .map(|(transform, material, oxidation, network)| DrainSource {
+            position: transform.translation.xy(),
+            material: *material,
+            oxidation: *oxidation,
+            network_size: network.map_or(1, |network| network.size),
+        })
+        .collect::<Vec<_>>();
+    if sources.is_empty() {
+        return;
+    }
+
+    // every segment rusts by what it drained this frame, once all the pirates have been drained
+    let mut absorbed = vec![0.0; sources.len()];
+    for (pirate, mut oxygen, effects, transform) in q_pirates.iter_mut() {
+        if pirate.is_marked_for_despawn() {
+            continue;
+        }
+
+        let multiplier = effects.modifiers().oxygen_drain * time.delta().as_secs_f32();
+        let shares = drain_shares(transform.translation.xy(), &sources);
+        for (absorbed, share) in absorbed.iter_mut().zip(shares.iter()) {
+            *absorbed += share * multiplier;
+        }
+        oxygen.0 -= shares.iter().sum::<f32>() * multiplier;
+    }
+
+    for ((_, material, mut oxidation, _), absorbed) in q_chain.iter_mut().zip(absorbed) {
+        if absorbed > 0.0 {
+            oxidation.absorb(absorbed, *material);
+        }
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(
+        FixedUpdate,
+        chain_drain_system
+            .after(pirate_movement_system)
+            .before(pirate_oxygen_system)
+            .run_if(in_state(GameState::WaveInProgress)),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MATERIALS: [ChainMaterial; 4] = [
+        ChainMaterial::Iron,
+        ChainMaterial::RustyIron,
+        ChainMaterial::Anchor,
+        ChainMaterial::Stainless,
+    ];
+
+    // a fresh segment on its own, this many tiles from the origin
+    fn source(tiles: Vec2, material: ChainMaterial) -> DrainSource {
+        DrainSource {
+            position: tiles * TILE_SIZE,
+            material,
+            oxidation: Oxidation::default(),
+            network_size: 1,
+        }
+    }
+
+    #[test]
+    fn falloff_is_full_up_close_and_gone_out_of_reach() {
+        for material in MATERIALS {
+            let reach = material.breathing_range() + FALLOFF_DISTANCE;
+            assert_eq!(falloff(0.0, material), 1.0);
+            assert_eq!(falloff(FULL_DRAIN_DISTANCE, material), 1.0);
+            assert_eq!(falloff(reach, material), 0.0);
+            assert_eq!(falloff(reach + 1.0, material), 0.0);
+
+            let halfway = (FULL_DRAIN_DISTANCE + reach) / 2.0;
+            assert!((falloff(halfway, material) - 0.5).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn anchor_chain_reaches_further_than_iron() {
+        let distance = ChainMaterial::Iron.breathing_range() + FALLOFF_DISTANCE;
+        assert_eq!(falloff(distance, ChainMaterial::Iron), 0.0);
+        assert!(falloff(distance, ChainMaterial::Anchor) > 0.0);
+    }
+
+    #[test]
+    fn shares_add_up_to_the_total_drain() {
+        let sources = [
+            source(Vec2::new(1.0, 0.0), ChainMaterial::Iron),
+            source(Vec2::new(0.0, 1.5), ChainMaterial::Anchor),
+            source(Vec2::new(-2.0, 0.0), ChainMaterial::Stainless),
+            source(Vec2::new(0.0, -0.8), ChainMaterial::RustyIron),
+        ];
+        let shares = drain_shares(Vec2::ZERO, &sources);
+        assert_eq!(shares.len(), sources.len());
+
+        let total = total_drain(Vec2::ZERO, &sources);
+        assert!(total > 0.0);
+        assert!((shares.iter().sum::<f32>() - total).abs() < 1e-4);
+    }
+
+    #[test]
+    fn lone_segment_drains_what_it_would_on_its_own() {
+        let sources = [source(Vec2::new(1.0, 0.0), ChainMaterial::Iron)];
+        let drain = total_drain(Vec2::ZERO, &sources);
+        assert!((drain - segment_drain(Vec2::ZERO, &sources[0])).abs() < 1e-4);
+    }
+
+    #[test]
+    fn drain_is_capped_even_when_walled_in() {
+        let sources = (0..20)
+            .map(|i| {
+                let side = [Vec2::X, Vec2::NEG_X, Vec2::Y, Vec2::NEG_Y][i % 4];
+                source(
+                    side * (0.5 + 0.1 * (i / 4) as f32),
+                    ChainMaterial::RustyIron,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(enclosed_sides(Vec2::ZERO, &sources), 4);
+
+        let uncapped: f32 = sources
+            .iter()
+            .map(|source| segment_drain(Vec2::ZERO, source))
+            .sum();
+        let cap = CHAIN_OXYGEN_DRAIN * DRAIN_CAP;
+        assert!(uncapped > cap);
+        assert!((total_drain(Vec2::ZERO, &sources) - cap).abs() < 1e-3);
+    }
+
+    #[test]
+    fn enclosed_sides_counts_segments_right_up_against_the_pirate() {
+        let right = source(Vec2::new(1.0, 0.0), ChainMaterial::Iron);
+        let left = source(Vec2::new(-1.0, 0.0), ChainMaterial::Iron);
+        let above = source(Vec2::new(0.0, 1.0), ChainMaterial::Iron);
+        let below = source(Vec2::new(0.0, -1.0), ChainMaterial::Iron);
+
+        assert_eq!(enclosed_sides(Vec2::ZERO, &[]), 0);
+        assert_eq!(enclosed_sides(Vec2::ZERO, &[right]), 1);
+        assert_eq!(enclosed_sides(Vec2::ZERO, &[right, right]), 1);
+        assert_eq!(enclosed_sides(Vec2::ZERO, &[right, left]), 2);
+        assert_eq!(enclosed_sides(Vec2::ZERO, &[right, left, above, below]), 4);
+    }
+
+    #[test]
+    fn far_diagonal_and_rusted_segments_dont_wall_a_pirate_in() {
+        let far = source(Vec2::new(2.0, 0.0), ChainMaterial::Iron);
+        let diagonal = source(Vec2::new(1.0, 1.0), ChainMaterial::Iron);
+        let mut rusted = source(Vec2::new(1.0, 0.0), ChainMaterial::Iron);
+        rusted.oxidation = Oxidation(1.0);
+
+        assert_eq!(enclosed_sides(Vec2::ZERO, &[far, diagonal, rusted]), 0);
+    }
+}
@@ -0,0 +1,173 @@
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+
+use crate::game::chain::{ChainMaterial, ChainSegment, Obstacle};
+use crate::game::controls::{NEXT_WAVE_BUTTON_OFFSET, NEXT_WAVE_BUTTON_SIZE};
+use crate::game::drain::{CHAIN_OXYGEN_DRAIN, DRAIN_CAP, DrainSource, total_drain};
+use crate::game::game_state::GameState;
+use crate::game::network::ChainNetwork;
+use crate::game::oxidation::{Oxidation, RustedThrough};
+use crate::game::tile::{GRID_HEIGHT, GRID_WIDTH, TILE_SIZE, Tile};
+use crate::game::ui::{ButtonClicked, HUD_MARGIN, Placed, button, hud, label};
+
+// Shows how much air a pirate would lose each second on every free tile of the
+// hold, so chains can be laid out before a wave rather than after it.
+
+// above the next wave button, high enough to stay clear of it when both grow to
+// fit a finger
+const DRAIN_BUTTON_OFFSET: Vec2 = Vec2::new(
+    HUD_MARGIN,
+    NEXT_WAVE_BUTTON_OFFSET.y + NEXT_WAVE_BUTTON_SIZE.y + 60.0,
+);
+const DRAIN_BUTTON_SIZE: Vec2 = Vec2::new(280.0, 60.0);
+
+const OVERLAY_COLOR: Color = Color::srgb(0.2, 0.35, 1.0);
+
+#[derive(Resource, Default)]
+struct DrainOverlay {
+    // whether the player wants to see it
+    visible: bool,
+    // whether it's on screen right now, which it only is while building
+    shown: bool,
+}
+
+#[derive(Component)]
+struct DrainButton;
+
+#[derive(Component)]
+struct DrainButtonText;
+
+#[derive(Component)]
+struct DrainOverlayCell;
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn(hud(GameState::Building))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    DrainButton,
+                    button(DRAIN_BUTTON_SIZE),
+                    Placed {
+                        anchor: Anchor::BottomRight,
+                        offset: DRAIN_BUTTON_OFFSET,
+                    },
+                ))
+                .with_child((
+                    DrainButtonText,
+                    label(&asset_server, "Show Drain", 30.0, Color::WHITE),
+                ));
+        });
+}
+
+fn drain_button(
+    mut evr_clicked: EventReader<ButtonClicked>,
+    mut overlay: ResMut<DrainOverlay>,
+    q_drain_button: Query<(), With<DrainButton>>,
+    mut q_text: Query<&mut Text, With<DrainButtonText>>,
+) {
+    if !evr_clicked
+        .read()
+        .any(|event| q_drain_button.contains(event.button))
+    {
+        return;
+    }
+
+    overlay.visible = !overlay.visible;
+    for mut text in q_text.iter_mut() {
+        text.0 = if overlay.visible {
+            "Hide Drain".to_string()
+        } else {
+            "Show Drain".to_string()
+        };
+    }
+}
+
+// rebuilt whenever the chains change underneath it
+fn update_drain_overlay(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    game_state: Res<State<GameState>>,
+    mut overlay: ResMut<DrainOverlay>,
+    q_chain: Query<
+        (
+            &Transform,
+            &ChainMaterial,
+            &Oxidation,
+            Option<&ChainNetwork>,
+        ),
+        (With<ChainSegment>, Without<RustedThrough>),
+    >,
+    q_obstacles: Query<&Obstacle>,
+    q_changed: Query<(), Or<(Changed<Oxidation>, Added<Obstacle>, Changed<ChainNetwork>)>>,
+    mut removed_obstacles: RemovedComponents<Obstacle>,
+    q_cells: Query<Entity, With<DrainOverlayCell>>,
+) {
+    let show = overlay.visible && *game_state.get() == GameState::Building;
+    let changed = removed_obstacles.read().count() > 0 || !q_changed.is_empty();
+    if show == overlay.shown && !(show && changed) {
+        return;
+    }
+
+    overlay.shown = show;
+    for entity in q_cells.iter() {
+        commands.entity(entity).despawn();
+    }
+    if !show {
+        return;
+    }
+
+    let sources = q_chain
+        .iter()
+        .map(|(transform, material, oxidation, network)| DrainSource {
+            position: transform.translation.xy(),
+            material: *material,
+            oxidation: *oxidation,
+            network_size: network.map_or(1, |network| network.size),
+        })
+        .collect::<Vec<_>>();
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+
+    for x in 1..GRID_WIDTH {
+        for y in 0..GRID_HEIGHT {
+            let tile = Tile { x, y };
+            if q_obstacles.iter().any(|obstacle| obstacle.tile == tile) {
+                continue;
+            }
+
+            let transform = tile.grid_coord_to_transform(1.5);
+            let drain = total_drain(transform.translation.xy(), &sources);
+            if drain <= 0.0 {
+                continue;
+            }
+
+            let strength = (drain / (CHAIN_OXYGEN_DRAIN * DRAIN_CAP)).min(1.0);
+            commands
+                .spawn((
+                    DrainOverlayCell,
+                    Sprite::from_color(
+                        OVERLAY_COLOR.with_alpha(0.15 + 0.5 * strength),
+                        Vec2::splat(TILE_SIZE),
+                    ),
+                    transform,
+                ))
+                .with_child((
+                    Text2d::new(format!("{:.0}", drain)),
+                    TextFont {
+                        font: font.clone(),
+                        font_size: 18.0,
+                        ..default()
+                    },
+                    TextColor(Color::linear_rgb(0.0, 0.0, 0.0)),
+                    Transform::from_xyz(0.0, 0.0, 0.1),
+                ));
+        }
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<DrainOverlay>();
+    app.add_systems(Startup, setup);
+    app.add_systems(Update, drain_button.run_if(in_state(GameState::Building)));
+    app.add_systems(Update, update_drain_overlay);
+}
@@ -2,6 +2,8 @@
 
 use bevy::prelude::*;
 
+use crate::game::boarding::BoardingPoint;
+use crate::game::chain::InventoryEntry;
 use crate::game::tile::Tile;
 
 pub struct TileEvent {
@@ -35,10 +37,21 @@ pub struct GoldBarLost;
 pub struct WaveStarted;
 
 #[derive(Event)]
-pub struct WaveComplete;
+pub struct WaveComplete {
+    // the wave had a captain
+    pub boss: bool,
+}
 
 #[derive(Event)]
-pub struct PirateDeath;
+pub struct PirateDeath {
+    pub location: Vec2,
+    // how big the pirate was, so what's left of it is the same size
+    pub scale: f32,
+}
+
+// a walking pirate put a foot down
+#[derive(Event)]
+pub struct PirateFootstep;
 
 #[derive(Event)]
 pub struct PrizeCollected;
@@ -52,6 +65,12 @@ pub struct PlayClickSFX;
 #[derive(Event)]
 pub struct PlayLongClickSFX;
 
+#[derive(Event)]
+pub struct PlayButtonClickSFX;
+
+#[derive(Event)]
+pub struct PlayButtonHoverSFX;
+
 #[derive(Event)]
 pub struct ChainPlaced;
 
@@ -78,24 +97,91 @@ pub struct TreeSpawned {
     pub tile: Tile,
 }
 
+// clears everything built in the hold
+#[derive(Event)]
+pub struct RunReset;
+
+// puts pirates, gold and the ship back to how they were before the first wave
+#[derive(Event)]
+pub struct WaveReset;
+
+#[derive(Event)]
+pub struct LevelStarted {
+    pub level: usize,
+}
+
+#[derive(Event)]
+pub struct PuzzleStarted {
+    pub puzzle: usize,
+}
+
+#[derive(Event)]
+pub struct PuzzleComplete {
+    pub solved: bool,
+}
+
+#[derive(Event)]
+pub struct InventoryReplaced {
+    pub entries: Vec<InventoryEntry>,
+}
+
+// take a chain off the hold and put it back in the inventory
+#[derive(Event)]
+pub struct RemoveChain {
+    pub chain: Entity,
+}
+
+#[derive(Event)]
+pub struct BoardingPointsReplaced {
+    pub points: Vec<BoardingPoint>,
+}
+
+#[derive(Event)]
+pub struct LevelComplete {
+    pub level: usize,
+    pub stars: u32,
+}
+
+// opens the controls screen over whatever is on screen, rather than from the title screen
+#[derive(Event)]
+pub struct OpenInputSettings;
+
+// opens the settings screen over whatever is on screen
+#[derive(Event)]
+pub struct OpenSettings;
+
 pub fn plugin(app: &mut App) {
     app.add_event::<TileMouseDown>();
     app.add_event::<TileMouseUp>();
     app.add_event::<TileMouseMove>();
+    app.add_event::<RemoveChain>();
     app.add_event::<GoldBarCollected>();
     app.add_event::<GoldBarDropped>();
     app.add_event::<GoldBarLost>();
     app.add_event::<WaveStarted>();
     app.add_event::<WaveComplete>();
     app.add_event::<PirateDeath>();
+    app.add_event::<PirateFootstep>();
     app.add_event::<PrizeCollected>();
     app.add_event::<GameOver>();
     app.add_event::<FoolsGoldSpawned>();
     app.add_event::<CrateSpawned>();
     app.add_event::<PlayClickSFX>();
     app.add_event::<PlayLongClickSFX>();
+    app.add_event::<PlayButtonClickSFX>();
+    app.add_event::<PlayButtonHoverSFX>();
     app.add_event::<ChainPlaced>();
     app.add_event::<ChainFinished>();
     app.add_event::<GlueSpawned>();
     app.add_event::<TreeSpawned>();
+    app.add_event::<RunReset>();
+    app.add_event::<WaveReset>();
+    app.add_event::<LevelStarted>();
+    app.add_event::<LevelComplete>();
+    app.add_event::<InventoryReplaced>();
+    app.add_event::<BoardingPointsReplaced>();
+    app.add_event::<PuzzleStarted>();
+    app.add_event::<PuzzleComplete>();
+    app.add_event::<OpenInputSettings>();
+    app.add_event::<OpenSettings>();
 }
@@ -1,8 +1,11 @@
 use bevy::prelude::*;
-use bevy::text::{LineBreak, TextBounds};
+use bevy::text::LineBreak;
 
-use crate::game::events::GameOver;
+use crate::game::events::{GameOver, RunReset, WaveReset};
+use crate::game::game_state::{GameMode, GameState};
+use crate::game::level::LEVELS;
 use crate::game::pirate::WaveNumber;
+use crate::game::ui::{ButtonClicked, PANEL_TEXT_COLOR, label, modal, panel, text_button};
 
 const GAME_OVER_WINDOW_WIDTH: f32 = 1400.0;
 const GAME_OVER_WINDOW_HEIGHT: f32 = 800.0;
@@ -10,7 +13,8 @@ const GAME_OVER_WINDOW_HEIGHT: f32 = 800.0;
 const GAME_OVER_WINDOW_PADDING: f32 = 20.0;
 
 const GAME_OVER_TEXT_BOX_WIDTH: f32 = GAME_OVER_WINDOW_WIDTH - 2.0 * GAME_OVER_WINDOW_PADDING;
-const GAME_OVER_TEXT_BOX_HEIGHT: f32 = GAME_OVER_WINDOW_HEIGHT - 2.0 * GAME_OVER_WINDOW_PADDING;
+
+const OK_BUTTON_SIZE: Vec2 = Vec2::new(260.0, 100.0);
 
 #[derive(Component, Debug)]
 pub struct GameOverWindow;
@@ -18,70 +22,87 @@ pub struct GameOverWindow;
 #[derive(Component, Debug)]
 pub struct OkButton;
 
-#[derive(Component, Debug)]
-pub struct OkButtonText;
-
 fn spawn_game_over_screen(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     wave_number: Res<WaveNumber>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
+    game_mode: Res<GameMode>,
     mut evr_game_over: EventReader<GameOver>,
 ) {
     for _ in evr_game_over.read() {
-        let rect = Rectangle::new(GAME_OVER_WINDOW_WIDTH, GAME_OVER_WINDOW_HEIGHT);
-        let color = Color::linear_rgba(0.8, 0.8, 0.8, 1.0);
+        let game_over_text = match *game_mode {
+            GameMode::Endless | GameMode::Puzzle(_) => format!(
+                "The pirates have escaped with all of your gold!\n\nYou made it to wave {}\n\nThank you for playing!\n\nReload the page to play again",
+                wave_number.0
+            ),
+            GameMode::Campaign(level) => format!(
+                "The pirates have escaped with too much of your gold!\n\nYou needed to keep {} gold through {} waves of {}",
+                LEVELS[level].gold_required,
+                LEVELS[level].waves.len(),
+                LEVELS[level].name
+            ),
+        };
 
-        let game_over_text = format!(
-            "The pirates have escaped with all of your gold!\n\nYou made it to wave {}\n\nThank you for playing!\n\nReload the page to play again",
-            wave_number.0
-        );
+        commands
+            .spawn((GameOverWindow, modal()))
+            .with_children(|parent| {
+                parent
+                    .spawn(panel(Vec2::new(
+                        GAME_OVER_WINDOW_WIDTH,
+                        GAME_OVER_WINDOW_HEIGHT,
+                    )))
+                    .with_children(|parent| {
+                        parent.spawn(label(&asset_server, "Game Over", 50.0, PANEL_TEXT_COLOR));
 
-        let e_game_over_window = commands
-            .spawn((
-                GameOverWindow,
-                Mesh2d(meshes.add(rect)),
-                MeshMaterial2d(materials.add(color)),
-                Transform::from_xyz(0.0, 0.0, 15.0),
-            ))
-            .id();
+                        parent.spawn((
+                            label(&asset_server, game_over_text, 35.0, PANEL_TEXT_COLOR),
+                            TextLayout::new(JustifyText::Center, LineBreak::WordBoundary),
+                            Node {
+                                width: Val::Px(GAME_OVER_TEXT_BOX_WIDTH),
+                                ..default()
+                            },
+                        ));
 
-        commands.entity(e_game_over_window).with_children(|parent| {
-            let font = asset_server.load("fonts/FiraSans-Bold.ttf");
-            let title_font = TextFont {
-                font: font.clone(),
-                font_size: 50.0,
-                ..default()
-            };
-            let body_text_font = TextFont {
-                font: font.clone(),
-                font_size: 35.0,
-                ..default()
-            };
+                        // campaign levels can be retried, so let the player back out to the level select
+                        if matches!(*game_mode, GameMode::Campaign(_)) {
+                            parent.spawn((
+                                OkButton,
+                                text_button(&asset_server, "Level Select", OK_BUTTON_SIZE),
+                            ));
+                        }
+                    });
+            });
+    }
+}
 
-            parent.spawn((
-                Text2d::new("Game Over"),
-                title_font,
-                TextColor(Color::linear_rgb(0.0, 0.0, 0.0)),
-                Transform::from_xyz(0.0, 300.0, 0.5),
-            ));
+fn return_to_level_select(
+    mut commands: Commands,
+    mut evr_clicked: EventReader<ButtonClicked>,
+    mut state: ResMut<NextState<GameState>>,
+    q_ok_button: Query<(), With<OkButton>>,
+    q_game_over_window: Query<Entity, With<GameOverWindow>>,
+    mut evw_run_reset: EventWriter<RunReset>,
+    mut evw_wave_reset: EventWriter<WaveReset>,
+) {
+    if !evr_clicked
+        .read()
+        .any(|event| q_ok_button.contains(event.button))
+    {
+        return;
+    }
 
-            parent.spawn((
-                Text2d::new(game_over_text),
-                body_text_font,
-                TextLayout::new(JustifyText::Center, LineBreak::WordBoundary),
-                TextColor(Color::linear_rgb(0.0, 0.0, 0.0)),
-                Transform::from_xyz(0.0, -300.0, 0.5),
-                TextBounds::from(Vec2::new(
-                    GAME_OVER_TEXT_BOX_WIDTH,
-                    GAME_OVER_TEXT_BOX_HEIGHT,
-                )),
-            ));
-        });
+    for entity in q_game_over_window.iter() {
+        commands.entity(entity).despawn();
     }
+    state.set(GameState::LevelSelect);
+    evw_run_reset.write(RunReset);
+    evw_wave_reset.write(WaveReset);
 }
 
 pub fn plugin(app: &mut App) {
     app.add_systems(Update, spawn_game_over_screen);
+    app.add_systems(
+        Update,
+        return_to_level_select.run_if(in_state(GameState::GameOver)),
+    );
 }
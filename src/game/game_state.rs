@@ -4,12 +4,27 @@ use bevy::prelude::*;
 pub enum GameState {
     #[default]
     TitleScreen,
+    InputSettings,
     Tutorial,
+    LevelSelect,
+    PuzzleSelect,
     Building,
     WaveInProgress,
     Prize,
     Modifier,
+    Victory,
+    PuzzleResult,
 
     #[allow(unused)]
     GameOver,
 }
+
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum GameMode {
+    #[default]
+    Endless,
+    // index into `level::LEVELS`
+    Campaign(usize),
+    // index into `puzzle::PuzzleLibrary`
+    Puzzle(usize),
+}
@@ -1,17 +1,52 @@
 use bevy::prelude::*;
+use grid_pathfinding::PathingGrid;
+use grid_util::grid::Grid;
+use grid_util::point::Point;
 
+use crate::game::chain::Obstacle;
 use crate::game::components::{Position, TileType};
-use crate::game::events::{FoolsGoldSpawned, GoldBarCollected, GoldBarDropped};
+use crate::game::events::{FoolsGoldSpawned, GoldBarCollected, GoldBarDropped, WaveReset};
+use crate::game::game_state::GameState;
 use crate::game::goldbar_text::GoldAmount;
-use crate::game::tile::Tile;
+use crate::game::input::{GridCursor, InputAction, update_grid_cursor};
+use crate::game::mouse::MousePos;
+use crate::game::pirate::{HOLD_POINT, get_pathing_grid, pirate_oxygen_system};
+use crate::game::status_effects::{EffectKind, EffectSource, StatusEffect};
+use crate::game::tile::{GRID_HEIGHT, GRID_WIDTH, TILE_SIZE, Tile};
 
 pub const TOTAL_GOLD_BARS: i32 = 4;
 pub const GOLD_ROOM_X: i32 = 27;
 pub const GOLD_ROOM_Y: i32 = 0;
 
+const LOOSE_GOLD_COLOR: Color = Color::srgb(1.0, 0.8, 0.45);
+// loose gold lies at an angle so it stands out from the neat stacks in the vault
+const LOOSE_GOLD_ROTATION: f32 = 0.4;
+
 #[derive(Component)]
 pub struct Gold;
 
+// a bar from the fool's gold modifier, which leaves whoever grabs it too dazzled to
+// leave and heading back for more
+#[derive(Component)]
+pub struct FoolsGold;
+
+pub const FOOLS_GOLD_CONFUSION: StatusEffect = StatusEffect {
+    kind: EffectKind::Confusion,
+    source: EffectSource::FoolsGold,
+    magnitude: 1.0,
+    remaining: 4.0,
+};
+
+// gold a pirate dropped out in the hold, rather than a bar sitting in the vault
+#[derive(Component)]
+pub struct LooseGold;
+
+// loose gold the player is carrying back to the vault
+#[derive(Component)]
+pub struct DraggedGold {
+    from: Tile,
+}
+
 fn spawn_gold_bar(
     commands: &mut Commands,
     asset_server: &Res<AssetServer>,
@@ -42,21 +77,52 @@ fn despawn_gold_bar(commands: &mut Commands, entity: Entity, gold_amount: &mut R
     commands.entity(entity).despawn();
 }
 
-pub fn spawn_gold_bars(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    mut gold_amount: ResMut<GoldAmount>,
-) {
+pub fn gold_room_positions() -> Vec<IVec2> {
     let mut gold_positions = Vec::with_capacity(TOTAL_GOLD_BARS as usize);
     for x in GOLD_ROOM_X..=GOLD_ROOM_X + 1 {
         for y in GOLD_ROOM_Y..=GOLD_ROOM_Y + TOTAL_GOLD_BARS / 2 - 1 {
             gold_positions.push(IVec2::new(x, y));
         }
     }
+    gold_positions
+}
+
+fn spawn_gold_room(
+    commands: &mut Commands,
+    asset_server: &Res<AssetServer>,
+    gold_amount: &mut ResMut<GoldAmount>,
+) {
+    for pos in gold_room_positions() {
+        spawn_gold_bar(commands, asset_server, pos, gold_amount);
+    }
+}
+
+pub fn spawn_gold_bars(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut gold_amount: ResMut<GoldAmount>,
+) {
+    spawn_gold_room(&mut commands, &asset_server, &mut gold_amount);
+}
+
+// put the hold back to a full stash of gold
+fn on_wave_reset(
+    mut commands: Commands,
+    mut evr_wave_reset: EventReader<WaveReset>,
+    asset_server: Res<AssetServer>,
+    mut gold_amount: ResMut<GoldAmount>,
+    q_gold: Query<Entity, With<Gold>>,
+) {
+    if evr_wave_reset.is_empty() {
+        return;
+    }
 
-    for pos in gold_positions {
-        spawn_gold_bar(&mut commands, &asset_server, pos, &mut gold_amount);
+    evr_wave_reset.clear();
+
+    for entity in q_gold.iter() {
+        despawn_gold_bar(&mut commands, entity, &mut gold_amount);
     }
+    spawn_gold_room(&mut commands, &asset_server, &mut gold_amount);
 }
 
 fn handle_gold_collected(
@@ -79,16 +145,191 @@ fn handle_gold_collected(
     }
 }
 
+// the closest tile to `tile` that isn't `taken`, so dropped gold doesn't pile up
+pub fn drop_tile(tile: Tile, taken: impl Fn(&Tile) -> bool) -> Tile {
+    for distance in 0..GRID_WIDTH + GRID_HEIGHT {
+        for dx in -distance..=distance {
+            let dy = distance - dx.abs();
+            for candidate in [
+                Tile {
+                    x: tile.x + dx,
+                    y: tile.y + dy,
+                },
+                Tile {
+                    x: tile.x + dx,
+                    y: tile.y - dy,
+                },
+            ] {
+                if (0..GRID_WIDTH).contains(&candidate.x)
+                    && (0..GRID_HEIGHT).contains(&candidate.y)
+                    && !taken(&candidate)
+                {
+                    return candidate;
+                }
+            }
+        }
+    }
+
+    tile
+}
+
+// Dropped gold lands on the closest free tile that pirates can still walk to, so it
+// never ends up inside an obstacle or shut away where nobody can reach it
+pub fn loose_gold_tile(
+    tile: Tile,
+    pathing_grid: &PathingGrid,
+    taken: impl Fn(&Tile) -> bool,
+) -> Tile {
+    drop_tile(tile, |candidate| {
+        let point = Point {
+            x: candidate.x,
+            y: candidate.y,
+        };
+        taken(candidate)
+            || pathing_grid.get(point.x as usize, point.y as usize)
+            || pathing_grid
+                .get_path_single_goal(point, HOLD_POINT, false)
+                .is_none()
+    })
+}
+
 fn handle_gold_dropped(
     mut commands: Commands,
     mut gold_amount: ResMut<GoldAmount>,
     mut gold_dropped_events: EventReader<GoldBarDropped>,
     asset_server: Res<AssetServer>,
+    q_gold: Query<&Tile, With<Gold>>,
+    q_obstacles: Query<&Obstacle>,
 ) {
+    if gold_dropped_events.is_empty() {
+        return;
+    }
+
+    let pathing_grid = get_pathing_grid(q_obstacles);
+    let mut taken = q_gold.iter().copied().collect::<Vec<_>>();
+
     for event in gold_dropped_events.read() {
-        // Spawn a new gold bar at the tile position
-        let pos = IVec2::new(event.tile.x, event.tile.y);
-        spawn_gold_bar(&mut commands, &asset_server, pos, &mut gold_amount);
+        let tile = loose_gold_tile(event.tile, &pathing_grid, |tile| taken.contains(tile));
+        taken.push(tile);
+
+        let pos = IVec2::new(tile.x, tile.y);
+        let entity = spawn_gold_bar(&mut commands, &asset_server, pos, &mut gold_amount);
+        commands.entity(entity).insert(LooseGold);
+    }
+}
+
+fn on_loose_gold_added(mut q_loose_gold: Query<(&mut Sprite, &mut Transform), Added<LooseGold>>) {
+    for (mut sprite, mut transform) in q_loose_gold.iter_mut() {
+        sprite.color = LOOSE_GOLD_COLOR;
+        transform.rotation = Quat::from_rotation_z(LOOSE_GOLD_ROTATION);
+    }
+}
+
+// the area the vault's bars are stacked in, which loose gold can be dropped back onto
+fn vault_rect() -> Rect {
+    let positions = gold_room_positions();
+    let corner = |pos: &IVec2| {
+        Tile { x: pos.x, y: pos.y }
+            .grid_coord_to_transform(0.0)
+            .translation
+            .xy()
+    };
+    let half_tile = Vec2::splat(TILE_SIZE / 2.0);
+    Rect::from_corners(
+        corner(&positions[0]) - half_tile,
+        corner(&positions[positions.len() - 1]) + half_tile,
+    )
+}
+
+// where dragged gold is, which is on the grid cursor when that's being moved
+// about rather than the mouse
+fn drag_position(cursor: &GridCursor, mouse_pos: &MousePos) -> Vec2 {
+    if cursor.shown {
+        cursor.tile.grid_coord_to_transform(0.0).translation.xy()
+    } else {
+        mouse_pos.0
+    }
+}
+
+fn grab_loose_gold(
+    mut commands: Commands,
+    mut evr_action: EventReader<InputAction>,
+    cursor: Res<GridCursor>,
+    q_loose_gold: Query<(Entity, &Tile), (With<LooseGold>, Without<DraggedGold>)>,
+) {
+    if !evr_action
+        .read()
+        .any(|action| *action == InputAction::BeginChain)
+    {
+        return;
+    }
+
+    if let Some((entity, tile)) = q_loose_gold.iter().find(|(_, tile)| **tile == cursor.tile) {
+        commands.entity(entity).insert(DraggedGold { from: *tile });
+    }
+}
+
+fn drag_loose_gold(
+    cursor: Res<GridCursor>,
+    mouse_pos: Res<MousePos>,
+    mut q_dragged_gold: Query<&mut Transform, With<DraggedGold>>,
+) {
+    for mut transform in q_dragged_gold.iter_mut() {
+        transform.translation = drag_position(&cursor, &mouse_pos).extend(6.0);
+    }
+}
+
+// Loose gold let go over the vault goes back into a free slot there. Anywhere else
+// it goes back to where it was picked up, so it can't be moved around as bait.
+fn release_loose_gold(
+    mut commands: Commands,
+    mut evr_action: EventReader<InputAction>,
+    cursor: Res<GridCursor>,
+    mouse_pos: Res<MousePos>,
+    mut q_dragged_gold: Query<
+        (
+            Entity,
+            &DraggedGold,
+            &mut Tile,
+            &mut Position,
+            &mut Transform,
+            &mut Sprite,
+        ),
+        With<LooseGold>,
+    >,
+    q_gold: Query<&Tile, (With<Gold>, Without<DraggedGold>)>,
+) {
+    if !evr_action
+        .read()
+        .any(|action| matches!(action, InputAction::Commit { .. }))
+    {
+        return;
+    }
+
+    let mut taken = q_gold.iter().copied().collect::<Vec<_>>();
+    for (entity, dragged, mut tile, mut position, mut transform, mut sprite) in
+        q_dragged_gold.iter_mut()
+    {
+        commands.entity(entity).remove::<DraggedGold>();
+
+        let vault_slot = gold_room_positions()
+            .into_iter()
+            .map(|pos| Tile { x: pos.x, y: pos.y })
+            .find(|slot| !taken.contains(slot));
+        let destination = match vault_slot {
+            Some(slot) if vault_rect().contains(drag_position(&cursor, &mouse_pos)) => {
+                commands.entity(entity).remove::<LooseGold>();
+                sprite.color = Color::WHITE;
+                transform.rotation = Quat::IDENTITY;
+                slot
+            }
+            _ => dragged.from,
+        };
+
+        taken.push(destination);
+        *tile = destination;
+        position.0 = IVec2::new(destination.x, destination.y);
+        transform.translation = destination.grid_coord_to_transform(2.0).translation;
     }
 }
 
@@ -101,13 +342,27 @@ fn handle_fools_gold_spawned(
     for event in fools_gold_spawned_events.read() {
         // Spawn a new gold bar at the tile position
         let pos = IVec2::new(event.tile.x, event.tile.y);
-        spawn_gold_bar(&mut commands, &asset_server, pos, &mut gold_amount);
+        let entity = spawn_gold_bar(&mut commands, &asset_server, pos, &mut gold_amount);
+        commands.entity(entity).insert(FoolsGold);
     }
 }
 
 pub fn plugin(app: &mut App) {
     app.add_systems(Startup, spawn_gold_bars);
-    app.add_systems(Update, handle_gold_collected);
-    app.add_systems(Update, handle_gold_dropped);
+    // pirates pick gold up and drop it during the wave's ticks, so it has to be
+    // gone or down before the next tick rather than the next frame
+    app.add_systems(
+        FixedUpdate,
+        (handle_gold_collected, handle_gold_dropped).after(pirate_oxygen_system),
+    );
+    app.add_systems(Update, on_loose_gold_added);
+    app.add_systems(
+        Update,
+        (grab_loose_gold, drag_loose_gold, release_loose_gold)
+            .chain()
+            .after(update_grid_cursor)
+            .run_if(in_state(GameState::Building)),
+    );
     app.add_systems(Update, handle_fools_gold_spawned);
+    app.add_systems(Update, on_wave_reset);
 }
@@ -1,4 +1,8 @@
 use bevy::prelude::*;
+use bevy::sprite::Anchor;
+
+use crate::game::game_state::GameState;
+use crate::game::ui::{HUD_MARGIN, Placed, hud, label};
 
 #[derive(Component)]
 struct GoldBarText;
@@ -9,24 +13,24 @@ pub struct GoldAmount {
 }
 
 fn spawn_gold_bar_text(mut commands: Commands, asset_server: Res<AssetServer>) {
-    commands.spawn((
-        // Accepts a `String` or any type that converts into a `String`, such as `&str`
-        Text2d::new("Gold: 0"),
-        TextFont {
-            // This font is loaded and will be used instead of the default font.
-            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
-            font_size: 55.0,
-            ..default()
-        },
+    commands.spawn(hud(GameState::Building)).with_child((
+        label(
+            &asset_server,
+            "Gold: 0",
+            55.0,
+            Color::linear_rgba(0.0, 0.0, 0.0, 1.0),
+        ),
         TextShadow::default(),
-        Transform::from_xyz(850.0, 330.0, 5.0),
-        TextColor(Color::linear_rgba(0.0, 0.0, 0.0, 1.0)),
+        Placed {
+            anchor: Anchor::TopRight,
+            offset: Vec2::splat(HUD_MARGIN),
+        },
         GoldBarText,
     ));
 }
 
 fn update_gold_text(
-    mut text_query: Query<&mut Text2d, With<GoldBarText>>,
+    mut text_query: Query<&mut Text, With<GoldBarText>>,
     gold_amount: Res<GoldAmount>,
 ) {
     if let Ok(mut text) = text_query.single_mut() {
@@ -0,0 +1,244 @@
+use bevy::ecs::event::EventCursor;
+use bevy::prelude::*;
+
+use crate::game::chain::{DraggingChain, MainInventoryChainButton, SelectedChain};
+use crate::game::events::{TileMouseDown, TileMouseMove};
+use crate::game::game_state::GameState;
+use crate::game::goldbar::DraggedGold;
+use crate::game::input_map::{BoundAction, InputDevices, InputMap};
+use crate::game::tile::{Direction, GRID_HEIGHT, GRID_WIDTH, TILE_SIZE, Tile, mouse_events};
+
+// Building chains and starting waves can be done with the mouse, the keyboard or
+// a gamepad. Whatever is used gets turned into actions here, and everything done
+// in the hold while building only ever looks at the actions and the cursor. What
+// the keys and buttons do is up to the player's `InputMap`.
+
+#[derive(Event, Clone, Copy, Debug, PartialEq)]
+pub enum InputAction {
+    // pick the nth chain in the inventory, counting from the left
+    SelectChain(usize),
+    MoveCursor(Direction),
+    // the mouse put the cursor straight onto a tile
+    PointAt(Tile),
+    // start a chain on the cursor, or pick up or mend whatever is there instead
+    BeginChain,
+    // drag the chain being drawn onto this tile
+    Extend(Tile),
+    // finish the chain being drawn, cutting it short rather than dropping it if
+    // it isn't finished and `keep_short` is set
+    Commit { keep_short: bool },
+    // drop the chain being drawn, or the stamp being held
+    Cancel,
+    StartWave,
+    // save the chain on the cursor as a stamp, or put down the stamp being held
+    SaveStamp,
+    TurnStamp,
+    MirrorStamp,
+}
+
+// the tile chains are built on when they aren't built with the mouse
+#[derive(Resource)]
+pub struct GridCursor {
+    pub tile: Tile,
+    // only drawn once it's been moved with the keyboard or a gamepad
+    pub shown: bool,
+}
+
+impl Default for GridCursor {
+    fn default() -> Self {
+        Self {
+            tile: Tile {
+                x: 1,
+                y: GRID_HEIGHT / 2,
+            },
+            shown: false,
+        }
+    }
+}
+
+#[derive(Component)]
+struct GridCursorMarker;
+
+const CURSOR_COLOR: Color = Color::linear_rgba(1.0, 0.85, 0.0, 0.45);
+
+fn mouse_actions(
+    input_map: Res<InputMap>,
+    devices: InputDevices,
+    mut evr_tile_mouse_down: EventReader<TileMouseDown>,
+    mut evr_tile_mouse_move: EventReader<TileMouseMove>,
+    mut evw_action: EventWriter<InputAction>,
+) {
+    for event in evr_tile_mouse_move.read() {
+        evw_action.write(InputAction::PointAt(event.0.tile));
+    }
+
+    for event in evr_tile_mouse_down.read() {
+        evw_action.write(InputAction::PointAt(event.0.tile));
+        evw_action.write(InputAction::BeginChain);
+    }
+
+    if devices.mouse_buttons.just_released(MouseButton::Left) {
+        evw_action.write(InputAction::Commit {
+            keep_short: input_map.pressed(BoundAction::KeepShort, &devices),
+        });
+    }
+}
+
+fn bound_actions(
+    input_map: Res<InputMap>,
+    devices: InputDevices,
+    q_dragging: Query<(), Or<(With<DraggingChain>, With<DraggedGold>)>>,
+    q_chain_buttons: Query<(&Transform, Has<SelectedChain>), With<MainInventoryChainButton>>,
+    mut evw_action: EventWriter<InputAction>,
+) {
+    let just_pressed = |action| input_map.just_pressed(action, &devices);
+
+    let cursor_moves = [
+        (BoundAction::CursorUp, Direction::Up),
+        (BoundAction::CursorDown, Direction::Down),
+        (BoundAction::CursorLeft, Direction::Left),
+        (BoundAction::CursorRight, Direction::Right),
+    ];
+    for (action, direction) in cursor_moves {
+        if just_pressed(action) {
+            evw_action.write(InputAction::MoveCursor(direction));
+        }
+    }
+
+    for action in BoundAction::ALL {
+        if let BoundAction::Chain(n) = action
+            && just_pressed(action)
+        {
+            evw_action.write(InputAction::SelectChain(n));
+        }
+    }
+
+    // whatever was picked up is put down again with the same button
+    if just_pressed(BoundAction::Place) {
+        if q_dragging.is_empty() {
+            evw_action.write(InputAction::BeginChain);
+        } else {
+            evw_action.write(InputAction::Commit {
+                keep_short: input_map.pressed(BoundAction::KeepShort, &devices),
+            });
+        }
+    }
+    if just_pressed(BoundAction::Cancel) {
+        evw_action.write(InputAction::Cancel);
+    }
+    if just_pressed(BoundAction::StartWave) {
+        evw_action.write(InputAction::StartWave);
+    }
+
+    let stamp_actions = [
+        (BoundAction::SaveStamp, InputAction::SaveStamp),
+        (BoundAction::TurnStamp, InputAction::TurnStamp),
+        (BoundAction::MirrorStamp, InputAction::MirrorStamp),
+    ];
+    for (bound, action) in stamp_actions {
+        if just_pressed(bound) {
+            evw_action.write(action);
+        }
+    }
+
+    // stepping through the inventory goes from whatever is picked now
+    let step = if just_pressed(BoundAction::NextChain) {
+        1
+    } else if just_pressed(BoundAction::PreviousChain) {
+        -1
+    } else {
+        return;
+    };
+    let mut buttons = q_chain_buttons.iter().collect::<Vec<_>>();
+    if buttons.is_empty() {
+        return;
+    }
+    buttons.sort_by(|(a, _), (b, _)| a.translation.x.total_cmp(&b.translation.x));
+    let next = match buttons.iter().position(|(_, selected)| *selected) {
+        Some(current) => (current as i32 + step).rem_euclid(buttons.len() as i32) as usize,
+        None => 0,
+    };
+    evw_action.write(InputAction::SelectChain(next));
+}
+
+// moving the cursor while a chain is being drawn drags the chain along with it
+pub fn update_grid_cursor(
+    mut actions: ResMut<Events<InputAction>>,
+    mut read: Local<EventCursor<InputAction>>,
+    mut cursor: ResMut<GridCursor>,
+) {
+    let mut moves = Vec::new();
+    for action in read.read(&actions) {
+        let tile = match *action {
+            InputAction::MoveCursor(direction) => {
+                cursor.shown = true;
+                let (dx, dy) = match direction {
+                    Direction::Up => (0, 1),
+                    Direction::Down => (0, -1),
+                    Direction::Left => (-1, 0),
+                    Direction::Right => (1, 0),
+                };
+                Tile {
+                    x: (cursor.tile.x + dx).clamp(1, GRID_WIDTH - 1),
+                    y: (cursor.tile.y + dy).clamp(0, GRID_HEIGHT - 1),
+                }
+            }
+            InputAction::PointAt(tile) => {
+                cursor.shown = false;
+                tile
+            }
+            _ => continue,
+        };
+
+        if tile != cursor.tile {
+            cursor.tile = tile;
+            moves.push(tile);
+        }
+    }
+
+    // these are read along with the rest of this frame's actions, by systems that run after this
+    for tile in moves {
+        actions.send(InputAction::Extend(tile));
+    }
+}
+
+fn setup(mut commands: Commands) {
+    commands.spawn((
+        GridCursorMarker,
+        Sprite::from_color(CURSOR_COLOR, Vec2::splat(TILE_SIZE)),
+        Transform::from_xyz(0.0, 0.0, 4.5),
+        Visibility::Hidden,
+    ));
+}
+
+fn draw_grid_cursor(
+    cursor: Res<GridCursor>,
+    game_state: Res<State<GameState>>,
+    mut q_marker: Query<(&mut Transform, &mut Visibility), With<GridCursorMarker>>,
+) {
+    for (mut transform, mut visibility) in q_marker.iter_mut() {
+        transform.translation = cursor.tile.grid_coord_to_transform(4.5).translation;
+        *visibility = if cursor.shown && *game_state.get() == GameState::Building {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app.add_event::<InputAction>();
+    app.init_resource::<GridCursor>();
+    app.add_systems(Startup, setup);
+    app.add_systems(
+        Update,
+        (
+            mouse_actions.after(mouse_events),
+            bound_actions,
+            update_grid_cursor,
+        )
+            .chain()
+            .run_if(in_state(GameState::Building)),
+    );
+    app.add_systems(Update, draw_grid_cursor);
+}
@@ -0,0 +1,488 @@
+use bevy::prelude::*;
+
+use crate::game::save;
+
+// Which keys, mouse buttons and gamepad buttons do what. Every action has a few
+// slots that can each hold any kind of binding, so the keyboard and a gamepad can
+// be used side by side. The left mouse button always points and clicks, so it
+// can't be bound to anything.
+
+const CONTROLS_SAVE: &str = "controls";
+
+pub const BINDING_SLOTS: usize = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButton),
+}
+
+// everything that can be bound, in the order the controls screen lists them
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BoundAction {
+    CursorUp,
+    CursorDown,
+    CursorLeft,
+    CursorRight,
+    // starts a chain, or finishes the one being drawn
+    Place,
+    // held while finishing a chain to keep it even though it's short
+    KeepShort,
+    // drops the chain being drawn, or pauses a wave
+    Cancel,
+    StartWave,
+    // saves the chain under the cursor as a stamp, or puts down the stamp being held
+    SaveStamp,
+    TurnStamp,
+    MirrorStamp,
+    PreviousChain,
+    NextChain,
+    // the nth chain in the inventory
+    Chain(usize),
+}
+
+impl BoundAction {
+    pub const ALL: [BoundAction; 22] = [
+        BoundAction::CursorUp,
+        BoundAction::CursorDown,
+        BoundAction::CursorLeft,
+        BoundAction::CursorRight,
+        BoundAction::Place,
+        BoundAction::KeepShort,
+        BoundAction::Cancel,
+        BoundAction::StartWave,
+        BoundAction::SaveStamp,
+        BoundAction::TurnStamp,
+        BoundAction::MirrorStamp,
+        BoundAction::PreviousChain,
+        BoundAction::NextChain,
+        BoundAction::Chain(0),
+        BoundAction::Chain(1),
+        BoundAction::Chain(2),
+        BoundAction::Chain(3),
+        BoundAction::Chain(4),
+        BoundAction::Chain(5),
+        BoundAction::Chain(6),
+        BoundAction::Chain(7),
+        BoundAction::Chain(8),
+    ];
+
+    pub fn label(&self) -> String {
+        match self {
+            BoundAction::CursorUp => "Cursor Up".to_string(),
+            BoundAction::CursorDown => "Cursor Down".to_string(),
+            BoundAction::CursorLeft => "Cursor Left".to_string(),
+            BoundAction::CursorRight => "Cursor Right".to_string(),
+            BoundAction::Place => "Place Chain".to_string(),
+            BoundAction::KeepShort => "Keep Short".to_string(),
+            BoundAction::Cancel => "Drop Chain / Pause".to_string(),
+            BoundAction::StartWave => "Start Wave".to_string(),
+            BoundAction::SaveStamp => "Save Stamp".to_string(),
+            BoundAction::TurnStamp => "Turn Stamp".to_string(),
+            BoundAction::MirrorStamp => "Mirror Stamp".to_string(),
+            BoundAction::PreviousChain => "Previous Chain".to_string(),
+            BoundAction::NextChain => "Next Chain".to_string(),
+            BoundAction::Chain(n) => format!("Chain {}", n + 1),
+        }
+    }
+
+    // how the action is written in the save file
+    fn name(&self) -> String {
+        match self {
+            BoundAction::Chain(n) => format!("Chain{}", n + 1),
+            action => format!("{:?}", action),
+        }
+    }
+
+    fn defaults(&self) -> Vec<Binding> {
+        use Binding::{Gamepad, Key, Mouse};
+
+        match self {
+            BoundAction::CursorUp => vec![
+                Key(KeyCode::ArrowUp),
+                Key(KeyCode::KeyW),
+                Gamepad(GamepadButton::DPadUp),
+            ],
+            BoundAction::CursorDown => vec![
+                Key(KeyCode::ArrowDown),
+                Key(KeyCode::KeyS),
+                Gamepad(GamepadButton::DPadDown),
+            ],
+            BoundAction::CursorLeft => vec![
+                Key(KeyCode::ArrowLeft),
+                Key(KeyCode::KeyA),
+                Gamepad(GamepadButton::DPadLeft),
+            ],
+            BoundAction::CursorRight => vec![
+                Key(KeyCode::ArrowRight),
+                Key(KeyCode::KeyD),
+                Gamepad(GamepadButton::DPadRight),
+            ],
+            BoundAction::Place => vec![
+                Key(KeyCode::Space),
+                Key(KeyCode::Enter),
+                Gamepad(GamepadButton::South),
+            ],
+            BoundAction::KeepShort => vec![
+                Key(KeyCode::ShiftLeft),
+                Key(KeyCode::ShiftRight),
+                Gamepad(GamepadButton::West),
+            ],
+            BoundAction::Cancel => vec![Key(KeyCode::Escape), Gamepad(GamepadButton::East)],
+            BoundAction::StartWave => vec![Key(KeyCode::KeyN), Gamepad(GamepadButton::Start)],
+            BoundAction::SaveStamp => {
+                vec![Mouse(MouseButton::Right), Gamepad(GamepadButton::North)]
+            }
+            BoundAction::TurnStamp => {
+                vec![Key(KeyCode::KeyR), Gamepad(GamepadButton::RightTrigger2)]
+            }
+            BoundAction::MirrorStamp => {
+                vec![Key(KeyCode::KeyF), Gamepad(GamepadButton::LeftTrigger2)]
+            }
+            BoundAction::PreviousChain => vec![Gamepad(GamepadButton::LeftTrigger)],
+            BoundAction::NextChain => vec![Gamepad(GamepadButton::RightTrigger)],
+            BoundAction::Chain(n) => vec![Key(DIGIT_KEYS[*n])],
+        }
+    }
+}
+
+const DIGIT_KEYS: [KeyCode; 9] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+];
+
+// the keys that can be bound. Anything else is ignored while rebinding
+pub const BINDABLE_KEYS: [KeyCode; 84] = [
+    KeyCode::KeyA,
+    KeyCode::KeyB,
+    KeyCode::KeyC,
+    KeyCode::KeyD,
+    KeyCode::KeyE,
+    KeyCode::KeyF,
+    KeyCode::KeyG,
+    KeyCode::KeyH,
+    KeyCode::KeyI,
+    KeyCode::KeyJ,
+    KeyCode::KeyK,
+    KeyCode::KeyL,
+    KeyCode::KeyM,
+    KeyCode::KeyN,
+    KeyCode::KeyO,
+    KeyCode::KeyP,
+    KeyCode::KeyQ,
+    KeyCode::KeyR,
+    KeyCode::KeyS,
+    KeyCode::KeyT,
+    KeyCode::KeyU,
+    KeyCode::KeyV,
+    KeyCode::KeyW,
+    KeyCode::KeyX,
+    KeyCode::KeyY,
+    KeyCode::KeyZ,
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+    KeyCode::Digit0,
+    KeyCode::ArrowUp,
+    KeyCode::ArrowDown,
+    KeyCode::ArrowLeft,
+    KeyCode::ArrowRight,
+    KeyCode::Space,
+    KeyCode::Enter,
+    KeyCode::Escape,
+    KeyCode::Tab,
+    KeyCode::Backspace,
+    KeyCode::ShiftLeft,
+    KeyCode::ShiftRight,
+    KeyCode::ControlLeft,
+    KeyCode::ControlRight,
+    KeyCode::AltLeft,
+    KeyCode::AltRight,
+    KeyCode::Minus,
+    KeyCode::Equal,
+    KeyCode::BracketLeft,
+    KeyCode::BracketRight,
+    KeyCode::Semicolon,
+    KeyCode::Quote,
+    KeyCode::Comma,
+    KeyCode::Period,
+    KeyCode::Slash,
+    KeyCode::Backslash,
+    KeyCode::Backquote,
+    KeyCode::Insert,
+    KeyCode::Delete,
+    KeyCode::Home,
+    KeyCode::End,
+    KeyCode::PageUp,
+    KeyCode::PageDown,
+    KeyCode::Numpad0,
+    KeyCode::Numpad1,
+    KeyCode::Numpad2,
+    KeyCode::Numpad3,
+    KeyCode::Numpad4,
+    KeyCode::Numpad5,
+    KeyCode::Numpad6,
+    KeyCode::Numpad7,
+    KeyCode::Numpad8,
+    KeyCode::Numpad9,
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+];
+
+pub const BINDABLE_MOUSE_BUTTONS: [MouseButton; 4] = [
+    MouseButton::Right,
+    MouseButton::Middle,
+    MouseButton::Back,
+    MouseButton::Forward,
+];
+
+pub const BINDABLE_GAMEPAD_BUTTONS: [GamepadButton; 19] = [
+    GamepadButton::South,
+    GamepadButton::East,
+    GamepadButton::North,
+    GamepadButton::West,
+    GamepadButton::C,
+    GamepadButton::Z,
+    GamepadButton::LeftTrigger,
+    GamepadButton::LeftTrigger2,
+    GamepadButton::RightTrigger,
+    GamepadButton::RightTrigger2,
+    GamepadButton::Select,
+    GamepadButton::Start,
+    GamepadButton::Mode,
+    GamepadButton::LeftThumb,
+    GamepadButton::RightThumb,
+    GamepadButton::DPadUp,
+    GamepadButton::DPadDown,
+    GamepadButton::DPadLeft,
+    GamepadButton::DPadRight,
+];
+
+impl Binding {
+    pub fn label(&self) -> String {
+        match self {
+            Binding::Key(key) => {
+                let name = format!("{:?}", key);
+                match name.strip_prefix("Key").or(name.strip_prefix("Digit")) {
+                    Some(short) => short.to_string(),
+                    None => name,
+                }
+            }
+            Binding::Mouse(button) => format!("Mouse {:?}", button),
+            Binding::Gamepad(button) => format!("Pad {:?}", button),
+        }
+    }
+
+    fn serialize(&self) -> String {
+        match self {
+            Binding::Key(key) => format!("key:{:?}", key),
+            Binding::Mouse(button) => format!("mouse:{:?}", button),
+            Binding::Gamepad(button) => format!("pad:{:?}", button),
+        }
+    }
+
+    fn deserialize(text: &str) -> Option<Self> {
+        let (kind, name) = text.split_once(':')?;
+        match kind {
+            "key" => BINDABLE_KEYS
+                .into_iter()
+                .find(|key| format!("{:?}", key) == name)
+                .map(Binding::Key),
+            "mouse" => BINDABLE_MOUSE_BUTTONS
+                .into_iter()
+                .find(|button| format!("{:?}", button) == name)
+                .map(Binding::Mouse),
+            "pad" => BINDABLE_GAMEPAD_BUTTONS
+                .into_iter()
+                .find(|button| format!("{:?}", button) == name)
+                .map(Binding::Gamepad),
+            _ => None,
+        }
+    }
+}
+
+// the bindings for every action, in the same order as `BoundAction::ALL`
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct InputMap {
+    pub bindings: Vec<[Option<Binding>; BINDING_SLOTS]>,
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        let bindings = BoundAction::ALL
+            .iter()
+            .map(|action| {
+                let mut slots = [None; BINDING_SLOTS];
+                for (slot, binding) in slots.iter_mut().zip(action.defaults()) {
+                    *slot = Some(binding);
+                }
+                slots
+            })
+            .collect();
+        Self { bindings }
+    }
+}
+
+impl InputMap {
+    fn slots(&self, action: BoundAction) -> impl Iterator<Item = Binding> + '_ {
+        let index = BoundAction::ALL
+            .iter()
+            .position(|other| *other == action)
+            .expect("every action is in BoundAction::ALL");
+        self.bindings[index].iter().flatten().copied()
+    }
+
+    pub fn just_pressed(&self, action: BoundAction, devices: &InputDevices) -> bool {
+        self.slots(action)
+            .any(|binding| devices.just_pressed(binding))
+    }
+
+    pub fn pressed(&self, action: BoundAction, devices: &InputDevices) -> bool {
+        self.slots(action).any(|binding| devices.pressed(binding))
+    }
+
+    // every (action, slot) whose binding is also used somewhere else
+    pub fn conflicts(&self) -> Vec<(usize, usize)> {
+        let all = self
+            .bindings
+            .iter()
+            .enumerate()
+            .flat_map(|(action, slots)| {
+                slots
+                    .iter()
+                    .enumerate()
+                    .filter_map(move |(slot, binding)| Some(((action, slot), (*binding)?)))
+            })
+            .collect::<Vec<_>>();
+        all.iter()
+            .filter(|(at, binding)| {
+                all.iter()
+                    .any(|(other_at, other)| other_at != at && other == binding)
+            })
+            .map(|(at, _)| *at)
+            .collect()
+    }
+
+    // one action per line, as its name and then its slots, with - for an empty one
+    fn serialize(&self) -> String {
+        BoundAction::ALL
+            .iter()
+            .zip(self.bindings.iter())
+            .map(|(action, slots)| {
+                let slots = slots
+                    .iter()
+                    .map(|binding| binding.map_or("-".to_string(), |binding| binding.serialize()))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                format!("{}={}", action.name(), slots)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    // actions that aren't in the file keep their defaults
+    fn deserialize(contents: &str) -> Self {
+        let mut map = Self::default();
+        for line in contents.lines() {
+            let Some((name, slots)) = line.split_once('=') else {
+                continue;
+            };
+            let Some(index) = BoundAction::ALL
+                .iter()
+                .position(|action| action.name() == name.trim())
+            else {
+                continue;
+            };
+
+            let mut bindings = [None; BINDING_SLOTS];
+            for (binding, text) in bindings.iter_mut().zip(slots.split_whitespace()) {
+                *binding = Binding::deserialize(text);
+            }
+            map.bindings[index] = bindings;
+        }
+        map
+    }
+
+    pub fn save(&self) {
+        save::store(CONTROLS_SAVE, &self.serialize());
+    }
+}
+
+// everything a binding can be pressed on
+#[derive(bevy::ecs::system::SystemParam)]
+pub struct InputDevices<'w, 's> {
+    pub keys: Res<'w, ButtonInput<KeyCode>>,
+    pub mouse_buttons: Res<'w, ButtonInput<MouseButton>>,
+    pub gamepads: Query<'w, 's, &'static Gamepad>,
+}
+
+impl InputDevices<'_, '_> {
+    pub fn just_pressed(&self, binding: Binding) -> bool {
+        match binding {
+            Binding::Key(key) => self.keys.just_pressed(key),
+            Binding::Mouse(button) => self.mouse_buttons.just_pressed(button),
+            Binding::Gamepad(button) => self
+                .gamepads
+                .iter()
+                .any(|gamepad| gamepad.just_pressed(button)),
+        }
+    }
+
+    pub fn pressed(&self, binding: Binding) -> bool {
+        match binding {
+            Binding::Key(key) => self.keys.pressed(key),
+            Binding::Mouse(button) => self.mouse_buttons.pressed(button),
+            Binding::Gamepad(button) => self.gamepads.iter().any(|gamepad| gamepad.pressed(button)),
+        }
+    }
+
+    // whatever bindable input was pressed this frame, if any
+    pub fn any_just_pressed(&self) -> Option<Binding> {
+        let key = BINDABLE_KEYS
+            .into_iter()
+            .find(|key| self.keys.just_pressed(*key))
+            .map(Binding::Key);
+        let mouse = || {
+            BINDABLE_MOUSE_BUTTONS
+                .into_iter()
+                .find(|button| self.mouse_buttons.just_pressed(*button))
+                .map(Binding::Mouse)
+        };
+        let gamepad = || {
+            BINDABLE_GAMEPAD_BUTTONS
+                .into_iter()
+                .map(Binding::Gamepad)
+                .find(|binding| self.just_pressed(*binding))
+        };
+        key.or_else(mouse).or_else(gamepad)
+    }
+}
+
+fn load_input_map(mut commands: Commands) {
+    let map = save::load(CONTROLS_SAVE)
+        .map(|contents| InputMap::deserialize(&contents))
+        .unwrap_or_default();
+    commands.insert_resource(map);
+}
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(Startup, load_input_map);
+}
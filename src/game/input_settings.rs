@@ -0,0 +1,281 @@
+use bevy::prelude::*;
+
+use crate::game::events::OpenInputSettings;
+use crate::game::game_state::GameState;
+use crate::game::input_map::{BINDING_SLOTS, BoundAction, InputDevices, InputMap};
+use crate::game::ui::{
+    ButtonClicked, ButtonColors, PANEL_TEXT_COLOR, UiButton, UiFocus, button, label, modal, panel,
+    text_button,
+};
+
+// The controls screen, opened from the title screen or over the pause menu. Click
+// a slot and press a key, mouse button or gamepad button to bind it, or
+// right-click a slot to clear it. Bindings used by more than one action show up
+// red.
+
+const WINDOW_SIZE: Vec2 = Vec2::new(1500.0, 1020.0);
+
+const ROW_HEIGHT: f32 = 34.0;
+const LABEL_WIDTH: f32 = 300.0;
+const SLOT_SPACING: f32 = 290.0;
+const SLOT_SIZE: Vec2 = Vec2::new(270.0, 32.0);
+
+const DEFAULTS_BUTTON_SIZE: Vec2 = Vec2::new(320.0, 70.0);
+const BACK_BUTTON_SIZE: Vec2 = Vec2::new(200.0, 70.0);
+const BUTTON_SPACING: f32 = 80.0;
+
+const SLOT_COLOR: Color = Color::linear_rgba(0.0, 0.0, 1.0, 1.0);
+const LISTENING_SLOT_COLOR: Color = Color::linear_rgba(1.0, 0.85, 0.0, 1.0);
+const CONFLICT_SLOT_COLOR: Color = Color::linear_rgba(0.8, 0.0, 0.0, 1.0);
+
+#[derive(Component)]
+pub struct InputSettingsWindow;
+
+#[derive(Component)]
+struct BindingSlot {
+    action: usize,
+    slot: usize,
+}
+
+#[derive(Component)]
+struct BindingSlotText;
+
+#[derive(Component)]
+struct ConflictText;
+
+#[derive(Component, Clone, Copy)]
+enum SettingsButton {
+    RestoreDefaults,
+    Back,
+}
+
+// the slot waiting for something to be pressed, if there is one
+#[derive(Resource, Default)]
+struct Rebinding {
+    listening: Option<(usize, usize)>,
+}
+
+fn spawn_input_settings_window(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn((InputSettingsWindow, modal()))
+        .with_children(|parent| {
+            parent.spawn(panel(WINDOW_SIZE)).with_children(|parent| {
+                parent.spawn(label(&asset_server, "Controls", 50.0, PANEL_TEXT_COLOR));
+                parent.spawn(label(
+                    &asset_server,
+                    "Click a slot and press a key or button to bind it. Right-click a slot to clear it.",
+                    24.0,
+                    PANEL_TEXT_COLOR,
+                ));
+
+                parent
+                    .spawn(Node {
+                        flex_direction: FlexDirection::Column,
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        for (i, action) in BoundAction::ALL.iter().enumerate() {
+                            parent
+                                .spawn(Node {
+                                    height: Val::Px(ROW_HEIGHT),
+                                    align_items: AlignItems::Center,
+                                    column_gap: Val::Px(SLOT_SPACING - SLOT_SIZE.x),
+                                    ..default()
+                                })
+                                .with_children(|parent| {
+                                    parent.spawn((
+                                        label(&asset_server, action.label(), 24.0, PANEL_TEXT_COLOR),
+                                        Node {
+                                            width: Val::Px(LABEL_WIDTH),
+                                            ..default()
+                                        },
+                                    ));
+
+                                    for slot in 0..BINDING_SLOTS {
+                                        parent
+                                            .spawn((BindingSlot { action: i, slot }, button(SLOT_SIZE)))
+                                            .with_child((
+                                                BindingSlotText,
+                                                label(&asset_server, "", 20.0, Color::WHITE),
+                                            ));
+                                    }
+                                });
+                        }
+                    });
+
+                parent.spawn((
+                    ConflictText,
+                    label(&asset_server, "", 24.0, CONFLICT_SLOT_COLOR),
+                ));
+
+                parent
+                    .spawn(Node {
+                        column_gap: Val::Px(BUTTON_SPACING),
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        for (text, settings_button, size) in [
+                            (
+                                "Restore Defaults",
+                                SettingsButton::RestoreDefaults,
+                                DEFAULTS_BUTTON_SIZE,
+                            ),
+                            ("Back", SettingsButton::Back, BACK_BUTTON_SIZE),
+                        ] {
+                            parent.spawn((settings_button, text_button(&asset_server, text, size)));
+                        }
+                    });
+            });
+        });
+}
+
+fn open_input_settings_window(
+    mut evr_open: EventReader<OpenInputSettings>,
+    commands: Commands,
+    asset_server: Res<AssetServer>,
+) {
+    if evr_open.read().count() > 0 {
+        spawn_input_settings_window(commands, asset_server);
+    }
+}
+
+fn despawn_input_settings_window(
+    mut commands: Commands,
+    mut rebinding: ResMut<Rebinding>,
+    mut focus: ResMut<UiFocus>,
+    q_window: Query<Entity, With<InputSettingsWindow>>,
+) {
+    rebinding.listening = None;
+    focus.paused = false;
+    for entity in q_window.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn rebind_controls(
+    mut commands: Commands,
+    devices: InputDevices,
+    mut evr_clicked: EventReader<ButtonClicked>,
+    mut input_map: ResMut<InputMap>,
+    mut rebinding: ResMut<Rebinding>,
+    mut focus: ResMut<UiFocus>,
+    game_state: Res<State<GameState>>,
+    mut state: ResMut<NextState<GameState>>,
+    q_window: Query<Entity, With<InputSettingsWindow>>,
+    q_slots: Query<(&BindingSlot, &Interaction)>,
+    q_settings_buttons: Query<&SettingsButton>,
+) {
+    // the left mouse button can't be bound, so clicking always picks a slot or stops listening
+    let clicked = evr_clicked.read().last().map(|event| event.button);
+    if clicked.is_some() || devices.mouse_buttons.just_pressed(MouseButton::Left) {
+        match clicked.and_then(|button| q_settings_buttons.get(button).ok()) {
+            Some(SettingsButton::RestoreDefaults) => {
+                *input_map = InputMap::default();
+                input_map.save();
+            }
+            // back to the title screen, or to whatever the window was opened over
+            Some(SettingsButton::Back) => {
+                if *game_state.get() == GameState::InputSettings {
+                    state.set(GameState::TitleScreen);
+                } else {
+                    for entity in q_window.iter() {
+                        commands.entity(entity).despawn();
+                    }
+                    rebinding.listening = None;
+                    focus.paused = false;
+                }
+                return;
+            }
+            None => {}
+        }
+
+        let clicked_slot = clicked
+            .and_then(|button| q_slots.get(button).ok())
+            .map(|(slot, _)| (slot.action, slot.slot));
+        rebinding.listening = match clicked_slot {
+            Some(slot) if rebinding.listening != Some(slot) => Some(slot),
+            _ => None,
+        };
+        // while listening, the keys that move the focus are bound instead
+        focus.paused = rebinding.listening.is_some();
+        return;
+    }
+
+    if let Some((action, slot)) = rebinding.listening {
+        if let Some(binding) = devices.any_just_pressed() {
+            input_map.bindings[action][slot] = Some(binding);
+            input_map.save();
+            rebinding.listening = None;
+            focus.paused = false;
+        }
+        return;
+    }
+
+    if devices.mouse_buttons.just_pressed(MouseButton::Right)
+        && let Some((slot, _)) = q_slots
+            .iter()
+            .find(|(_, interaction)| **interaction == Interaction::Hovered)
+    {
+        input_map.bindings[slot.action][slot.slot] = None;
+        input_map.save();
+    }
+}
+
+fn update_binding_slots(
+    input_map: Res<InputMap>,
+    rebinding: Res<Rebinding>,
+    mut q_slots: Query<(&BindingSlot, &mut UiButton, &Children)>,
+    mut q_slot_text: Query<&mut Text, (With<BindingSlotText>, Without<ConflictText>)>,
+    mut q_conflict_text: Query<&mut Text, With<ConflictText>>,
+) {
+    let conflicts = input_map.conflicts();
+
+    for (slot, mut button, children) in q_slots.iter_mut() {
+        let at = (slot.action, slot.slot);
+        let binding = input_map.bindings[slot.action][slot.slot];
+        let (color, label) = if rebinding.listening == Some(at) {
+            (LISTENING_SLOT_COLOR, "Press a key...".to_string())
+        } else if conflicts.contains(&at) {
+            (
+                CONFLICT_SLOT_COLOR,
+                binding.map(|b| b.label()).unwrap_or_default(),
+            )
+        } else {
+            (SLOT_COLOR, binding.map(|b| b.label()).unwrap_or_default())
+        };
+
+        button.colors = ButtonColors::filled(color);
+        for child in children.iter() {
+            if let Ok(mut text) = q_slot_text.get_mut(child) {
+                text.0 = label.clone();
+            }
+        }
+    }
+
+    for mut text in q_conflict_text.iter_mut() {
+        text.0 = if conflicts.is_empty() {
+            String::new()
+        } else {
+            "Some keys or buttons are bound to more than one action".to_string()
+        };
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<Rebinding>();
+    app.add_systems(
+        OnEnter(GameState::InputSettings),
+        spawn_input_settings_window,
+    );
+    app.add_systems(
+        OnExit(GameState::InputSettings),
+        despawn_input_settings_window,
+    );
+    app.add_systems(Update, open_input_settings_window);
+    app.add_systems(
+        Update,
+        (rebind_controls, update_binding_slots)
+            .chain()
+            .run_if(any_with_component::<InputSettingsWindow>),
+    );
+}
@@ -0,0 +1,421 @@
+use bevy::prelude::*;
+use grid_util::point::Point;
+
+use crate::game::boarding::{BoardingKind, BoardingPoint, PORT_SHIP};
+use crate::game::chain::{ChainMaterial, InventoryEntry};
+use crate::game::events::{
+    BoardingPointsReplaced, CrateSpawned, InventoryReplaced, LevelComplete, LevelStarted,
+};
+use crate::game::goldbar::TOTAL_GOLD_BARS;
+use crate::game::save;
+use crate::game::tile::Tile;
+
+const CAMPAIGN_SAVE: &str = "campaign";
+
+#[derive(Clone, Debug)]
+pub struct WaveSpec {
+    pub pirates: u32,
+    pub movement_speed: f32,
+    pub oxygen: f32,
+    // how many of the layout's boarding points this wave comes aboard from
+    pub boarding_points: usize,
+    // a captain comes aboard behind the crew
+    pub boss: bool,
+}
+
+pub struct Level {
+    pub name: &'static str,
+    pub crates: &'static [Tile],
+    pub boarding_points: &'static [BoardingPoint],
+    pub waves: &'static [WaveSpec],
+    pub inventory: &'static [InventoryEntry],
+    // gold that must still be in the hold once the last wave is over
+    pub gold_required: i32,
+}
+
+impl Level {
+    pub fn stars(&self, gold: i32) -> u32 {
+        if gold >= TOTAL_GOLD_BARS {
+            3
+        } else if gold > self.gold_required {
+            2
+        } else if gold >= self.gold_required {
+            1
+        } else {
+            0
+        }
+    }
+}
+
+pub const LEVELS: &[Level] = &[
+    Level {
+        name: "Shakedown",
+        crates: &[],
+        boarding_points: &[PORT_SHIP],
+        waves: &[
+            WaveSpec {
+                pirates: 3,
+                movement_speed: 200.0,
+                oxygen: 100.0,
+                boarding_points: 1,
+                boss: false,
+            },
+            WaveSpec {
+                pirates: 4,
+                movement_speed: 220.0,
+                oxygen: 100.0,
+                boarding_points: 1,
+                boss: false,
+            },
+            WaveSpec {
+                pirates: 5,
+                movement_speed: 250.0,
+                oxygen: 110.0,
+                boarding_points: 1,
+                boss: false,
+            },
+        ],
+        inventory: &[
+            InventoryEntry {
+                stock: 1,
+                length: 9,
+                material: ChainMaterial::Iron,
+            },
+            InventoryEntry {
+                stock: 2,
+                length: 4,
+                material: ChainMaterial::Iron,
+            },
+            InventoryEntry {
+                stock: 3,
+                length: 2,
+                material: ChainMaterial::Iron,
+            },
+        ],
+        gold_required: 1,
+    },
+    Level {
+        name: "Cargo Hold",
+        crates: &[
+            Tile { x: 6, y: 0 },
+            Tile { x: 6, y: 1 },
+            Tile { x: 6, y: 2 },
+            Tile { x: 6, y: 8 },
+            Tile { x: 6, y: 9 },
+            Tile { x: 6, y: 10 },
+            Tile { x: 12, y: 4 },
+            Tile { x: 12, y: 5 },
+            Tile { x: 12, y: 6 },
+            Tile { x: 18, y: 0 },
+            Tile { x: 18, y: 1 },
+            Tile { x: 18, y: 9 },
+            Tile { x: 18, y: 10 },
+        ],
+        boarding_points: &[PORT_SHIP],
+        waves: &[
+            WaveSpec {
+                pirates: 4,
+                movement_speed: 220.0,
+                oxygen: 100.0,
+                boarding_points: 1,
+                boss: false,
+            },
+            WaveSpec {
+                pirates: 5,
+                movement_speed: 250.0,
+                oxygen: 110.0,
+                boarding_points: 1,
+                boss: false,
+            },
+            WaveSpec {
+                pirates: 5,
+                movement_speed: 280.0,
+                oxygen: 120.0,
+                boarding_points: 1,
+                boss: false,
+            },
+            WaveSpec {
+                pirates: 6,
+                movement_speed: 300.0,
+                oxygen: 120.0,
+                boarding_points: 1,
+                boss: false,
+            },
+        ],
+        inventory: &[
+            InventoryEntry {
+                stock: 1,
+                length: 9,
+                material: ChainMaterial::Iron,
+            },
+            InventoryEntry {
+                stock: 3,
+                length: 4,
+                material: ChainMaterial::Iron,
+            },
+            InventoryEntry {
+                stock: 2,
+                length: 2,
+                material: ChainMaterial::Iron,
+            },
+        ],
+        gold_required: 2,
+    },
+    Level {
+        name: "The Gauntlet",
+        crates: &[
+            Tile { x: 4, y: 3 },
+            Tile { x: 5, y: 3 },
+            Tile { x: 6, y: 3 },
+            Tile { x: 7, y: 3 },
+            Tile { x: 4, y: 7 },
+            Tile { x: 5, y: 7 },
+            Tile { x: 6, y: 7 },
+            Tile { x: 7, y: 7 },
+            Tile { x: 14, y: 2 },
+            Tile { x: 15, y: 2 },
+            Tile { x: 14, y: 8 },
+            Tile { x: 15, y: 8 },
+            Tile { x: 21, y: 4 },
+            Tile { x: 21, y: 6 },
+        ],
+        boarding_points: &[
+            PORT_SHIP,
+            BoardingPoint {
+                point: Point { x: 11, y: 10 },
+                kind: BoardingKind::Hatch,
+            },
+        ],
+        waves: &[
+            WaveSpec {
+                pirates: 5,
+                movement_speed: 250.0,
+                oxygen: 110.0,
+                boarding_points: 1,
+                boss: false,
+            },
+            WaveSpec {
+                pirates: 6,
+                movement_speed: 280.0,
+                oxygen: 120.0,
+                boarding_points: 1,
+                boss: false,
+            },
+            WaveSpec {
+                pirates: 6,
+                movement_speed: 300.0,
+                oxygen: 130.0,
+                boarding_points: 2,
+                boss: false,
+            },
+            WaveSpec {
+                pirates: 7,
+                movement_speed: 320.0,
+                oxygen: 140.0,
+                boarding_points: 2,
+                boss: false,
+            },
+            WaveSpec {
+                pirates: 8,
+                movement_speed: 350.0,
+                oxygen: 150.0,
+                boarding_points: 2,
+                boss: false,
+            },
+        ],
+        inventory: &[
+            InventoryEntry {
+                stock: 2,
+                length: 9,
+                material: ChainMaterial::Iron,
+            },
+            InventoryEntry {
+                stock: 2,
+                length: 4,
+                material: ChainMaterial::Iron,
+            },
+            InventoryEntry {
+                stock: 4,
+                length: 2,
+                material: ChainMaterial::Iron,
+            },
+        ],
+        gold_required: 2,
+    },
+    Level {
+        name: "Captain's Stash",
+        crates: &[
+            Tile { x: 3, y: 4 },
+            Tile { x: 3, y: 6 },
+            Tile { x: 9, y: 0 },
+            Tile { x: 9, y: 1 },
+            Tile { x: 9, y: 2 },
+            Tile { x: 9, y: 3 },
+            Tile { x: 9, y: 7 },
+            Tile { x: 9, y: 8 },
+            Tile { x: 9, y: 9 },
+            Tile { x: 9, y: 10 },
+            Tile { x: 16, y: 5 },
+            Tile { x: 22, y: 2 },
+            Tile { x: 22, y: 3 },
+            Tile { x: 22, y: 7 },
+            Tile { x: 22, y: 8 },
+        ],
+        boarding_points: &[
+            PORT_SHIP,
+            BoardingPoint {
+                point: Point { x: 13, y: 8 },
+                kind: BoardingKind::Hatch,
+            },
+            BoardingPoint {
+                point: Point { x: 13, y: 2 },
+                kind: BoardingKind::Hatch,
+            },
+        ],
+        waves: &[
+            WaveSpec {
+                pirates: 5,
+                movement_speed: 300.0,
+                oxygen: 120.0,
+                boarding_points: 1,
+                boss: false,
+            },
+            WaveSpec {
+                pirates: 6,
+                movement_speed: 320.0,
+                oxygen: 130.0,
+                boarding_points: 2,
+                boss: false,
+            },
+            WaveSpec {
+                pirates: 7,
+                movement_speed: 350.0,
+                oxygen: 140.0,
+                boarding_points: 2,
+                boss: false,
+            },
+            WaveSpec {
+                pirates: 8,
+                movement_speed: 370.0,
+                oxygen: 150.0,
+                boarding_points: 3,
+                boss: false,
+            },
+            WaveSpec {
+                pirates: 8,
+                movement_speed: 400.0,
+                oxygen: 160.0,
+                boarding_points: 3,
+                boss: false,
+            },
+            WaveSpec {
+                pirates: 10,
+                movement_speed: 400.0,
+                oxygen: 180.0,
+                boarding_points: 3,
+                boss: true,
+            },
+        ],
+        inventory: &[
+            InventoryEntry {
+                stock: 2,
+                length: 9,
+                material: ChainMaterial::Iron,
+            },
+            InventoryEntry {
+                stock: 3,
+                length: 4,
+                material: ChainMaterial::Iron,
+            },
+            InventoryEntry {
+                stock: 3,
+                length: 2,
+                material: ChainMaterial::Iron,
+            },
+        ],
+        gold_required: 3,
+    },
+];
+
+// Best star count earned on each level, persisted between sessions
+#[derive(Resource, Debug)]
+pub struct CampaignProgress {
+    pub stars: Vec<u32>,
+}
+
+impl Default for CampaignProgress {
+    fn default() -> Self {
+        Self {
+            stars: vec![0; LEVELS.len()],
+        }
+    }
+}
+
+impl CampaignProgress {
+    // the first level is always open, every other level needs the previous one beaten
+    pub fn is_unlocked(&self, level: usize) -> bool {
+        level == 0 || self.stars.get(level - 1).is_some_and(|stars| *stars > 0)
+    }
+
+    fn serialize(&self) -> String {
+        self.stars
+            .iter()
+            .map(|stars| stars.to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn deserialize(contents: &str) -> Self {
+        let mut progress = Self::default();
+        for (level, line) in contents.lines().enumerate().take(LEVELS.len()) {
+            progress.stars[level] = line.trim().parse().unwrap_or(0).min(3);
+        }
+        progress
+    }
+}
+
+fn load_progress(mut commands: Commands) {
+    let progress = save::load(CAMPAIGN_SAVE)
+        .map(|contents| CampaignProgress::deserialize(&contents))
+        .unwrap_or_default();
+    commands.insert_resource(progress);
+}
+
+fn save_progress(
+    mut evr_level_complete: EventReader<LevelComplete>,
+    mut progress: ResMut<CampaignProgress>,
+) {
+    for event in evr_level_complete.read() {
+        // only ever improve on the best result
+        if event.stars > progress.stars[event.level] {
+            progress.stars[event.level] = event.stars;
+            save::store(CAMPAIGN_SAVE, &progress.serialize());
+        }
+    }
+}
+
+fn apply_level_layout(
+    mut evr_level_started: EventReader<LevelStarted>,
+    mut evw_crate_spawned: EventWriter<CrateSpawned>,
+    mut evw_inventory_replaced: EventWriter<InventoryReplaced>,
+    mut evw_boarding_points_replaced: EventWriter<BoardingPointsReplaced>,
+) {
+    for event in evr_level_started.read() {
+        let level = &LEVELS[event.level];
+        for tile in level.crates {
+            evw_crate_spawned.write(CrateSpawned { tile: *tile });
+        }
+        evw_inventory_replaced.write(InventoryReplaced {
+            entries: level.inventory.to_vec(),
+        });
+        evw_boarding_points_replaced.write(BoardingPointsReplaced {
+            points: level.boarding_points.to_vec(),
+        });
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<CampaignProgress>();
+    app.add_systems(Startup, load_progress);
+    app.add_systems(Update, (save_progress, apply_level_layout));
+}
@@ -0,0 +1,126 @@
+use bevy::prelude::*;
+
+use crate::game::events::LevelStarted;
+use crate::game::game_state::{GameMode, GameState};
+use crate::game::level::{CampaignProgress, LEVELS};
+use crate::game::ui::{ButtonClicked, Disabled, PANEL_TEXT_COLOR, button, label, modal, panel};
+
+const LEVEL_SELECT_WINDOW_WIDTH: f32 = 1400.0;
+const LEVEL_SELECT_WINDOW_HEIGHT: f32 = 800.0;
+
+const LEVEL_BUTTON_SIZE: Vec2 = Vec2::new(280.0, 180.0);
+const LEVEL_BUTTON_SPACING: f32 = 320.0;
+
+#[derive(Component)]
+pub struct LevelSelectWindow;
+
+#[derive(Component)]
+pub struct LevelButton {
+    level: usize,
+}
+
+fn stars_text(stars: u32) -> String {
+    format!("Stars: {} / 3", stars)
+}
+
+fn spawn_level_select_window(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    progress: Res<CampaignProgress>,
+) {
+    commands
+        .spawn((LevelSelectWindow, modal()))
+        .with_children(|parent| {
+            parent
+                .spawn(panel(Vec2::new(
+                    LEVEL_SELECT_WINDOW_WIDTH,
+                    LEVEL_SELECT_WINDOW_HEIGHT,
+                )))
+                .with_children(|parent| {
+                    parent.spawn(label(
+                        &asset_server,
+                        "Choose a Level",
+                        50.0,
+                        PANEL_TEXT_COLOR,
+                    ));
+
+                    parent
+                        .spawn(Node {
+                            column_gap: Val::Px(LEVEL_BUTTON_SPACING - LEVEL_BUTTON_SIZE.x),
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            for (i, level) in LEVELS.iter().enumerate() {
+                                let unlocked = progress.is_unlocked(i);
+                                let status = if unlocked {
+                                    stars_text(progress.stars[i])
+                                } else {
+                                    "Locked".to_string()
+                                };
+
+                                let mut level_button = parent.spawn((
+                                    LevelButton { level: i },
+                                    button(LEVEL_BUTTON_SIZE),
+                                    children![
+                                        label(
+                                            &asset_server,
+                                            format!("{}. {}", i + 1, level.name),
+                                            30.0,
+                                            Color::WHITE,
+                                        ),
+                                        label(
+                                            &asset_server,
+                                            format!("{} waves", level.waves.len()),
+                                            30.0,
+                                            Color::WHITE,
+                                        ),
+                                        label(
+                                            &asset_server,
+                                            status,
+                                            30.0,
+                                            Color::linear_rgba(1.0, 0.85, 0.0, 1.0),
+                                        ),
+                                    ],
+                                ));
+                                if !unlocked {
+                                    level_button.insert(Disabled);
+                                }
+                            }
+                        });
+                });
+        });
+}
+
+fn level_button_click(
+    mut commands: Commands,
+    mut evr_clicked: EventReader<ButtonClicked>,
+    mut state: ResMut<NextState<GameState>>,
+    mut game_mode: ResMut<GameMode>,
+    q_level_buttons: Query<&LevelButton>,
+    q_level_select_window: Query<Entity, With<LevelSelectWindow>>,
+    mut evw_level_started: EventWriter<LevelStarted>,
+) {
+    for event in evr_clicked.read() {
+        let Ok(level_button) = q_level_buttons.get(event.button) else {
+            continue;
+        };
+
+        commands
+            .entity(q_level_select_window.single().unwrap())
+            .despawn();
+        *game_mode = GameMode::Campaign(level_button.level);
+        state.set(GameState::Building);
+        evw_level_started.write(LevelStarted {
+            level: level_button.level,
+        });
+        break;
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(GameState::LevelSelect), spawn_level_select_window);
+    app.add_systems(
+        Update,
+        level_button_click.run_if(in_state(GameState::LevelSelect)),
+    );
+}
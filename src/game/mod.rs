@@ -3,53 +3,102 @@ mod components;
 use bevy::prelude::*;
 
 mod background;
+mod boarding;
 mod camera;
+mod captain;
 mod chain;
 mod controls;
+mod drain;
+mod drain_overlay;
 mod events;
 mod game_over_screen;
 mod game_state;
 mod goldbar;
 mod goldbar_text;
+mod input;
+mod input_map;
+mod input_settings;
+mod level;
+mod level_select;
 mod modifier_screen;
 mod modifiers;
 mod mouse;
 mod music;
+mod network;
+mod oxidation;
 mod oxygen;
+mod pause;
 mod pirate;
+mod pirate_animation;
 mod prizes;
+mod puzzle;
+mod puzzle_result_screen;
+mod puzzle_select;
+mod save;
+mod settings;
+mod settings_screen;
 mod ship;
+mod simulation;
 mod sound_effects;
+mod stamps;
+mod status_effects;
 mod tile;
 mod title_screen;
+mod touch;
 mod tutorial;
+mod ui;
+mod victory_screen;
 
-use crate::game::game_state::GameState;
+use crate::game::game_state::{GameMode, GameState};
 
 pub struct GamePlugin;
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
         app.init_state::<GameState>()
+            .init_resource::<GameMode>()
             .add_plugins(controls::plugin)
+            .add_plugins(pause::plugin)
             .add_plugins(camera::plugin)
             .add_plugins(mouse::plugin)
+            .add_plugins(input::plugin)
+            .add_plugins(input_map::plugin)
+            .add_plugins(input_settings::plugin)
+            .add_plugins(touch::plugin)
+            .add_plugins(ui::plugin)
             .add_plugins(background::plugin)
             .add_plugins(tile::plugin)
+            .add_plugins(boarding::plugin)
             .add_plugins(chain::plugin)
+            .add_plugins(stamps::plugin)
             .add_plugins(events::plugin)
             .add_plugins(pirate::plugin)
+            .add_plugins(captain::plugin)
+            .add_plugins(pirate_animation::plugin)
             .add_plugins(prizes::plugin)
             .add_plugins(tutorial::plugin)
             .add_plugins(ship::plugin)
             .add_plugins(goldbar_text::plugin)
             .add_plugins(goldbar::plugin)
             .add_plugins(oxygen::plugin)
+            .add_plugins(drain::plugin)
+            .add_plugins(drain_overlay::plugin)
+            .add_plugins(network::plugin)
+            .add_plugins(oxidation::plugin)
             .add_plugins(music::plugin)
             .add_plugins(sound_effects::plugin)
+            .add_plugins(settings::plugin)
+            .add_plugins(settings_screen::plugin)
             .add_plugins(modifiers::plugin)
+            .add_plugins(status_effects::plugin)
             .add_plugins(modifier_screen::plugin)
             .add_plugins(game_over_screen::plugin)
-            .add_plugins(title_screen::plugin);
+            .add_plugins(title_screen::plugin)
+            .add_plugins(level::plugin)
+            .add_plugins(level_select::plugin)
+            .add_plugins(victory_screen::plugin)
+            .add_plugins(puzzle::plugin)
+            .add_plugins(puzzle_select::plugin)
+            .add_plugins(puzzle_result_screen::plugin);
     }
 }
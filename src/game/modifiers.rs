@@ -2,54 +2,37 @@ use bevy::prelude::*;
 
 use crate::game::{
     chain::Obstacle,
-    events::{GlueSpawned, TreeSpawned},
+    events::{GlueSpawned, RunReset, TreeSpawned},
+    game_state::GameState,
+    pirate::pirate_movement_system,
+    status_effects::{
+        EffectKind, EffectSource, StatusEffect, StatusEffects, apply_effect, tick_status_effects,
+    },
     tile::{TILE_SIZE, Tile},
 };
 
+// glue holds pirates to half speed for a while after they step out of it
+const GLUE_SLOW: StatusEffect = StatusEffect {
+    kind: EffectKind::Slow,
+    source: EffectSource::GluePuddle,
+    magnitude: 0.5,
+    remaining: 2.0,
+};
+
 #[derive(Component)]
 pub struct GluePuddle;
 
 #[derive(Component)]
 pub struct Tree;
 
-#[derive(Component)]
-pub struct Sticky {
-    pub duration: f32,
-}
-
-pub fn sticky_system(
-    time: Res<Time>,
-    mut commands: Commands,
-    mut q_sticky_pirates: Query<(Entity, &mut Sticky)>,
-) {
-    for (entity, mut sticky) in q_sticky_pirates.iter_mut() {
-        sticky.duration -= time.delta().as_secs_f32();
-        if sticky.duration <= 0.0 {
-            commands.entity(entity).remove::<Sticky>();
-        }
-    }
-}
-
 pub fn glue_puddle_system(
-    mut commands: Commands,
-    mut q_non_sticky_pirates: Query<(Entity, &Transform), Without<Sticky>>,
-    mut q_sticky_pirates: Query<(&Transform, &mut Sticky), With<Sticky>>,
+    mut q_pirates: Query<(&Transform, &mut StatusEffects)>,
     q_puddles: Query<&Transform, With<GluePuddle>>,
 ) {
-    // first do pirates without sticky
-    for (entity, transform) in q_non_sticky_pirates.iter_mut() {
-        for puddle in q_puddles.iter() {
-            if transform.translation.distance(puddle.translation) < TILE_SIZE {
-                commands.entity(entity).insert(Sticky { duration: 2.0 });
-            }
-        }
-    }
-
-    // for pirates with sticky, refresh their duration
-    for (transform, mut sticky) in q_sticky_pirates.iter_mut() {
+    for (transform, mut effects) in q_pirates.iter_mut() {
         for puddle in q_puddles.iter() {
             if transform.translation.distance(puddle.translation) < TILE_SIZE {
-                sticky.duration = 2.0;
+                apply_effect(&mut effects, GLUE_SLOW);
             }
         }
     }
@@ -92,9 +75,29 @@ fn spawn_tree(
     }
 }
 
+fn on_run_reset(
+    mut commands: Commands,
+    mut evr_run_reset: EventReader<RunReset>,
+    q_modifiers: Query<Entity, Or<(With<GluePuddle>, With<Tree>)>>,
+) {
+    if evr_run_reset.is_empty() {
+        return;
+    }
+
+    evr_run_reset.clear();
+
+    for entity in q_modifiers.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
 pub fn plugin(app: &mut App) {
+    app.add_systems(Update, (spawn_glue, spawn_tree, on_run_reset));
     app.add_systems(
-        Update,
-        (sticky_system, glue_puddle_system, spawn_glue, spawn_tree),
+        FixedUpdate,
+        glue_puddle_system
+            .after(tick_status_effects)
+            .before(pirate_movement_system)
+            .run_if(in_state(GameState::WaveInProgress)),
     );
 }
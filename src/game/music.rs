@@ -1,24 +1,29 @@
-use bevy::{audio::Volume, prelude::*};
+use bevy::prelude::*;
 use rand::prelude::*;
 
-use crate::game::events::{WaveComplete, WaveStarted};
+use crate::game::events::{LevelComplete, WaveComplete, WaveReset, WaveStarted};
+use crate::game::settings::{Channel, Sound};
 
 #[derive(Component)]
 pub struct Music;
 
-fn set_volume(mut global_volume: ResMut<GlobalVolume>) {
-    global_volume.volume = Volume::Linear(0.3);
-}
-
 fn wave_end_music(
     mut commands: Commands,
     q_music: Query<Entity, With<Music>>,
     mut evr_wave_complete: EventReader<WaveComplete>,
+    mut evr_level_complete: EventReader<LevelComplete>,
+    mut evr_wave_reset: EventReader<WaveReset>,
 ) {
-    for _ in evr_wave_complete.read() {
-        for entity in q_music.iter() {
-            commands.entity(entity).despawn();
-        }
+    if evr_wave_complete.is_empty() && evr_level_complete.is_empty() && evr_wave_reset.is_empty() {
+        return;
+    }
+
+    evr_wave_complete.clear();
+    evr_level_complete.clear();
+    evr_wave_reset.clear();
+
+    for entity in q_music.iter() {
+        commands.entity(entity).despawn();
     }
 }
 
@@ -38,17 +43,13 @@ fn wave_start_music(
         let music_idx = rng.gen_range(0..MUSIC_LIST.len());
         commands.spawn((
             Music,
+            Sound::new(Channel::Music, 0.6),
             AudioPlayer::new(asset_server.load(MUSIC_LIST[music_idx])),
-            PlaybackSettings {
-                mode: bevy::audio::PlaybackMode::Loop,
-                volume: Volume::Linear(0.6),
-                ..default()
-            },
+            PlaybackSettings::LOOP,
         ));
     }
 }
 
 pub fn plugin(app: &mut App) {
-    app.add_systems(Startup, set_volume);
     app.add_systems(Update, (wave_start_music, wave_end_music));
 }
@@ -0,0 +1,326 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use crate::game::chain::{BranchOf, Chain, ChainMaterial, ChainSegment};
+use crate::game::events::PirateDeath;
+use crate::game::game_state::GameState;
+use crate::game::oxidation::{Oxidation, RustedThrough, link_color};
+use crate::game::oxygen::Oxygen;
+use crate::game::pirate::{OnGangplank, Pirate, in_breathing_range, pirate_oxygen_system};
+use crate::game::status_effects::{
+    EffectKind, EffectSource, StatusEffect, StatusEffects, apply_effect,
+};
+use crate::game::tile::Tile;
+
+// Chains whose ends touch join up into a network. Bigger networks drain harder
+// (see `drain::network_multiplier`), and a pirate suffocating next to one sends a
+// pulse along it that takes a burst of air from every other pirate beside it,
+// which can set off another pulse in turn.
+
+// oxygen a pulse takes from each pirate it passes
+pub const PULSE_DAMAGE: f32 = 25.0;
+
+// a pulse knocks the pirates it passes off their feet for a moment, and leaves
+// them winded so the chains take more out of them for a while after
+pub const PULSE_STUN: StatusEffect = StatusEffect {
+    kind: EffectKind::Stun,
+    source: EffectSource::NetworkPulse,
+    magnitude: 1.0,
+    remaining: 0.6,
+};
+pub const PULSE_WINDED: StatusEffect = StatusEffect {
+    kind: EffectKind::OxygenDrain,
+    source: EffectSource::NetworkPulse,
+    magnitude: 0.5,
+    remaining: 3.0,
+};
+
+// links per second
+const PULSE_SPEED: f32 = 14.0;
+
+const PULSE_FLASH_TIME: f32 = 0.3;
+const PULSE_COLOR: Color = Color::srgb(0.6, 0.9, 1.0);
+
+// which network a segment's chain is part of, and how many chains are in it.
+// Ids only mean anything until the chains change again
+#[derive(Component, Clone, Copy, Debug)]
+pub struct ChainNetwork {
+    pub id: usize,
+    pub size: usize,
+}
+
+#[derive(Component)]
+struct NetworkPulse {
+    // the links the pulse can get to, nearest first, with how many links along they are
+    links: Vec<(Entity, Vec2, u32)>,
+    elapsed: f32,
+    // how many of `links` the pulse has got to so far
+    reached: usize,
+    hit: Vec<Entity>,
+}
+
+// a link lighting up as a pulse goes through it
+#[derive(Component)]
+pub struct PulseFlash(Timer);
+
+// the network each chain is part of, given the tiles at either end of every chain
+// and which chains branch out of which. A network is named after the first chain in it
+pub fn chain_networks(ends: &[(Tile, Tile)], branches: &[(usize, usize)]) -> Vec<usize> {
+    fn root(networks: &mut [usize], mut chain: usize) -> usize {
+        while networks[chain] != chain {
+            networks[chain] = networks[networks[chain]];
+            chain = networks[chain];
+        }
+        chain
+    }
+
+    let mut joined = branches.to_vec();
+    for a in 0..ends.len() {
+        for b in a + 1..ends.len() {
+            let (a_first, a_last) = ends[a];
+            let (b_first, b_last) = ends[b];
+            let touching = [a_first, a_last]
+                .iter()
+                .any(|end| end.is_adjacent(&b_first) || end.is_adjacent(&b_last));
+            if touching {
+                joined.push((a, b));
+            }
+        }
+    }
+
+    let mut networks = (0..ends.len()).collect::<Vec<_>>();
+    for (a, b) in joined {
+        let a_root = root(&mut networks, a);
+        let b_root = root(&mut networks, b);
+        networks[a_root.max(b_root)] = a_root.min(b_root);
+    }
+
+    (0..ends.len())
+        .map(|chain| root(&mut networks, chain))
+        .collect()
+}
+
+// how many chains are in the network of each chain
+pub fn network_sizes(networks: &[usize]) -> Vec<usize> {
+    networks
+        .iter()
+        .map(|network| networks.iter().filter(|other| *other == network).count())
+        .collect()
+}
+
+// how many links along from `start` each tile is, or None if a gap cuts it off
+pub fn pulse_distances(tiles: &[Tile], start: usize) -> Vec<Option<u32>> {
+    let mut distances = vec![None; tiles.len()];
+    distances[start] = Some(0);
+    let mut queue = VecDeque::from([start]);
+    while let Some(current) = queue.pop_front() {
+        let distance = distances[current].unwrap_or_default();
+        for (next, tile) in tiles.iter().enumerate() {
+            if distances[next].is_none() && tile.is_adjacent(&tiles[current]) {
+                distances[next] = Some(distance + 1);
+                queue.push_back(next);
+            }
+        }
+    }
+    distances
+}
+
+// chains only change shape while building, or when links get knocked out of them
+fn update_chain_networks(
+    mut commands: Commands,
+    q_chains: Query<(Entity, &Children, Option<&BranchOf>), With<Chain>>,
+    q_changed: Query<(), (With<Chain>, Changed<Children>)>,
+    mut removed_chains: RemovedComponents<Chain>,
+    q_segments: Query<&ChainSegment>,
+) {
+    if removed_chains.read().count() == 0 && q_changed.is_empty() {
+        return;
+    }
+
+    let chains = q_chains
+        .iter()
+        .map(|(entity, children, branch_of)| {
+            let segments = children
+                .iter()
+                .filter(|child| q_segments.contains(*child))
+                .collect::<Vec<_>>();
+            (entity, segments, branch_of)
+        })
+        .filter(|(_, segments, _)| !segments.is_empty())
+        .collect::<Vec<_>>();
+    let ends = chains
+        .iter()
+        .map(|(_, segments, _)| {
+            let first = q_segments.get(segments[0]).unwrap().tile;
+            let last = q_segments.get(segments[segments.len() - 1]).unwrap().tile;
+            (first, last)
+        })
+        .collect::<Vec<_>>();
+    let branches = chains
+        .iter()
+        .enumerate()
+        .filter_map(|(i, (_, _, branch_of))| {
+            let branch_of = (*branch_of)?.0;
+            let trunk = chains
+                .iter()
+                .position(|(entity, _, _)| *entity == branch_of)?;
+            Some((trunk, i))
+        })
+        .collect::<Vec<_>>();
+    let networks = chain_networks(&ends, &branches);
+    let sizes = network_sizes(&networks);
+
+    for (i, (_, segments, _)) in chains.iter().enumerate() {
+        for segment in segments.iter() {
+            commands.entity(*segment).insert(ChainNetwork {
+                id: networks[i],
+                size: sizes[i],
+            });
+        }
+    }
+}
+
+fn start_pulses(
+    mut commands: Commands,
+    mut evr_pirate_death: EventReader<PirateDeath>,
+    q_segments: Query<(Entity, &ChainSegment, &Transform, &ChainNetwork), Without<RustedThrough>>,
+) {
+    for event in evr_pirate_death.read() {
+        let Some((origin_entity, _, _, origin)) = q_segments
+            .iter()
+            .filter(|(_, _, transform, _)| {
+                in_breathing_range(event.location, transform.translation.xy())
+            })
+            .min_by(|(_, _, a, _), (_, _, b, _)| {
+                let a = a.translation.xy().distance(event.location);
+                let b = b.translation.xy().distance(event.location);
+                a.total_cmp(&b)
+            })
+        else {
+            continue;
+        };
+
+        let segments = q_segments
+            .iter()
+            .filter(|(_, _, _, network)| network.id == origin.id)
+            .collect::<Vec<_>>();
+        let start = segments
+            .iter()
+            .position(|(entity, _, _, _)| *entity == origin_entity)
+            .unwrap_or_default();
+        let tiles = segments
+            .iter()
+            .map(|(_, segment, _, _)| segment.tile)
+            .collect::<Vec<_>>();
+
+        let mut links = segments
+            .iter()
+            .zip(pulse_distances(&tiles, start))
+            .filter_map(|((entity, _, transform, _), distance)| {
+                Some((*entity, transform.translation.xy(), distance?))
+            })
+            .collect::<Vec<_>>();
+        links.sort_by_key(|(_, _, distance)| *distance);
+
+        commands.spawn(NetworkPulse {
+            links,
+            elapsed: 0.0,
+            reached: 0,
+            hit: Vec::new(),
+        });
+    }
+}
+
+fn advance_pulses(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut q_pulses: Query<(Entity, &mut NetworkPulse)>,
+    mut q_pirates: Query<
+        (Entity, &Pirate, &mut Oxygen, &mut StatusEffects, &Transform),
+        Without<OnGangplank>,
+    >,
+) {
+    for (entity, mut pulse) in q_pulses.iter_mut() {
+        pulse.elapsed += time.delta().as_secs_f32();
+        let front = pulse.elapsed * PULSE_SPEED;
+
+        while pulse.reached < pulse.links.len() && pulse.links[pulse.reached].2 as f32 <= front {
+            let (link, location, _) = pulse.links[pulse.reached];
+            pulse.reached += 1;
+
+            // the link may have been knocked out since the pulse set off
+            commands
+                .entity(link)
+                .try_insert(PulseFlash(Timer::from_seconds(
+                    PULSE_FLASH_TIME,
+                    TimerMode::Once,
+                )));
+
+            for (pirate_entity, pirate, mut oxygen, mut effects, transform) in q_pirates.iter_mut()
+            {
+                if pirate.is_marked_for_despawn()
+                    || pulse.hit.contains(&pirate_entity)
+                    || !in_breathing_range(transform.translation.xy(), location)
+                {
+                    continue;
+                }
+                oxygen.0 -= PULSE_DAMAGE;
+                apply_effect(&mut effects, PULSE_STUN);
+                apply_effect(&mut effects, PULSE_WINDED);
+                pulse.hit.push(pirate_entity);
+            }
+        }
+
+        if pulse.reached == pulse.links.len() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+// a pulse still on its way when the wave ends would carry on into the next one
+fn clear_pulses(mut commands: Commands, q_pulses: Query<Entity, With<NetworkPulse>>) {
+    for entity in q_pulses.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn flash_pulsed_links(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut q_links: Query<
+        (
+            Entity,
+            &mut PulseFlash,
+            &mut Sprite,
+            &ChainMaterial,
+            &Oxidation,
+        ),
+        Without<RustedThrough>,
+    >,
+) {
+    for (entity, mut flash, mut sprite, material, oxidation) in q_links.iter_mut() {
+        flash.0.tick(time.delta());
+        let color = link_color(*material, *oxidation);
+        if flash.0.finished() {
+            sprite.color = color;
+            commands.entity(entity).remove::<PulseFlash>();
+            continue;
+        }
+
+        sprite.color = PULSE_COLOR.mix(&color, flash.0.fraction());
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(Update, (update_chain_networks, flash_pulsed_links));
+    // pulses take air from pirates, so they go with the rest of the wave's ticks
+    app.add_systems(
+        FixedUpdate,
+        (start_pulses, advance_pulses)
+            .chain()
+            .before(pirate_oxygen_system)
+            .run_if(in_state(GameState::WaveInProgress)),
+    );
+    app.add_systems(OnExit(GameState::WaveInProgress), clear_pulses);
+}
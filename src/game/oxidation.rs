@@ -0,0 +1,169 @@
+use bevy::prelude::*;
+
+use crate::game::boarding::BoardingPoints;
+use crate::game::chain::{
+    ChainButton, ChainButtonStock, ChainMaterial, ChainSegment, MainInventoryChainButton, Obstacle,
+    take_link,
+};
+use crate::game::events::InventoryReplaced;
+use crate::game::game_state::GameState;
+use crate::game::goldbar::LooseGold;
+use crate::game::input::{GridCursor, InputAction, update_grid_cursor};
+use crate::game::network::PulseFlash;
+use crate::game::pirate::{hold_reachable, pathing_grid_from_tiles};
+use crate::game::tile::Tile;
+
+// Chains take the air out of the hold by rusting. Every link keeps track of how
+// much it has rusted, drains less the further it goes, and eventually rusts
+// right through and has to be replaced between waves.
+
+// how much oxygen a fresh iron link takes in before it rusts right through
+const RUST_THROUGH_OXYGEN: f32 = 600.0;
+
+// a link that's nearly rusted through only drains this much of what a fresh one does
+const RUSTED_DRAIN: f32 = 0.4;
+
+const RUST_COLOR: Color = Color::srgb(0.55, 0.25, 0.1);
+
+// how far a link has rusted, from 0 when fresh to 1 when rusted through
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq)]
+pub struct Oxidation(pub f32);
+
+impl Oxidation {
+    pub fn drain_multiplier(&self) -> f32 {
+        1.0 - (1.0 - RUSTED_DRAIN) * self.0
+    }
+
+    // rust from having drained `oxygen` out of the air
+    pub fn absorb(&mut self, oxygen: f32, material: ChainMaterial) {
+        self.0 = (self.0 + oxygen * material.rust_rate() / RUST_THROUGH_OXYGEN).min(1.0);
+    }
+
+    pub fn is_rusted_through(&self) -> bool {
+        self.0 >= 1.0
+    }
+}
+
+// the colour a link has rusted to
+pub fn link_color(material: ChainMaterial, oxidation: Oxidation) -> Color {
+    material.tint().mix(&RUST_COLOR, oxidation.0)
+}
+
+// a link that's rusted through, leaving a gap until it's replaced. It keeps the
+// image it had so the replacement fits back into the chain the same way
+#[derive(Component)]
+pub struct RustedThrough {
+    image: Handle<Image>,
+}
+
+fn update_oxidized_links(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut q_links: Query<
+        (
+            Entity,
+            &Oxidation,
+            &ChainMaterial,
+            &mut Sprite,
+            Has<PulseFlash>,
+        ),
+        (Changed<Oxidation>, Without<RustedThrough>),
+    >,
+) {
+    for (entity, oxidation, material, mut sprite, flashing) in q_links.iter_mut() {
+        if !oxidation.is_rusted_through() {
+            // a flashing link goes back to its rusted colour once the flash is over
+            if !flashing {
+                sprite.color = link_color(*material, *oxidation);
+            }
+            continue;
+        }
+
+        let image = std::mem::replace(
+            &mut sprite.image,
+            asset_server.load("images/broken-chain.png"),
+        );
+        sprite.color = Color::WHITE;
+        commands
+            .entity(entity)
+            .insert(RustedThrough { image })
+            .remove::<Obstacle>();
+    }
+}
+
+// clicking a rusted through link while building puts a fresh one in its place,
+// cut from a chain of the same material in the inventory. Like a chain, it can't
+// go in if it would shut the pirates out of the hold
+fn replace_rusted_links(
+    mut commands: Commands,
+    mut evr_action: EventReader<InputAction>,
+    cursor: Res<GridCursor>,
+    boarding_points: Res<BoardingPoints>,
+    q_obstacles: Query<&Obstacle>,
+    mut q_buttons: Query<(&mut ChainButton, &Children, &Transform), With<MainInventoryChainButton>>,
+    mut q_stock_text: Query<&mut Text2d, With<ChainButtonStock>>,
+    mut evw_inventory_replaced: EventWriter<InventoryReplaced>,
+    mut q_links: Query<(
+        Entity,
+        &ChainSegment,
+        &ChainMaterial,
+        &RustedThrough,
+        &mut Oxidation,
+        &mut Sprite,
+    )>,
+    q_loose_gold: Query<&Tile, With<LooseGold>>,
+) {
+    if !evr_action
+        .read()
+        .any(|action| *action == InputAction::BeginChain)
+    {
+        return;
+    }
+
+    // gold dropped in the gap gets picked up first
+    if q_loose_gold.iter().any(|tile| *tile == cursor.tile) {
+        return;
+    }
+
+    let Some((entity, segment, material, rusted, mut oxidation, mut sprite)) = q_links
+        .iter_mut()
+        .find(|(_, segment, ..)| segment.tile == cursor.tile)
+    else {
+        return;
+    };
+
+    let pathing_grid = pathing_grid_from_tiles(
+        q_obstacles
+            .iter()
+            .map(|obstacle| obstacle.tile)
+            .chain(std::iter::once(segment.tile)),
+    );
+    if !hold_reachable(&pathing_grid, &boarding_points.points)
+        || !take_link(
+            *material,
+            &mut q_buttons,
+            &mut q_stock_text,
+            &mut evw_inventory_replaced,
+        )
+    {
+        return;
+    }
+
+    *oxidation = Oxidation::default();
+    sprite.image = rusted.image.clone();
+    sprite.color = material.tint();
+    commands
+        .entity(entity)
+        .insert(Obstacle { tile: segment.tile })
+        .remove::<RustedThrough>();
+}
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(Update, update_oxidized_links);
+    app.add_systems(
+        Update,
+        replace_rusted_links
+            .after(update_grid_cursor)
+            .run_if(in_state(GameState::Building)),
+    );
+}
@@ -1,5 +1,6 @@
 use bevy::prelude::*;
 
+use crate::game::captain::Captain;
 use crate::game::game_state::GameState;
 
 #[derive(Component)]
@@ -12,7 +13,8 @@ fn spawn_oxygen_bar(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
-    q_pirates: Query<(Entity, &Oxygen), Added<Oxygen>>,
+    // a captain's air is shown in the HUD instead
+    q_pirates: Query<(Entity, &Oxygen), (Added<Oxygen>, Without<Captain>)>,
 ) {
     for (e_pirate, oxygen) in q_pirates.iter() {
         let rect = Rectangle::new(100.0, 10.0);
@@ -34,8 +36,10 @@ fn update_oxygen_bar(
 ) {
     for (oxygen, children) in q_pirates.iter_mut() {
         for t_bar in children.iter() {
-            let mut transform = q_bars.get_mut(t_bar).unwrap();
-            transform.scale.x = oxygen.0 / 100.0;
+            // pirates carry other things over their heads too
+            if let Ok(mut transform) = q_bars.get_mut(t_bar) {
+                transform.scale.x = oxygen.0 / 100.0;
+            }
         }
     }
 }
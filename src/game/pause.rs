@@ -0,0 +1,320 @@
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+
+use crate::game::boarding::BoardingPoints;
+use crate::game::chain::ENDLESS_INVENTORY;
+use crate::game::events::{
+    BoardingPointsReplaced, InventoryReplaced, LevelStarted, OpenInputSettings, OpenSettings,
+    PuzzleStarted, RunReset, WaveReset,
+};
+use crate::game::game_state::{GameMode, GameState};
+use crate::game::input_map::{BoundAction, InputDevices, InputMap};
+use crate::game::input_settings::InputSettingsWindow;
+use crate::game::settings_screen::SettingsWindow;
+use crate::game::ui::{
+    ButtonClicked, ButtonColors, HUD_MARGIN, PANEL_TEXT_COLOR, Placed, UiButton, hud, label, modal,
+    panel, text_button,
+};
+
+// A wave can be paused with the pause button, or with whatever drops a chain while
+// building, and sped up with the speed buttons under the gold. Both work on bevy's
+// virtual clock, which the wave's fixed ticks and every system in `Update` run
+// on, so everything that moves or counts down during a wave stops or speeds up
+// together.
+
+const SPEEDS: [f32; 3] = [1.0, 2.0, 4.0];
+
+// under the gold, in from the top right corner of the screen
+const SPEED_BAR_OFFSET: Vec2 = Vec2::new(HUD_MARGIN, 120.0);
+const SPEED_BUTTON_SIZE: Vec2 = Vec2::new(90.0, 50.0);
+const PAUSE_BUTTON_SIZE: Vec2 = Vec2::new(120.0, 50.0);
+const SPEED_BUTTON_SPACING: f32 = 10.0;
+
+const SELECTED_SPEED_COLOR: Color = Color::linear_rgba(0.0, 0.6, 0.0, 1.0);
+
+const PAUSE_MENU_SIZE: Vec2 = Vec2::new(600.0, 760.0);
+const MENU_BUTTON_SIZE: Vec2 = Vec2::new(360.0, 90.0);
+
+// how fast waves play, which carries over from one wave to the next
+#[derive(Resource)]
+struct WaveSpeed(f32);
+
+impl Default for WaveSpeed {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+// set while the run is being restarted, so it's only laid out again once the
+// old one has been cleared away
+#[derive(Resource, Default)]
+struct Restarting(bool);
+
+#[derive(Component)]
+struct PauseMenu;
+
+#[derive(Component, Clone, Copy)]
+enum PauseMenuButton {
+    Resume,
+    Settings,
+    Controls,
+    Restart,
+    QuitToTitle,
+}
+
+#[derive(Component)]
+struct PauseButton;
+
+#[derive(Component)]
+struct SpeedButton(f32);
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn(hud(GameState::WaveInProgress))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    Placed {
+                        anchor: Anchor::TopRight,
+                        offset: SPEED_BAR_OFFSET,
+                    },
+                    Node {
+                        column_gap: Val::Px(SPEED_BUTTON_SPACING),
+                        ..default()
+                    },
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        PauseButton,
+                        text_button(&asset_server, "Pause", PAUSE_BUTTON_SIZE),
+                    ));
+                    for speed in SPEEDS {
+                        parent.spawn((
+                            SpeedButton(speed),
+                            text_button(&asset_server, format!("{}x", speed), SPEED_BUTTON_SIZE),
+                        ));
+                    }
+                });
+        });
+}
+
+fn spawn_pause_menu(commands: &mut Commands, asset_server: &AssetServer) {
+    let menu_buttons = [
+        ("Resume", PauseMenuButton::Resume),
+        ("Settings", PauseMenuButton::Settings),
+        ("Controls", PauseMenuButton::Controls),
+        ("Restart", PauseMenuButton::Restart),
+        ("Quit to Title", PauseMenuButton::QuitToTitle),
+    ];
+
+    commands
+        .spawn((PauseMenu, modal()))
+        .with_children(|parent| {
+            parent
+                .spawn(panel(PAUSE_MENU_SIZE))
+                .with_children(|parent| {
+                    parent.spawn(label(asset_server, "Paused", 50.0, PANEL_TEXT_COLOR));
+                    for (text, menu_button) in menu_buttons {
+                        parent.spawn((
+                            menu_button,
+                            text_button(asset_server, text, MENU_BUTTON_SIZE),
+                        ));
+                    }
+                });
+        });
+}
+
+fn close_pause_menu(
+    commands: &mut Commands,
+    time: &mut Time<Virtual>,
+    q_pause_menu: &Query<Entity, With<PauseMenu>>,
+) {
+    for entity in q_pause_menu.iter() {
+        commands.entity(entity).despawn();
+    }
+    time.unpause();
+}
+
+fn toggle_pause(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    input_map: Res<InputMap>,
+    devices: InputDevices,
+    mut evr_clicked: EventReader<ButtonClicked>,
+    mut time: ResMut<Time<Virtual>>,
+    q_pause_button: Query<(), With<PauseButton>>,
+    q_pause_menu: Query<Entity, With<PauseMenu>>,
+    q_input_settings: Query<(), With<InputSettingsWindow>>,
+    q_settings: Query<(), With<SettingsWindow>>,
+) {
+    let clicked = evr_clicked
+        .read()
+        .any(|event| q_pause_button.contains(event.button));
+    if !clicked && !input_map.just_pressed(BoundAction::Cancel, &devices) {
+        return;
+    }
+
+    // the menu stays put while a screen opened from it is still open over it
+    if !q_input_settings.is_empty() || !q_settings.is_empty() {
+        return;
+    }
+
+    if q_pause_menu.is_empty() {
+        spawn_pause_menu(&mut commands, &asset_server);
+        time.pause();
+    } else {
+        close_pause_menu(&mut commands, &mut time, &q_pause_menu);
+    }
+}
+
+// leaving the wave, however it's left, unpauses the game in `leave_wave`
+fn pause_menu_click(
+    mut commands: Commands,
+    mut evr_clicked: EventReader<ButtonClicked>,
+    mut time: ResMut<Time<Virtual>>,
+    mut state: ResMut<NextState<GameState>>,
+    mut restarting: ResMut<Restarting>,
+    q_menu_buttons: Query<&PauseMenuButton>,
+    q_pause_menu: Query<Entity, With<PauseMenu>>,
+    mut evw_open_settings: EventWriter<OpenSettings>,
+    mut evw_open_input_settings: EventWriter<OpenInputSettings>,
+    mut evw_run_reset: EventWriter<RunReset>,
+    mut evw_wave_reset: EventWriter<WaveReset>,
+    mut evw_inventory_replaced: EventWriter<InventoryReplaced>,
+    mut evw_boarding_points_replaced: EventWriter<BoardingPointsReplaced>,
+) {
+    let Some(menu_button) = evr_clicked
+        .read()
+        .find_map(|event| q_menu_buttons.get(event.button).ok())
+    else {
+        return;
+    };
+
+    match menu_button {
+        PauseMenuButton::Resume => close_pause_menu(&mut commands, &mut time, &q_pause_menu),
+        PauseMenuButton::Settings => {
+            evw_open_settings.write(OpenSettings);
+        }
+        PauseMenuButton::Controls => {
+            evw_open_input_settings.write(OpenInputSettings);
+        }
+        PauseMenuButton::Restart => {
+            evw_run_reset.write(RunReset);
+            evw_wave_reset.write(WaveReset);
+            restarting.0 = true;
+            state.set(GameState::Building);
+        }
+        PauseMenuButton::QuitToTitle => {
+            evw_run_reset.write(RunReset);
+            evw_wave_reset.write(WaveReset);
+            // endless runs start from the title screen as the game starts up
+            lay_out_endless(
+                &mut evw_inventory_replaced,
+                &mut evw_boarding_points_replaced,
+            );
+            state.set(GameState::TitleScreen);
+        }
+    }
+}
+
+fn lay_out_endless(
+    evw_inventory_replaced: &mut EventWriter<InventoryReplaced>,
+    evw_boarding_points_replaced: &mut EventWriter<BoardingPointsReplaced>,
+) {
+    evw_inventory_replaced.write(InventoryReplaced {
+        entries: ENDLESS_INVENTORY.to_vec(),
+    });
+    evw_boarding_points_replaced.write(BoardingPointsReplaced {
+        points: BoardingPoints::default().points,
+    });
+}
+
+// crates go down a frame after being asked for, so a level is only laid out
+// again once the reset has cleared the old one away
+fn restart_run(
+    mut restarting: ResMut<Restarting>,
+    game_mode: Res<GameMode>,
+    mut evw_level_started: EventWriter<LevelStarted>,
+    mut evw_puzzle_started: EventWriter<PuzzleStarted>,
+    mut evw_inventory_replaced: EventWriter<InventoryReplaced>,
+    mut evw_boarding_points_replaced: EventWriter<BoardingPointsReplaced>,
+) {
+    if !std::mem::take(&mut restarting.0) {
+        return;
+    }
+
+    match *game_mode {
+        GameMode::Endless => lay_out_endless(
+            &mut evw_inventory_replaced,
+            &mut evw_boarding_points_replaced,
+        ),
+        GameMode::Campaign(level) => {
+            evw_level_started.write(LevelStarted { level });
+        }
+        GameMode::Puzzle(puzzle) => {
+            evw_puzzle_started.write(PuzzleStarted { puzzle });
+        }
+    }
+}
+
+fn speed_buttons(
+    mut evr_clicked: EventReader<ButtonClicked>,
+    mut speed: ResMut<WaveSpeed>,
+    mut time: ResMut<Time<Virtual>>,
+    q_speed_buttons: Query<&SpeedButton>,
+) {
+    let Some(speed_button) = evr_clicked
+        .read()
+        .find_map(|event| q_speed_buttons.get(event.button).ok())
+    else {
+        return;
+    };
+
+    speed.0 = speed_button.0;
+    time.set_relative_speed(speed.0);
+}
+
+fn update_speed_buttons(
+    speed: Res<WaveSpeed>,
+    mut q_speed_buttons: Query<(&SpeedButton, &mut UiButton)>,
+) {
+    if !speed.is_changed() {
+        return;
+    }
+
+    for (speed_button, mut button) in q_speed_buttons.iter_mut() {
+        button.colors = if speed_button.0 == speed.0 {
+            ButtonColors::filled(SELECTED_SPEED_COLOR)
+        } else {
+            ButtonColors::FILLED
+        };
+    }
+}
+
+fn enter_wave(speed: Res<WaveSpeed>, mut time: ResMut<Time<Virtual>>) {
+    time.set_relative_speed(speed.0);
+}
+
+// building always goes at normal speed
+fn leave_wave(
+    mut commands: Commands,
+    mut time: ResMut<Time<Virtual>>,
+    q_pause_menu: Query<Entity, With<PauseMenu>>,
+) {
+    close_pause_menu(&mut commands, &mut time, &q_pause_menu);
+    time.set_relative_speed(1.0);
+}
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<WaveSpeed>();
+    app.init_resource::<Restarting>();
+    app.add_systems(Startup, setup);
+    app.add_systems(OnEnter(GameState::WaveInProgress), enter_wave);
+    app.add_systems(OnExit(GameState::WaveInProgress), leave_wave);
+    app.add_systems(OnEnter(GameState::Building), restart_run);
+    app.add_systems(
+        Update,
+        (toggle_pause, pause_menu_click, speed_buttons).run_if(in_state(GameState::WaveInProgress)),
+    );
+    app.add_systems(Update, update_speed_buttons);
+}
@@ -1,13 +1,29 @@
+use std::cmp::Reverse;
+
 use bevy::prelude::*;
 
-use crate::game::chain::{ChainSegment, Obstacle};
+use crate::game::boarding::{
+    BoardingKind, BoardingPoint, BoardingPoints, pirates_at_boarding_point,
+};
+use crate::game::captain::{
+    BOSS_WAVE_INTERVAL, CAPTAIN_OXYGEN_MULTIPLIER, CAPTAIN_SCALE, CAPTAIN_SPEED_MULTIPLIER,
+    Captain, OxygenTank,
+};
+use crate::game::chain::Obstacle;
 use crate::game::events::{
-    GameOver, GoldBarCollected, GoldBarDropped, GoldBarLost, PirateDeath, WaveComplete, WaveStarted,
+    GameOver, GoldBarCollected, GoldBarDropped, GoldBarLost, LevelComplete, PirateDeath,
+    PuzzleComplete, WaveComplete, WaveReset, WaveStarted,
 };
-use crate::game::game_state::GameState;
-use crate::game::goldbar::Gold;
-use crate::game::modifiers::{Sticky, Tree};
+use crate::game::game_state::{GameMode, GameState};
+use crate::game::goldbar::{FOOLS_GOLD_CONFUSION, FoolsGold, Gold, TOTAL_GOLD_BARS};
+use crate::game::level::{LEVELS, WaveSpec};
+use crate::game::modifiers::Tree;
 use crate::game::oxygen::Oxygen;
+use crate::game::puzzle::{Puzzle, PuzzleLibrary};
+use crate::game::ship::Ship;
+use crate::game::status_effects::{
+    EffectKind, EffectSource, StatusEffect, StatusEffects, apply_effect,
+};
 use crate::game::tile::{GRID_HEIGHT, GRID_WIDTH, GRID_X_START, GRID_Y_START, TILE_SIZE, Tile};
 
 use grid_pathfinding::PathingGrid;
@@ -17,12 +33,43 @@ use grid_util::point::Point;
 pub const BOAT_POINT: Point = Point { x: 0, y: 5 };
 pub const HOLD_POINT: Point = Point { x: 26, y: 5 };
 
-const SPAWN_INTERVAL: f32 = 2.0;
+// Waves run in fixed ticks, so pirates go the same way at any frame rate and
+// however fast the wave is played. Rendering frames fall between ticks, so a
+// pirate is drawn part way between where the last two ticks put it.
+pub const WAVE_TICK: f32 = 1.0 / 60.0;
+
+pub const SPAWN_INTERVAL: f32 = 2.0;
+const ENDLESS_PIRATES_PER_WAVE: u32 = 5;
+
+pub const GOLD_PICKUP_DISTANCE: f32 = 2.0;
 
-#[derive(PartialEq)]
+// how close pirates get before the one behind waits its turn
+pub const PIRATE_SPACING: f32 = TILE_SIZE * 0.6;
+
+// oxygen per second for every tree a pirate is standing next to
+pub const TREE_OXYGEN_GAIN: f32 = 30.0;
+
+// below this share of its starting oxygen a pirate panics and runs for air
+pub const PANIC_OXYGEN: f32 = 0.3;
+// and it only calms down again once it has this much back
+pub const CALM_OXYGEN: f32 = 0.6;
+// panic lasts until the pirate calms down, not for a set time
+pub const PANIC_HASTE: StatusEffect = StatusEffect {
+    kind: EffectKind::Haste,
+    source: EffectSource::Panic,
+    magnitude: 0.5,
+    remaining: f32::INFINITY,
+};
+
+// pirates that make it off the ship alive come back next wave with bigger lungs
+pub const VETERAN_OXYGEN_BONUS: f32 = 1.25;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum PirateState {
     PathingGold,
     PathingExit,
+    // out of breath, heading for fresh air or the nearest way out
+    Panicking,
 }
 
 #[derive(Component)]
@@ -39,25 +86,97 @@ pub struct Pirate {
     state: PirateState,
     carrying_gold: bool,
     marked_for_despawn: bool,
+    // what the pirate came aboard with, morale is measured against it
+    starting_oxygen: f32,
+}
+
+impl Pirate {
+    pub fn is_marked_for_despawn(&self) -> bool {
+        self.marked_for_despawn
+    }
+
+    pub fn is_carrying_gold(&self) -> bool {
+        self.carrying_gold
+    }
+
+    pub fn is_panicking(&self) -> bool {
+        self.state == PirateState::Panicking
+    }
+}
+
+// Where the last two ticks put a pirate or ship. Its `Transform` is only where it
+// really is while ticks run, and is put back part way between the two for drawing.
+#[derive(Component)]
+pub struct TickPosition {
+    previous: Vec2,
+    current: Vec2,
+}
+
+impl TickPosition {
+    pub fn new(location: Vec2) -> Self {
+        Self {
+            previous: location,
+            current: location,
+        }
+    }
+
+    // for putting something straight somewhere, rather than moving it there
+    pub fn jump_to(&mut self, transform: &mut Transform, location: Vec2) {
+        *self = Self::new(location);
+        transform.translation.x = location.x;
+        transform.translation.y = location.y;
+    }
+}
+
+// Pirates walking between a docked ship and its boarding point. They are outside
+// the hold, so chains can't touch them.
+#[derive(Component)]
+pub struct OnGangplank {
+    pub target: Vec2,
+    // walking back up to the ship rather than down into the hold
+    pub leaving: bool,
+}
+
+// where a pirate is and how much right of way it has, for working out who waits
+pub struct CrowdMember {
+    pub location: Vec2,
+    pub exiting: bool,
+    // any fixed order works, it only breaks ties between otherwise equal pirates
+    pub order: u32,
 }
 
 #[derive(Component)]
 pub struct WaveState {
     pub pirates_per_wave: u32,
     pub pirates_spawned: u32,
+    pub movement_speed: f32,
+    pub oxygen: f32,
+    pub boarding_point: BoardingPoint,
+    // how many of this spawner's pirates survived an earlier wave
+    pub veterans: u32,
+    // the last one aboard from this spawner is the captain
+    pub captain: bool,
 }
 
 #[derive(Resource, Default)]
 pub struct WaveNumber(pub u32);
 
-fn grid_coord_to_transform(p: &Point) -> Vec2 {
+// pirates that escaped alive since the last wave started, waiting to come back
+#[derive(Resource, Default)]
+pub struct Veterans(pub u32);
+
+// whether the wave in progress has a captain, for a bigger prize once it's over
+#[derive(Resource, Default)]
+pub struct BossWave(pub bool);
+
+pub fn grid_coord_to_transform(p: &Point) -> Vec2 {
     Vec2::new(
         GRID_X_START + p.x as f32 * TILE_SIZE,
         GRID_Y_START + p.y as f32 * TILE_SIZE,
     )
 }
 
-fn vec_to_grid_coord(v: &Vec2) -> Point {
+pub fn vec_to_grid_coord(v: &Vec2) -> Point {
     Point {
         x: ((v.x - GRID_X_START + TILE_SIZE / 2.0) / TILE_SIZE) as i32,
         y: ((v.y - GRID_Y_START + TILE_SIZE / 2.0) / TILE_SIZE) as i32,
@@ -80,30 +199,207 @@ fn find_closest_point_idx(points: &[Point], location: Vec2) -> usize {
     closest_idx
 }
 
-fn find_closest_gold(
-    gold_tiles: Query<(Entity, &Transform), (Without<Pirate>, With<Gold>)>,
+pub fn find_closest<T>(
+    candidates: impl Iterator<Item = (T, Vec2)>,
     location: Vec2,
-) -> Option<(Entity, Transform)> {
+) -> Option<(T, Vec2)> {
     let mut closest_distance: f32 = 100000000000.0;
-    let mut result: Option<(Entity, Transform)> = None;
+    let mut result: Option<(T, Vec2)> = None;
 
-    for (entity, transform) in gold_tiles.iter() {
-        let distance: f32 = (transform.translation.xy() - location).length();
+    for (candidate, candidate_location) in candidates {
+        let distance: f32 = (candidate_location - location).length();
         if distance < closest_distance {
             closest_distance = distance;
-            result = Some((entity, *transform));
+            result = Some((candidate, candidate_location));
         }
     }
 
     result
 }
 
+fn find_closest_gold(
+    gold_tiles: Query<(Entity, &Transform), (Without<Pirate>, With<Gold>)>,
+    claimed: &[Entity],
+    location: Vec2,
+) -> Option<(Entity, Vec2)> {
+    find_closest(
+        gold_tiles
+            .iter()
+            .filter(|(entity, _)| !claimed.contains(entity))
+            .map(|(entity, transform)| (entity, transform.translation.xy())),
+        location,
+    )
+}
+
+// the next point a pirate at `location` should walk to on its way to `end`
+pub fn path_target(pathing_grid: &PathingGrid, location: Vec2, end: Point) -> Vec2 {
+    let start = vec_to_grid_coord(&location);
+    let path: Option<Vec<Point>> = pathing_grid.get_path_single_goal(start, end, false);
+
+    match &path {
+        Some(val) => {
+            let closest_point_index: usize = find_closest_point_idx(val, location);
+
+            let mut target_point = val[val.len() - 1];
+            if closest_point_index < val.len() - 1 {
+                target_point = val[closest_point_index + 1];
+            }
+
+            grid_coord_to_transform(&target_point)
+        }
+        None => grid_coord_to_transform(&end),
+    }
+}
+
+// move up to `travel` towards `target`, snapping onto it rather than overshooting
+pub fn step_towards(location: Vec2, target: Vec2, travel: f32) -> Vec2 {
+    let direction_vec: Vec2 = target - location;
+    if direction_vec.length() < travel {
+        target
+    } else {
+        location + direction_vec.normalize() * travel
+    }
+}
+
+// move `travel` along the path to `end`, turning corners rather than stopping at
+// them, so one long step covers the same ground as several short ones however
+// fast the wave is going
+pub fn follow_path(pathing_grid: &PathingGrid, location: Vec2, end: Point, travel: f32) -> Vec2 {
+    let mut location = location;
+    let mut travel = travel;
+    while travel > 0.0 {
+        let target = path_target(pathing_grid, location, end);
+        let step = location.distance(target).min(travel);
+        if step <= 0.0 {
+            break;
+        }
+        location = step_towards(location, target, travel);
+        travel -= step;
+    }
+    location
+}
+
+// the candidate a pirate at `location` can reach in the fewest steps
+pub fn nearest_reachable(
+    pathing_grid: &PathingGrid,
+    location: Vec2,
+    candidates: impl Iterator<Item = Point>,
+) -> Option<Point> {
+    let start = vec_to_grid_coord(&location);
+    candidates
+        .filter_map(|point| {
+            let path = pathing_grid.get_path_single_goal(start, point, false)?;
+            Some((path.len(), point))
+        })
+        .min_by_key(|(steps, _)| *steps)
+        .map(|(_, point)| point)
+}
+
+// the exit a pirate at `location` can reach in the fewest steps, falling back to
+// the first one if every exit has been cut off
+pub fn nearest_exit(pathing_grid: &PathingGrid, location: Vec2, exits: &[BoardingPoint]) -> Point {
+    nearest_reachable(pathing_grid, location, exits.iter().map(|exit| exit.point))
+        .unwrap_or(exits[0].point)
+}
+
+// where a panicking pirate runs: an exit, or an open tile next to a tree if that's closer
+pub fn panic_target(
+    pathing_grid: &PathingGrid,
+    location: Vec2,
+    exits: &[BoardingPoint],
+    fresh_air: &[Point],
+) -> Point {
+    nearest_reachable(
+        pathing_grid,
+        location,
+        exits
+            .iter()
+            .map(|exit| exit.point)
+            .chain(fresh_air.iter().copied()),
+    )
+    .unwrap_or(exits[0].point)
+}
+
+// the open tiles beside each tree, where a pirate can catch its breath
+pub fn fresh_air_points(
+    pathing_grid: &PathingGrid,
+    trees: impl Iterator<Item = Tile>,
+) -> Vec<Point> {
+    let mut points = Vec::new();
+    for tree in trees {
+        for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+            let point = Point {
+                x: tree.x + dx,
+                y: tree.y + dy,
+            };
+            if (0..GRID_WIDTH).contains(&point.x)
+                && (0..GRID_HEIGHT).contains(&point.y)
+                && !pathing_grid.get(point.x as usize, point.y as usize)
+                && !points.contains(&point)
+            {
+                points.push(point);
+            }
+        }
+    }
+    points
+}
+
+// A pirate panics once its air runs low and only settles down once it has recovered
+// well past that, so it doesn't flip back and forth at the threshold
+pub fn next_morale(state: PirateState, oxygen: f32, starting_oxygen: f32) -> Option<PirateState> {
+    match state {
+        PirateState::Panicking if oxygen >= starting_oxygen * CALM_OXYGEN => {
+            Some(PirateState::PathingGold)
+        }
+        PirateState::Panicking => None,
+        _ if oxygen < starting_oxygen * PANIC_OXYGEN => Some(PirateState::Panicking),
+        _ => None,
+    }
+}
+
+// A pirate waits for anyone just ahead of it with right of way: pirates on their way
+// out go first, otherwise the lower order does. Someone always has right of way over
+// everyone, so a queue can't lock up.
+pub fn must_give_way(pirate: &CrowdMember, target: Vec2, crowd: &[CrowdMember]) -> bool {
+    let heading = target - pirate.location;
+    crowd.iter().any(|other| {
+        let offset = other.location - pirate.location;
+        offset.length() < PIRATE_SPACING
+            && offset.dot(heading) > 0.0
+            && (other.exiting, Reverse(other.order)) > (pirate.exiting, Reverse(pirate.order))
+    })
+}
+
+// every boarding point still has a way through to the gold
+pub fn hold_reachable(pathing_grid: &PathingGrid, boarding_points: &[BoardingPoint]) -> bool {
+    boarding_points.iter().all(|boarding_point| {
+        let point = boarding_point.point;
+        !pathing_grid.get(point.x as usize, point.y as usize)
+            && pathing_grid
+                .get_path_single_goal(point, HOLD_POINT, false)
+                .is_some()
+    })
+}
+
+// whether a pirate is close enough to a chain or tree to be affected by it
+pub fn in_breathing_range(pirate_pos: Vec2, source_pos: Vec2) -> bool {
+    let dx = (source_pos.x - pirate_pos.x).abs();
+    let dy = (source_pos.y - pirate_pos.y).abs();
+
+    // add a little buffer
+    dx <= TILE_SIZE * 1.2 && dy <= TILE_SIZE * 1.2
+}
+
 pub fn get_pathing_grid(chain_segs: Query<&Obstacle>) -> PathingGrid {
+    pathing_grid_from_tiles(chain_segs.iter().map(|chain_seg| chain_seg.tile))
+}
+
+pub fn pathing_grid_from_tiles(obstacles: impl Iterator<Item = Tile>) -> PathingGrid {
     let mut pathing_grid: PathingGrid = PathingGrid::new(29, 11, false);
     pathing_grid.allow_diagonal_move = false;
 
-    for chain_seg in chain_segs.iter() {
-        pathing_grid.set(chain_seg.tile.x as usize, chain_seg.tile.y as usize, true);
+    for tile in obstacles {
+        pathing_grid.set(tile.x as usize, tile.y as usize, true);
     }
 
     // set the back wall
@@ -119,99 +415,171 @@ pub fn get_pathing_grid(chain_segs: Query<&Obstacle>) -> PathingGrid {
     pathing_grid
 }
 
-fn pirate_movement_system(
+pub fn pirate_movement_system(
+    mut commands: Commands,
     time: Res<Time>,
-    mut pirates: Query<(&mut Pirate, &mut Transform, &MovementSpeed, Option<&Sticky>)>,
+    mut pirates: Query<
+        (
+            Entity,
+            &mut Pirate,
+            &mut Transform,
+            &MovementSpeed,
+            &mut StatusEffects,
+        ),
+        Without<OnGangplank>,
+    >,
     q_obstacles: Query<&Obstacle>,
+    q_trees: Query<&Obstacle, With<Tree>>,
     gold_tiles: Query<(Entity, &Transform), (Without<Pirate>, With<Gold>)>,
+    q_fools_gold: Query<(), With<FoolsGold>>,
+    q_ships: Query<(&Ship, &Transform), Without<Pirate>>,
+    boarding_points: Res<BoardingPoints>,
+    mut veterans: ResMut<Veterans>,
     mut event_gold_picked_up: EventWriter<GoldBarCollected>,
     mut event_gold_lost: EventWriter<GoldBarLost>,
 ) {
     let pathing_grid = get_pathing_grid(q_obstacles);
-
-    for (mut pirate, mut transform, speed, sticky) in pirates.iter_mut() {
+    let fresh_air = fresh_air_points(&pathing_grid, q_trees.iter().map(|tree| tree.tile));
+    // pirates that escaped or died this tick are gone before the next one
+    let crowd = pirates
+        .iter()
+        .filter(|(_, pirate, _, _, _)| !pirate.marked_for_despawn)
+        .map(|(entity, pirate, transform, _, _)| CrowdMember {
+            location: transform.translation.xy(),
+            exiting: pirate.state != PirateState::PathingGold,
+            order: entity.index(),
+        })
+        .collect::<Vec<_>>();
+
+    // gold picked up this tick is only despawned once the tick is over, so it
+    // can't be picked up twice
+    let mut claimed = Vec::new();
+    for (entity, mut pirate, mut transform, speed, mut effects) in pirates.iter_mut() {
+        if pirate.marked_for_despawn {
+            continue;
+        }
         let pirate_location = transform.translation.xy();
-        let pirate_point = vec_to_grid_coord(&pirate_location);
+        let modifiers = effects.modifiers();
 
-        let nearest_gold_point = match find_closest_gold(gold_tiles, pirate_location) {
-            Some((entity, transform)) => {
-                let nearest_gold_location = transform.translation.xy();
+        let nearest_gold_point = match find_closest_gold(gold_tiles, &claimed, pirate_location) {
+            Some((entity, nearest_gold_location)) => {
                 let nearest_gold_point = vec_to_grid_coord(&nearest_gold_location);
 
-                if !pirate.carrying_gold && pirate_location.distance(nearest_gold_location) < 2.0 {
+                // a panicking pirate has no time to stop for gold
+                if !pirate.carrying_gold
+                    && pirate.state != PirateState::Panicking
+                    && pirate_location.distance(nearest_gold_location) < GOLD_PICKUP_DISTANCE
+                {
                     pirate.state = PirateState::PathingExit;
                     let tile = Tile {
                         x: nearest_gold_point.x,
                         y: nearest_gold_point.y,
                     };
                     event_gold_picked_up.write(GoldBarCollected { tile, entity });
+                    claimed.push(entity);
                     pirate.carrying_gold = true;
+                    if q_fools_gold.contains(entity) {
+                        apply_effect(&mut effects, FOOLS_GOLD_CONFUSION);
+                    }
                 }
 
                 nearest_gold_point
             }
             None => {
-                pirate.state = PirateState::PathingExit;
-                BOAT_POINT
+                if pirate.state == PirateState::PathingGold {
+                    pirate.state = PirateState::PathingExit;
+                }
+                boarding_points.open()[0].point
             }
         };
 
-        let start: Point;
-        let end: Point;
-        match pirate.state {
-            PirateState::PathingGold => {
-                start = pirate_point;
-                end = nearest_gold_point;
+        // confused pirates head the wrong way until it wears off
+        let end = match (pirate.state, modifiers.confused) {
+            (PirateState::PathingGold, false) | (PirateState::PathingExit, true) => {
+                nearest_gold_point
             }
-            PirateState::PathingExit => {
-                start = pirate_point;
-                end = BOAT_POINT;
+            (PirateState::PathingExit, false) | (PirateState::PathingGold, true) => {
+                nearest_exit(&pathing_grid, pirate_location, boarding_points.open())
             }
-        }
-
-        let path: Option<Vec<Point>> = pathing_grid.get_path_single_goal(start, end, false);
-
-        let target_vec: Vec2 = match &path {
-            Some(val) => {
-                let closest_point_index: usize =
-                    find_closest_point_idx(val, transform.translation.xy());
+            (PirateState::Panicking, _) => panic_target(
+                &pathing_grid,
+                pirate_location,
+                boarding_points.open(),
+                &fresh_air,
+            ),
+        };
 
-                let mut target_point = val[val.len() - 1];
-                if closest_point_index < val.len() - 1 {
-                    target_point = val[closest_point_index + 1];
-                }
+        let target_vec = path_target(&pathing_grid, pirate_location, end);
 
-                grid_coord_to_transform(&target_point)
-            }
-            None => grid_coord_to_transform(&end),
+        let me = CrowdMember {
+            location: pirate_location,
+            exiting: pirate.state != PirateState::PathingGold,
+            order: entity.index(),
         };
-
-        let speed = if sticky.is_some() {
-            speed.0 * 0.5
+        let speed = if must_give_way(&me, target_vec, &crowd) {
+            0.0
         } else {
-            speed.0
+            speed.0 * modifiers.speed
         };
 
-        let mut direction_vec: Vec2 = target_vec - pirate_location;
-        let distance = direction_vec.length();
         let travel: f32 = speed * time.delta().as_secs_f32();
-        direction_vec = direction_vec.normalize() * travel;
-        if distance < travel {
-            transform.translation.x = target_vec.x;
-            transform.translation.y = target_vec.y;
-        } else {
-            transform.translation.x += direction_vec.x;
-            transform.translation.y += direction_vec.y;
-        }
+        let new_location = follow_path(&pathing_grid, pirate_location, end, travel);
+        transform.translation.x = new_location.x;
+        transform.translation.y = new_location.y;
 
-        let new_location = transform.translation.xy();
         let new_point = vec_to_grid_coord(&new_location);
 
-        if pirate.state == PirateState::PathingExit && new_point == BOAT_POINT {
+        // a panicking pirate that made it to fresh air stays put until it has calmed down
+        let at_exit = boarding_points
+            .open()
+            .iter()
+            .any(|boarding_point| boarding_point.point == end);
+        if pirate.state != PirateState::PathingGold && at_exit && new_point == end {
+            veterans.0 += 1;
             if pirate.carrying_gold {
                 event_gold_lost.write(GoldBarLost);
+                pirate.carrying_gold = false;
             }
+
+            // pirates escaping by ship walk back up its gangplank, a hatch takes them straight away
+            match q_ships.iter().find(|(ship, _)| ship.boarding_point == end) {
+                Some((_, ship_transform)) => {
+                    commands.entity(entity).insert(OnGangplank {
+                        target: ship_transform.translation.xy(),
+                        leaving: true,
+                    });
+                }
+                None => pirate.marked_for_despawn = true,
+            }
+        }
+    }
+}
+
+fn pirate_gangplank_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut pirates: Query<(
+        Entity,
+        &mut Pirate,
+        &mut Transform,
+        &MovementSpeed,
+        &OnGangplank,
+    )>,
+) {
+    for (entity, mut pirate, mut transform, speed, gangplank) in pirates.iter_mut() {
+        let travel = speed.0 * time.delta().as_secs_f32();
+        let new_location = step_towards(transform.translation.xy(), gangplank.target, travel);
+        transform.translation.x = new_location.x;
+        transform.translation.y = new_location.y;
+
+        if new_location != gangplank.target {
+            continue;
+        }
+
+        if gangplank.leaving {
             pirate.marked_for_despawn = true;
+        } else {
+            commands.entity(entity).remove::<OnGangplank>();
         }
     }
 }
@@ -220,86 +588,138 @@ pub fn pirate_spawn_system(
     mut commands: Commands,
     time: Res<Time>,
     mut spawners: Query<(Entity, &mut SpawnTimer, &mut WaveState), With<Spawner>>,
-    mut wave_number: ResMut<WaveNumber>,
-    asset_server: Res<AssetServer>,
+    q_ships: Query<(&Ship, &Transform)>,
 ) {
     for (entity, mut timer, mut wave_state) in spawners.iter_mut() {
+        let boarding_point = wave_state.boarding_point;
+
+        // pirates wait on deck until their ship has docked
+        let spawn_location = match boarding_point.kind {
+            BoardingKind::Ship => {
+                let Some((_, ship_transform)) = q_ships.iter().find(|(ship, ship_transform)| {
+                    ship.boarding_point == boarding_point.point && ship.is_docked(ship_transform)
+                }) else {
+                    continue;
+                };
+                ship_transform.translation.xy()
+            }
+            BoardingKind::Hatch => grid_coord_to_transform(&boarding_point.point),
+        };
+
         timer.0.tick(time.delta());
         if timer.0.just_finished() && wave_state.pirates_spawned < wave_state.pirates_per_wave {
-            let y_coord: f32 = (2.6 - wave_state.pirates_spawned as f32) * 100.0;
-
-            let movement_speed = wave_number.0 as f32 * 50.0 + 200.0;
-            let oxygen_level = wave_number.0 as f32 * 10.0 + 100.0;
-
-            commands.spawn((
+            // veterans come aboard first
+            let is_captain =
+                wave_state.captain && wave_state.pirates_spawned + 1 == wave_state.pirates_per_wave;
+            let (oxygen, movement_speed, scale) = if is_captain {
+                (
+                    wave_state.oxygen * CAPTAIN_OXYGEN_MULTIPLIER,
+                    wave_state.movement_speed * CAPTAIN_SPEED_MULTIPLIER,
+                    CAPTAIN_SCALE,
+                )
+            } else if wave_state.pirates_spawned < wave_state.veterans {
+                (
+                    wave_state.oxygen * VETERAN_OXYGEN_BONUS,
+                    wave_state.movement_speed,
+                    0.5,
+                )
+            } else {
+                (wave_state.oxygen, wave_state.movement_speed, 0.5)
+            };
+            let mut e_pirate = commands.spawn((
                 Pirate {
                     state: PirateState::PathingGold,
                     carrying_gold: false,
                     marked_for_despawn: false,
+                    starting_oxygen: oxygen,
                 },
-                Sprite {
-                    image: asset_server.load("images/pirate.png"),
-                    ..default()
-                },
-                Transform::from_xyz(GRID_X_START, y_coord, 4.0).with_scale(vec3(0.5, 0.5, 0.5)),
+                // the sprite goes on a child, so it can be animated without moving the pirate
+                Visibility::default(),
+                Transform::from_translation(spawn_location.extend(4.0))
+                    .with_scale(Vec3::splat(scale)),
+                TickPosition::new(spawn_location),
                 MovementSpeed(movement_speed),
-                Oxygen(oxygen_level),
+                Oxygen(oxygen),
+                StatusEffects::default(),
             ));
+            if boarding_point.kind == BoardingKind::Ship {
+                e_pirate.insert(OnGangplank {
+                    target: grid_coord_to_transform(&boarding_point.point),
+                    leaving: false,
+                });
+            }
+            if is_captain {
+                e_pirate.insert(Captain::new(
+                    oxygen,
+                    wave_state.movement_speed,
+                    wave_state.oxygen,
+                ));
+            }
 
             wave_state.pirates_spawned += 1;
             if wave_state.pirates_spawned == wave_state.pirates_per_wave {
                 commands.entity(entity).despawn();
-                wave_number.0 += 1;
             }
         }
     }
 }
 
-fn pirate_oxygen_system(
+// chains have already drained the pirates by now, see `drain::chain_drain_system`
+pub fn pirate_oxygen_system(
     time: Res<Time>,
-    mut q_pirates: Query<(&mut Pirate, &mut Oxygen, &Transform)>,
-    q_chain: Query<&Transform, With<ChainSegment>>,
-    q_trees: Query<&Transform, With<Tree>>,
+    mut q_pirates: Query<
+        (&mut Pirate, &mut Oxygen, &mut StatusEffects, &Transform),
+        Without<OnGangplank>,
+    >,
+    q_trees: Query<&Transform, Or<(With<Tree>, With<OxygenTank>)>>,
     mut evw_pirate_death: EventWriter<PirateDeath>,
     mut evw_gold_dropped: EventWriter<GoldBarDropped>,
 ) {
-    for (mut pirate, mut oxygen, transform) in q_pirates.iter_mut() {
+    for (mut pirate, mut oxygen, mut effects, transform) in q_pirates.iter_mut() {
+        let pirate_pos = transform.translation.xy();
         for tree in q_trees.iter() {
-            let tree_pos = tree.translation.xy();
-            let pirate_pos = transform.translation.xy();
-            let dx = (tree_pos.x - pirate_pos.x).abs();
-            let dy = (tree_pos.y - pirate_pos.y).abs();
-
-            // add a little buffer
-            if dx <= TILE_SIZE * 1.2 && dy <= TILE_SIZE * 1.2 {
-                oxygen.0 += 30.0 * time.delta().as_secs_f32();
+            if in_breathing_range(pirate_pos, tree.translation.xy()) {
+                oxygen.0 += TREE_OXYGEN_GAIN * time.delta().as_secs_f32();
             }
         }
-        for chain_seg in q_chain.iter() {
-            // if pirate is next to a chain, deplete oxygen
-            let chain_pos = chain_seg.translation.xy();
-            let pirate_pos = transform.translation.xy();
-            let dx = (chain_pos.x - pirate_pos.x).abs();
-            let dy = (chain_pos.y - pirate_pos.y).abs();
-
-            // add a little buffer
-            if dx <= TILE_SIZE * 1.2 && dy <= TILE_SIZE * 1.2 {
-                let pirate_was_alive = oxygen.0 > 0.0;
-                oxygen.0 -= 10.0 * time.delta().as_secs_f32();
-                if pirate_was_alive && oxygen.0 <= 0.0 {
-                    pirate.marked_for_despawn = true;
-                    evw_pirate_death.write(PirateDeath {});
-                    if pirate.carrying_gold {
-                        let pirate_point = vec_to_grid_coord(&pirate_pos);
-                        evw_gold_dropped.write(GoldBarDropped {
-                            tile: Tile {
-                                x: pirate_point.x,
-                                y: pirate_point.y,
-                            },
-                        });
-                    }
-                    break;
+        if pirate.marked_for_despawn {
+            continue;
+        }
+        if oxygen.0 <= 0.0 {
+            pirate.marked_for_despawn = true;
+            evw_pirate_death.write(PirateDeath {
+                location: pirate_pos,
+                scale: transform.scale.x,
+            });
+            if pirate.carrying_gold {
+                pirate.carrying_gold = false;
+                let pirate_point = vec_to_grid_coord(&pirate_pos);
+                evw_gold_dropped.write(GoldBarDropped {
+                    tile: Tile {
+                        x: pirate_point.x,
+                        y: pirate_point.y,
+                    },
+                });
+            }
+            continue;
+        }
+        if let Some(state) = next_morale(pirate.state, oxygen.0, pirate.starting_oxygen) {
+            pirate.state = state;
+            if state == PirateState::Panicking {
+                apply_effect(&mut effects, PANIC_HASTE);
+                // the gold only slows it down, so it gets dropped where the pirate stands
+                if pirate.carrying_gold {
+                    pirate.carrying_gold = false;
+                    let pirate_point = vec_to_grid_coord(&pirate_pos);
+                    evw_gold_dropped.write(GoldBarDropped {
+                        tile: Tile {
+                            x: pirate_point.x,
+                            y: pirate_point.y,
+                        },
+                    });
                 }
+            } else {
+                effects.remove_source(EffectSource::Panic);
             }
         }
     }
@@ -309,32 +729,167 @@ fn end_wave_system(
     q_pirates: Query<Entity, With<Pirate>>,
     q_spawner: Query<Entity, With<Spawner>>,
     q_gold_tiles: Query<Entity, With<Gold>>,
+    game_mode: Res<GameMode>,
+    wave_number: Res<WaveNumber>,
+    boss_wave: Res<BossWave>,
     mut next_state: ResMut<NextState<GameState>>,
     mut evw_wave_complete: EventWriter<WaveComplete>,
     mut evw_game_over: EventWriter<GameOver>,
+    mut evw_level_complete: EventWriter<LevelComplete>,
+    mut evw_puzzle_complete: EventWriter<PuzzleComplete>,
 ) {
-    if q_pirates.iter().count() == 0 && q_spawner.iter().count() == 0 {
-        if q_gold_tiles.iter().count() == 0 {
+    if q_pirates.iter().count() != 0 || q_spawner.iter().count() != 0 {
+        return;
+    }
+
+    let gold = q_gold_tiles.iter().count() as i32;
+
+    // puzzles are a single wave, and they fail as soon as gold is lost
+    if matches!(*game_mode, GameMode::Puzzle(_)) {
+        next_state.set(GameState::PuzzleResult);
+        evw_puzzle_complete.write(PuzzleComplete {
+            solved: gold >= TOTAL_GOLD_BARS,
+        });
+        return;
+    }
+    if gold == 0 {
+        next_state.set(GameState::GameOver);
+        evw_game_over.write(GameOver);
+        return;
+    }
+
+    // a campaign level is decided once its last wave is over
+    if let GameMode::Campaign(level) = *game_mode
+        && wave_number.0 as usize >= LEVELS[level].waves.len()
+    {
+        let stars = LEVELS[level].stars(gold);
+        if stars > 0 {
+            next_state.set(GameState::Victory);
+            evw_level_complete.write(LevelComplete { level, stars });
+        } else {
             next_state.set(GameState::GameOver);
             evw_game_over.write(GameOver);
-        } else {
-            next_state.set(GameState::Prize);
-            evw_wave_complete.write(WaveComplete);
         }
+        return;
     }
+
+    next_state.set(GameState::Prize);
+    evw_wave_complete.write(WaveComplete { boss: boss_wave.0 });
 }
 
-fn spawn_setup(mut commands: Commands, mut evr_wave_started: EventReader<WaveStarted>) {
+fn spawn_setup(
+    mut commands: Commands,
+    mut evr_wave_started: EventReader<WaveStarted>,
+    game_mode: Res<GameMode>,
+    mut wave_number: ResMut<WaveNumber>,
+    mut boarding_points: ResMut<BoardingPoints>,
+    mut veterans: ResMut<Veterans>,
+    mut boss_wave: ResMut<BossWave>,
+    library: Res<PuzzleLibrary>,
+    puzzles: Res<Assets<Puzzle>>,
+) {
     for _ in evr_wave_started.read() {
-        // Spawn spawner
-        commands.spawn((
-            Spawner,
-            SpawnTimer(Timer::from_seconds(SPAWN_INTERVAL, TimerMode::Repeating)),
-            WaveState {
-                pirates_per_wave: 5,
-                pirates_spawned: 0,
+        let wave = match *game_mode {
+            GameMode::Endless => WaveSpec {
+                pirates: ENDLESS_PIRATES_PER_WAVE,
+                movement_speed: wave_number.0 as f32 * 50.0 + 200.0,
+                oxygen: wave_number.0 as f32 * 10.0 + 100.0,
+                boarding_points: 1,
+                boss: (wave_number.0 + 1).is_multiple_of(BOSS_WAVE_INTERVAL),
             },
-        ));
+            GameMode::Campaign(level) => {
+                let waves = LEVELS[level].waves;
+                waves[(wave_number.0 as usize).min(waves.len() - 1)].clone()
+            }
+            GameMode::Puzzle(puzzle) => {
+                let Some(puzzle) = library.get(&puzzles, puzzle) else {
+                    continue;
+                };
+                puzzle.wave.clone()
+            }
+        };
+
+        // Spawn a spawner for every boarding point this wave uses, never opening one
+        // that would have nobody to send
+        let open = boarding_points.open_for_wave(wave.boarding_points.min(wave.pirates as usize));
+        // survivors are shared out the same way, so no spawner gets more than it sends
+        let wave_veterans = std::mem::take(&mut veterans.0).min(wave.pirates);
+        boss_wave.0 = wave.boss;
+        for (i, boarding_point) in open.iter().enumerate() {
+            // the captain follows the crew off the first ship
+            let captain = wave.boss && i == 0;
+            let pirates =
+                pirates_at_boarding_point(wave.pirates, open.len(), i) + u32::from(captain);
+            if pirates == 0 {
+                continue;
+            }
+
+            commands.spawn((
+                Spawner,
+                SpawnTimer(Timer::from_seconds(SPAWN_INTERVAL, TimerMode::Repeating)),
+                WaveState {
+                    pirates_per_wave: pirates,
+                    pirates_spawned: 0,
+                    movement_speed: wave.movement_speed,
+                    oxygen: wave.oxygen,
+                    boarding_point: *boarding_point,
+                    veterans: pirates_at_boarding_point(wave_veterans, open.len(), i),
+                    captain,
+                },
+            ));
+        }
+        wave_number.0 += 1;
+    }
+}
+
+// clear out any wave that was still running and start counting from the first wave
+fn on_wave_reset(
+    mut commands: Commands,
+    mut evr_wave_reset: EventReader<WaveReset>,
+    mut wave_number: ResMut<WaveNumber>,
+    mut veterans: ResMut<Veterans>,
+    q_pirates: Query<Entity, With<Pirate>>,
+    q_spawner: Query<Entity, With<Spawner>>,
+) {
+    if evr_wave_reset.is_empty() {
+        return;
+    }
+
+    evr_wave_reset.clear();
+
+    for entity in q_pirates.iter().chain(q_spawner.iter()) {
+        commands.entity(entity).despawn();
+    }
+    wave_number.0 = 0;
+    veterans.0 = 0;
+}
+
+// move pirates back to where the simulation has them before any ticks run
+fn restore_tick_positions(mut q_pirates: Query<(&TickPosition, &mut Transform)>) {
+    for (position, mut transform) in q_pirates.iter_mut() {
+        transform.translation.x = position.current.x;
+        transform.translation.y = position.current.y;
+    }
+}
+
+fn start_tick(mut q_pirates: Query<(&mut TickPosition, &Transform)>) {
+    for (mut position, transform) in q_pirates.iter_mut() {
+        position.previous = transform.translation.xy();
+    }
+}
+
+// and once they've run, draw them as far between the last two ticks as the
+// time left over is towards the next one
+fn interpolate_tick_positions(
+    time: Res<Time<Fixed>>,
+    mut q_pirates: Query<(&mut TickPosition, &mut Transform)>,
+) {
+    let t = time.overstep_fraction();
+    for (mut position, mut transform) in q_pirates.iter_mut() {
+        position.current = transform.translation.xy();
+        let drawn = position.previous.lerp(position.current, t);
+        transform.translation.x = drawn.x;
+        transform.translation.y = drawn.y;
     }
 }
 
@@ -348,23 +903,45 @@ fn despawn_pirates(mut commands: Commands, mut q_pirates: Query<(&Pirate, Entity
 
 pub fn plugin(app: &mut App) {
     app.init_resource::<WaveNumber>();
+    app.init_resource::<Veterans>();
+    app.init_resource::<BossWave>();
+    // a retried wave resets and restarts together, so clear the old wave out first
+    app.add_systems(Update, on_wave_reset.before(spawn_setup));
 
+    app.insert_resource(Time::<Fixed>::from_seconds(WAVE_TICK as f64));
     app.add_systems(
         Update,
+        spawn_setup.run_if(in_state(GameState::WaveInProgress)),
+    );
+    app.add_systems(
+        FixedUpdate,
         (
-            spawn_setup,
             pirate_spawn_system,
             pirate_movement_system,
+            pirate_gangplank_system,
             pirate_oxygen_system,
         )
+            .chain()
             .run_if(in_state(GameState::WaveInProgress)),
     );
+    app.add_systems(
+        RunFixedMainLoop,
+        (
+            restore_tick_positions.in_set(RunFixedMainLoopSystem::BeforeFixedMainLoop),
+            interpolate_tick_positions.in_set(RunFixedMainLoopSystem::AfterFixedMainLoop),
+        ),
+    );
+    app.add_systems(FixedPreUpdate, start_tick);
 
-    // We have to run these here to ensure that:
-    // 1. The spawner is created prior to checking if the wave is complete
-    // 2. The pirates are despawned safely after any other updates
+    // pirates that escaped or died go at the end of the tick they did it in, so the
+    // next tick never sees them however many ticks run in a frame
+    app.add_systems(
+        FixedPostUpdate,
+        despawn_pirates.run_if(in_state(GameState::WaveInProgress)),
+    );
+    // checked here so the spawner is created before the wave can count as complete
     app.add_systems(
         PostUpdate,
-        (end_wave_system, despawn_pirates).run_if(in_state(GameState::WaveInProgress)),
+        end_wave_system.run_if(in_state(GameState::WaveInProgress)),
     );
 }
@@ -0,0 +1,214 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::prelude::*;
+
+use crate::game::events::{PirateDeath, PirateFootstep};
+use crate::game::pirate::Pirate;
+use crate::game::tile::TILE_SIZE;
+
+// how far a pirate walks between frames of its walk cycle
+const STRIDE_LENGTH: f32 = TILE_SIZE * 0.35;
+
+// the walk cycle is a strip of frames side by side, and a foot lands on the even ones
+const WALK_FRAME_SIZE: UVec2 = UVec2::splat(128);
+const WALK_FRAMES: usize = 4;
+
+const PANIC_COLOR: Color = Color::srgb(1.0, 0.55, 0.55);
+
+const SUFFOCATION_TIME: f32 = 0.8;
+const SUFFOCATED_COLOR: Color = Color::srgb(0.45, 0.55, 1.0);
+
+// the pirate's walk cycle, on a child so it can be animated apart from the pirate
+#[derive(Component)]
+struct PirateBody;
+
+#[derive(Resource)]
+struct WalkSheet {
+    image: Handle<Image>,
+    layout: Handle<TextureAtlasLayout>,
+}
+
+// the bar a pirate is making off with, held over its head
+#[derive(Component)]
+struct CarriedGold;
+
+#[derive(Component)]
+struct WalkCycle {
+    last_location: Vec2,
+    // distance walked since the cycle last moved on a frame
+    stride: f32,
+    frame: usize,
+}
+
+// what's left of a pirate that ran out of air, played out before it disappears
+#[derive(Component)]
+struct Suffocating(Timer);
+
+fn load_walk_sheet(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+) {
+    commands.insert_resource(WalkSheet {
+        image: asset_server.load("images/pirate_walk.png"),
+        layout: layouts.add(TextureAtlasLayout::from_grid(
+            WALK_FRAME_SIZE,
+            WALK_FRAMES as u32,
+            1,
+            None,
+            None,
+        )),
+    });
+}
+
+fn on_pirate_added(
+    mut commands: Commands,
+    walk_sheet: Res<WalkSheet>,
+    q_pirates: Query<(Entity, &Transform), Added<Pirate>>,
+) {
+    for (entity, transform) in q_pirates.iter() {
+        commands
+            .entity(entity)
+            .insert(WalkCycle {
+                last_location: transform.translation.xy(),
+                stride: 0.0,
+                frame: 0,
+            })
+            .with_child((
+                PirateBody,
+                Sprite::from_atlas_image(
+                    walk_sheet.image.clone(),
+                    TextureAtlas {
+                        layout: walk_sheet.layout.clone(),
+                        index: 0,
+                    },
+                ),
+            ));
+    }
+}
+
+fn animate_walk(
+    mut q_pirates: Query<(&Pirate, &Transform, &mut WalkCycle, &Children)>,
+    mut q_bodies: Query<&mut Sprite, With<PirateBody>>,
+    mut evw_footstep: EventWriter<PirateFootstep>,
+) {
+    for (pirate, transform, mut walk_cycle, children) in q_pirates.iter_mut() {
+        let location = transform.translation.xy();
+        let moved = location - walk_cycle.last_location;
+        walk_cycle.last_location = location;
+
+        // faster pirates cover a stride sooner, so they step quicker too
+        walk_cycle.stride += moved.length();
+        if walk_cycle.stride >= STRIDE_LENGTH {
+            walk_cycle.stride -= STRIDE_LENGTH;
+            walk_cycle.frame = (walk_cycle.frame + 1) % WALK_FRAMES;
+            if walk_cycle.frame % 2 == 0 {
+                evw_footstep.write(PirateFootstep);
+            }
+        }
+        // standing still settles back onto both feet
+        if moved == Vec2::ZERO && walk_cycle.frame % 2 == 1 {
+            walk_cycle.frame = (walk_cycle.frame + 1) % WALK_FRAMES;
+            walk_cycle.stride = 0.0;
+        }
+
+        for child in children.iter() {
+            let Ok(mut sprite) = q_bodies.get_mut(child) else {
+                continue;
+            };
+            if let Some(atlas) = sprite.texture_atlas.as_mut() {
+                atlas.index = walk_cycle.frame;
+            }
+            // face the way the pirate is walking
+            if moved.x != 0.0 {
+                sprite.flip_x = moved.x < 0.0;
+            }
+            sprite.color = if pirate.is_panicking() {
+                PANIC_COLOR
+            } else {
+                Color::WHITE
+            };
+        }
+    }
+}
+
+fn show_carried_gold(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    q_pirates: Query<(Entity, &Pirate, &Children)>,
+    q_carried_gold: Query<(), With<CarriedGold>>,
+) {
+    for (entity, pirate, children) in q_pirates.iter() {
+        let carried = children
+            .iter()
+            .find(|child| q_carried_gold.contains(*child));
+        match (pirate.is_carrying_gold(), carried) {
+            (true, None) => {
+                commands.entity(entity).with_child((
+                    CarriedGold,
+                    Sprite {
+                        image: asset_server.load("images/gold_bar.png"),
+                        ..default()
+                    },
+                    Transform::from_xyz(0.0, 45.0, 1.0),
+                ));
+            }
+            (false, Some(child)) => commands.entity(child).despawn(),
+            _ => {}
+        }
+    }
+}
+
+fn spawn_suffocating_pirate(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut evr_pirate_death: EventReader<PirateDeath>,
+) {
+    for event in evr_pirate_death.read() {
+        commands.spawn((
+            Suffocating(Timer::from_seconds(SUFFOCATION_TIME, TimerMode::Once)),
+            Sprite {
+                image: asset_server.load("images/pirate.png"),
+                ..default()
+            },
+            Transform::from_translation(event.location.extend(4.0))
+                .with_scale(Vec3::splat(event.scale)),
+        ));
+    }
+}
+
+// a last few gasps, then the pirate keels over and fades away
+fn animate_suffocation(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut q_suffocating: Query<(Entity, &mut Suffocating, &mut Sprite, &mut Transform)>,
+) {
+    for (entity, mut suffocating, mut sprite, mut transform) in q_suffocating.iter_mut() {
+        suffocating.0.tick(time.delta());
+        if suffocating.0.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        let t = suffocating.0.fraction();
+        let gasp = (t * 30.0).sin() * 0.15 * (1.0 - t);
+        transform.rotation = Quat::from_rotation_z(gasp - FRAC_PI_2 * t);
+        sprite.color = Color::WHITE
+            .mix(&SUFFOCATED_COLOR, t)
+            .with_alpha(1.0 - t * t);
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(Startup, load_walk_sheet);
+    app.add_systems(
+        Update,
+        (
+            on_pirate_added,
+            animate_walk,
+            show_carried_gold,
+            spawn_suffocating_pirate,
+            animate_suffocation,
+        ),
+    );
+}
@@ -1,149 +1,214 @@
 use bevy::prelude::*;
 
 use crate::game::chain::{
-    CHAIN_BUTTON_SIZE, ChainButton, ChainButtonStock, MainInventoryChainButton, spawn_chain_button,
+    CHAIN_BUTTON_SIZE, ChainButton, ChainMaterial, InventoryEntry, MainInventoryChainButton,
+    add_to_inventory,
+};
+use crate::game::events::{InventoryReplaced, PrizeCollected, WaveComplete};
+use crate::game::game_state::{GameMode, GameState};
+use crate::game::ui::{
+    ButtonClicked, ButtonColors, PANEL_TEXT_COLOR, UiButton, label, modal, panel,
 };
-use crate::game::events::{PrizeCollected, WaveComplete};
-use crate::game::game_state::GameState;
-use crate::game::mouse::MousePos;
 
 const PRIZE_WINDOW_WIDTH: f32 = 1400.0;
 const PRIZE_WINDOW_HEIGHT: f32 = 800.0;
 
+// seeing off a captain is worth twice the chains
+const BOSS_PRIZE_MULTIPLIER: u32 = 2;
+
+const PRIZE_SPACING: f32 = 300.0;
+
+// stainless is cheap, so it comes by the handful
+const PRIZES: [InventoryEntry; 4] = [
+    InventoryEntry {
+        stock: 1,
+        length: 9,
+        material: ChainMaterial::Iron,
+    },
+    InventoryEntry {
+        stock: 2,
+        length: 4,
+        material: ChainMaterial::RustyIron,
+    },
+    InventoryEntry {
+        stock: 1,
+        length: 4,
+        material: ChainMaterial::Anchor,
+    },
+    InventoryEntry {
+        stock: 6,
+        length: 2,
+        material: ChainMaterial::Stainless,
+    },
+];
+
 #[derive(Component)]
 pub struct PrizeWindow;
 
-#[derive(Component, Debug, Default)]
+#[derive(Component, Debug)]
 pub struct PrizeWindowChainButton;
 
+// a chain option drawn the same way as the chain buttons in the inventory, with
+// its stock top right, its length bottom left and its material underneath
+fn spawn_prize_button(
+    parent: &mut ChildSpawnerCommands,
+    entry: &InventoryEntry,
+    asset_server: &AssetServer,
+) {
+    let InventoryEntry {
+        stock,
+        length,
+        material,
+    } = *entry;
+    let corner_text = |text: String, color: Color, left: f32, top: f32| {
+        (
+            label(asset_server, text, 50.0, color),
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(left),
+                top: Val::Px(top),
+                ..default()
+            },
+        )
+    };
+
+    parent.spawn((
+        PrizeWindowChainButton,
+        ChainButton {
+            stock,
+            length,
+            material,
+        },
+        UiButton {
+            colors: ButtonColors::IMAGE,
+        },
+        ImageNode::new(asset_server.load("images/chain.png")),
+        Node {
+            width: Val::Px(CHAIN_BUTTON_SIZE),
+            height: Val::Px(CHAIN_BUTTON_SIZE),
+            ..default()
+        },
+        children![
+            corner_text(
+                format!("{}", stock),
+                Color::linear_rgb(0.0, 0.0, 1.0),
+                50.0,
+                -30.0
+            ),
+            corner_text(
+                format!("{}", length),
+                Color::linear_rgb(1.0, 0.0, 0.0),
+                -10.0,
+                30.0
+            ),
+            (
+                label(asset_server, material.name(), 24.0, material.tint()),
+                TextShadow::default(),
+                TextLayout::new_with_justify(JustifyText::Center),
+                Node {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px((CHAIN_BUTTON_SIZE - PRIZE_SPACING) / 2.0),
+                    top: Val::Px(CHAIN_BUTTON_SIZE + 10.0),
+                    width: Val::Px(PRIZE_SPACING),
+                    ..default()
+                },
+            ),
+        ],
+    ));
+}
+
 fn on_wave_complete(
     mut evr_wave_complete: EventReader<WaveComplete>,
-    asset_server: ResMut<AssetServer>,
+    asset_server: Res<AssetServer>,
     mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
 ) {
     if evr_wave_complete.is_empty() {
         return;
     }
 
-    evr_wave_complete.clear();
-
-    let rect = Rectangle::new(PRIZE_WINDOW_WIDTH, PRIZE_WINDOW_HEIGHT);
-    let color = Color::linear_rgba(0.8, 0.8, 0.8, 1.0);
-
-    // spawn prize window
-    let e_prize_window = commands
-        .spawn((
-            PrizeWindow,
-            Mesh2d(meshes.add(rect)),
-            MeshMaterial2d(materials.add(color)),
-            Transform::from_xyz(0.0, 0.0, 15.0),
-        ))
-        .id();
-
-    commands.entity(e_prize_window).with_children(|parent| {
-        parent.spawn((
-            Text2d::new("Choose New Chains"),
-            TextFont {
-                font: asset_server.load("fonts/FiraSans-Bold.ttf"),
-                font_size: 50.0,
-                ..default()
-            },
-            TextColor(Color::linear_rgb(0.0, 0.0, 0.0)),
-            Transform::from_xyz(0.0, 300.0, 0.5),
-        ));
-    });
-
-    // spawn chain options
-    spawn_chain_button::<PrizeWindowChainButton>(
-        &mut commands,
-        e_prize_window,
-        1,
-        9,
-        &asset_server,
-        Vec2::new(-400.0, 0.0),
-    );
+    let boss = evr_wave_complete.read().any(|event| event.boss);
+    let (title, multiplier) = if boss {
+        ("Captain Repelled! Choose New Chains", BOSS_PRIZE_MULTIPLIER)
+    } else {
+        ("Choose New Chains", 1)
+    };
 
-    spawn_chain_button::<PrizeWindowChainButton>(
-        &mut commands,
-        e_prize_window,
-        2,
-        4,
-        &asset_server,
-        Vec2::new(0.0, 0.0),
-    );
+    commands
+        .spawn((PrizeWindow, modal()))
+        .with_children(|parent| {
+            parent
+                .spawn(panel(Vec2::new(PRIZE_WINDOW_WIDTH, PRIZE_WINDOW_HEIGHT)))
+                .with_children(|parent| {
+                    parent.spawn(label(&asset_server, title, 50.0, PANEL_TEXT_COLOR));
 
-    spawn_chain_button::<PrizeWindowChainButton>(
-        &mut commands,
-        e_prize_window,
-        3,
-        2,
-        &asset_server,
-        Vec2::new(400.0, 0.0),
-    );
+                    // spawn chain options
+                    parent
+                        .spawn(Node {
+                            column_gap: Val::Px(PRIZE_SPACING - CHAIN_BUTTON_SIZE),
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            for prize in PRIZES.iter() {
+                                spawn_prize_button(
+                                    parent,
+                                    &InventoryEntry {
+                                        stock: prize.stock * multiplier,
+                                        ..prize.clone()
+                                    },
+                                    &asset_server,
+                                );
+                            }
+                        });
+                });
+        });
 }
 
-fn mouse_down_on_chain_button_in_prize_window(
+fn prize_button_click(
     mut commands: Commands,
-    mouse_pos: Res<MousePos>,
-    mouse_button: Res<ButtonInput<MouseButton>>,
+    mut evr_clicked: EventReader<ButtonClicked>,
     mut state: ResMut<NextState<GameState>>,
-    mut q_chain_buttons: Query<
-        (&ChainButton, &mut Sprite, &GlobalTransform),
-        (
-            With<PrizeWindowChainButton>,
-            Without<MainInventoryChainButton>,
-        ),
-    >,
-    mut q_chain_button_in_inventory: Query<
-        (&mut ChainButton, &Children),
+    game_mode: Res<GameMode>,
+    q_prize_buttons: Query<&ChainButton, With<PrizeWindowChainButton>>,
+    q_chain_button_in_inventory: Query<
+        (&ChainButton, &Transform),
         (
             With<MainInventoryChainButton>,
             Without<PrizeWindowChainButton>,
         ),
     >,
-    mut q_chain_button_stock_text: Query<&mut Text2d, With<ChainButtonStock>>,
-    q_prize_window: Query<(Entity, &PrizeWindow)>,
+    q_prize_window: Query<Entity, With<PrizeWindow>>,
     mut evw_prize_collected: EventWriter<PrizeCollected>,
+    mut evw_inventory_replaced: EventWriter<InventoryReplaced>,
 ) {
-    for (selected_chain_button, mut sprite, transform) in q_chain_buttons.iter_mut() {
-        if mouse_pos.is_in(
-            transform.translation().truncate(),
-            Vec2::splat(CHAIN_BUTTON_SIZE),
-        ) {
-            if mouse_button.just_pressed(MouseButton::Left) {
-                let (e_prize_window, _) = q_prize_window.single().unwrap();
-                commands.entity(e_prize_window).despawn();
-                state.set(GameState::Modifier);
-                evw_prize_collected.write(PrizeCollected {});
-                for (mut chain_button_in_inventory, children) in
-                    q_chain_button_in_inventory.iter_mut()
-                {
-                    if chain_button_in_inventory.length == selected_chain_button.length {
-                        chain_button_in_inventory.stock += selected_chain_button.stock;
-                    }
-
-                    for child in children.iter() {
-                        if let Ok(mut text) = q_chain_button_stock_text.get_mut(child) {
-                            text.0 = format!("{}", chain_button_in_inventory.stock);
-                        }
-                    }
-                }
-                break;
-            } else {
-                sprite.color = Color::linear_rgba(0.0, 0.0, 1.0, 1.0);
-            }
-        } else {
-            sprite.color = Color::linear_rgba(1.0, 1.0, 1.0, 1.0);
-        }
+    let Some(selected_chain_button) = evr_clicked
+        .read()
+        .find_map(|event| q_prize_buttons.get(event.button).ok())
+    else {
+        return;
+    };
+
+    commands.entity(q_prize_window.single().unwrap()).despawn();
+    // campaign layouts are hand-authored, so they skip the random modifiers
+    match *game_mode {
+        GameMode::Endless => state.set(GameState::Modifier),
+        GameMode::Campaign(_) | GameMode::Puzzle(_) => state.set(GameState::Building),
     }
+    evw_prize_collected.write(PrizeCollected {});
+
+    // rebuild the inventory in its current order, a new kind of chain going on the end
+    let mut buttons = q_chain_button_in_inventory.iter().collect::<Vec<_>>();
+    buttons.sort_by(|(_, a), (_, b)| a.translation.x.total_cmp(&b.translation.x));
+    let mut entries = buttons
+        .iter()
+        .map(|(button, _)| button.entry())
+        .collect::<Vec<_>>();
+    add_to_inventory(&mut entries, selected_chain_button.entry());
+    evw_inventory_replaced.write(InventoryReplaced { entries });
 }
 
 pub fn plugin(app: &mut App) {
     app.add_systems(
         Update,
-        (on_wave_complete, mouse_down_on_chain_button_in_prize_window)
-            .run_if(in_state(GameState::Prize)),
+        (on_wave_complete, prize_button_click).run_if(in_state(GameState::Prize)),
     );
 }
@@ -0,0 +1,587 @@
+use std::fmt;
+use std::str::FromStr;
+
+use bevy::asset::{AssetLoader, LoadContext, io::Reader};
+use bevy::prelude::*;
+use grid_util::point::Point;
+
+use crate::game::boarding::{BoardingKind, BoardingPoint, PORT_SHIP};
+use crate::game::chain::{ChainMaterial, InventoryEntry};
+use crate::game::events::{
+    BoardingPointsReplaced, CrateSpawned, GoldBarLost, InventoryReplaced, PuzzleComplete,
+    PuzzleStarted,
+};
+use crate::game::game_state::{GameMode, GameState};
+use crate::game::level::WaveSpec;
+use crate::game::pirate::{hold_reachable, pathing_grid_from_tiles};
+use crate::game::simulation::simulate_wave;
+use crate::game::tile::{GRID_HEIGHT, GRID_WIDTH, Tile};
+
+// Puzzles are plain text files, one `key: value` per line and `#` for comments:
+//
+//   name: The Corridor
+//   crates: 4,0 4,1 4,2          (any number of lines, tiles as x,y)
+//   ship: 0,5                    (boarding points, defaults to a single ship at 0,5)
+//   ship: 24,3                   (ships board at x 0 on the port side or 24 on starboard)
+//   hatch: 12,10
+//   wave: 4 250 100              (pirates, movement speed, oxygen)
+//   chain: 1 9                   (stock, length; one line per inventory entry)
+//   chain: 2 4 rusty             (an optional material: iron, rusty, anchor or stainless)
+//   solution: 6,2 6,3 6,4 6,5    (one line per chain, in drawing order)
+//   solution: rusty 9,4 10,4     (chains are iron unless a material comes first)
+//
+// The solution is never shown to the player, it proves the puzzle can be beaten.
+const PUZZLE_FILES: [&str; 3] = [
+    "puzzles/corridor.puzzle",
+    "puzzles/pincer.puzzle",
+    "puzzles/long_way_round.puzzle",
+];
+
+#[derive(Asset, TypePath, Debug)]
+pub struct Puzzle {
+    pub name: String,
+    pub crates: Vec<Tile>,
+    pub boarding_points: Vec<BoardingPoint>,
+    pub wave: WaveSpec,
+    pub inventory: Vec<InventoryEntry>,
+    pub solution: Vec<SolutionChain>,
+}
+
+#[derive(Debug)]
+pub struct SolutionChain {
+    pub material: ChainMaterial,
+    pub tiles: Vec<Tile>,
+}
+
+#[derive(Debug)]
+pub enum PuzzleError {
+    Io(std::io::Error),
+    Parse { line: usize, message: String },
+    Invalid(String),
+}
+
+impl fmt::Display for PuzzleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PuzzleError::Io(err) => write!(f, "could not read puzzle: {}", err),
+            PuzzleError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+            PuzzleError::Invalid(message) => write!(f, "invalid puzzle: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for PuzzleError {}
+
+impl From<std::io::Error> for PuzzleError {
+    fn from(err: std::io::Error) -> Self {
+        PuzzleError::Io(err)
+    }
+}
+
+fn parse_tiles(value: &str) -> Result<Vec<Tile>, String> {
+    value
+        .split_whitespace()
+        .map(|word| {
+            let (x, y) = word
+                .split_once(',')
+                .ok_or_else(|| format!("expected a tile like 3,4 but found {:?}", word))?;
+            let x = x.parse().map_err(|_| format!("bad tile x in {:?}", word))?;
+            let y = y.parse().map_err(|_| format!("bad tile y in {:?}", word))?;
+            Ok(Tile { x, y })
+        })
+        .collect()
+}
+
+// a material name at the start of `value`, if there is one, and the rest of it
+fn parse_material(value: &str) -> Result<(ChainMaterial, &str), String> {
+    let (first, rest) = value.split_once(' ').unwrap_or((value, ""));
+    if first.contains(',') || first.parse::<u32>().is_ok() {
+        return Ok((ChainMaterial::default(), value));
+    }
+    let material =
+        ChainMaterial::from_name(first).ok_or_else(|| format!("unknown material {:?}", first))?;
+    Ok((material, rest.trim()))
+}
+
+fn parse_numbers<T: FromStr>(value: &str, count: usize) -> Result<Vec<T>, String> {
+    let numbers = value
+        .split_whitespace()
+        .map(|word| {
+            word.parse()
+                .map_err(|_| format!("expected a number but found {:?}", word))
+        })
+        .collect::<Result<Vec<T>, String>>()?;
+
+    if numbers.len() != count {
+        return Err(format!(
+            "expected {} numbers but found {}",
+            count,
+            numbers.len()
+        ));
+    }
+    Ok(numbers)
+}
+
+fn parse_puzzle(contents: &str) -> Result<Puzzle, PuzzleError> {
+    let mut name = None;
+    let mut crates = Vec::new();
+    let mut boarding_points = Vec::new();
+    let mut wave = None;
+    let mut inventory = Vec::new();
+    let mut solution = Vec::new();
+
+    for (i, line) in contents.lines().enumerate() {
+        let line_error = |message: String| PuzzleError::Parse {
+            line: i + 1,
+            message,
+        };
+
+        let line = line.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let (key, value) = line
+            .split_once(':')
+            .ok_or_else(|| line_error("expected `key: value`".to_string()))?;
+        let value = value.trim();
+
+        match key.trim() {
+            "name" => name = Some(value.to_string()),
+            "crates" => crates.extend(parse_tiles(value).map_err(line_error)?),
+            "ship" | "hatch" => {
+                let kind = if key.trim() == "ship" {
+                    BoardingKind::Ship
+                } else {
+                    BoardingKind::Hatch
+                };
+                for tile in parse_tiles(value).map_err(line_error)? {
+                    boarding_points.push(BoardingPoint {
+                        point: Point {
+                            x: tile.x,
+                            y: tile.y,
+                        },
+                        kind,
+                    });
+                }
+            }
+            "wave" => {
+                if wave.is_some() {
+                    return Err(line_error("a puzzle has exactly one wave".to_string()));
+                }
+                let numbers = parse_numbers::<f32>(value, 3).map_err(line_error)?;
+                wave = Some(WaveSpec {
+                    pirates: numbers[0] as u32,
+                    movement_speed: numbers[1],
+                    oxygen: numbers[2],
+                    boarding_points: 0,
+                    // the simulation that checks puzzles has no captains
+                    boss: false,
+                });
+            }
+            "chain" => {
+                // the material comes last here, after the numbers
+                let (numbers, material) = match value.rsplit_once(' ') {
+                    Some((numbers, last)) if last.parse::<u32>().is_err() => {
+                        (numbers, parse_material(last).map_err(line_error)?.0)
+                    }
+                    _ => (value, ChainMaterial::default()),
+                };
+                let numbers = parse_numbers::<u32>(numbers, 2).map_err(line_error)?;
+                inventory.push(InventoryEntry {
+                    stock: numbers[0],
+                    length: numbers[1],
+                    material,
+                });
+            }
+            "solution" => {
+                let (material, tiles) = parse_material(value).map_err(line_error)?;
+                solution.push(SolutionChain {
+                    material,
+                    tiles: parse_tiles(tiles).map_err(line_error)?,
+                });
+            }
+            other => return Err(line_error(format!("unknown key {:?}", other))),
+        }
+    }
+
+    if boarding_points.is_empty() {
+        boarding_points.push(PORT_SHIP);
+    }
+
+    // the single wave boards from everywhere at once
+    let mut wave = wave.ok_or_else(|| PuzzleError::Invalid("missing wave".to_string()))?;
+    wave.boarding_points = boarding_points.len();
+
+    Ok(Puzzle {
+        name: name.ok_or_else(|| PuzzleError::Invalid("missing name".to_string()))?,
+        crates,
+        boarding_points,
+        wave,
+        inventory,
+        solution,
+    })
+}
+
+fn in_hold(tile: &Tile) -> bool {
+    (1..GRID_WIDTH).contains(&tile.x) && (0..GRID_HEIGHT).contains(&tile.y)
+}
+
+fn boarding_point_is_valid(boarding_point: &BoardingPoint) -> bool {
+    let tile = Tile {
+        x: boarding_point.point.x,
+        y: boarding_point.point.y,
+    };
+    match boarding_point.kind {
+        BoardingKind::Ship => {
+            (tile.x == 0 || tile.x == GRID_WIDTH - 1) && (0..GRID_HEIGHT).contains(&tile.y)
+        }
+        BoardingKind::Hatch => in_hold(&tile),
+    }
+}
+
+fn path_is_open(obstacles: &[Tile], boarding_points: &[BoardingPoint]) -> bool {
+    hold_reachable(
+        &pathing_grid_from_tiles(obstacles.iter().copied()),
+        boarding_points,
+    )
+}
+
+// check the layout is legal, the solution could actually be drawn, and that the
+// wave is only beaten with the chains placed
+fn validate_puzzle(puzzle: &Puzzle) -> Result<(), String> {
+    if let Some(tile) = puzzle.crates.iter().find(|tile| !in_hold(tile)) {
+        return Err(format!(
+            "crate at {},{} is outside the hold",
+            tile.x, tile.y
+        ));
+    }
+    if let Some(boarding_point) = puzzle
+        .boarding_points
+        .iter()
+        .find(|boarding_point| !boarding_point_is_valid(boarding_point))
+    {
+        return Err(format!(
+            "{:?} at {},{} is not somewhere pirates can board",
+            boarding_point.kind, boarding_point.point.x, boarding_point.point.y
+        ));
+    }
+    if !path_is_open(&puzzle.crates, &puzzle.boarding_points) {
+        return Err("the crates block the way to the gold".to_string());
+    }
+    if puzzle.inventory.is_empty() {
+        return Err("the inventory is empty".to_string());
+    }
+    if puzzle.solution.is_empty() {
+        return Err("missing solution".to_string());
+    }
+
+    let mut chain_tiles: Vec<Tile> = Vec::new();
+    for chain in puzzle.solution.iter() {
+        for (i, tile) in chain.tiles.iter().enumerate() {
+            if !in_hold(tile) || puzzle.crates.contains(tile) || chain_tiles.contains(tile) {
+                return Err(format!("solution tile {},{} is not free", tile.x, tile.y));
+            }
+            if i > 0 && !chain.tiles[i - 1].is_adjacent(tile) {
+                return Err(format!("solution chain breaks at {},{}", tile.x, tile.y));
+            }
+            chain_tiles.push(*tile);
+        }
+    }
+
+    // the inventory is keyed by (material, length), and so is what the solution uses
+    let is_entry_for = |entry: &InventoryEntry, chain: &SolutionChain| {
+        entry.material == chain.material && entry.length == chain.tiles.len() as u32
+    };
+    for entry in puzzle.inventory.iter() {
+        let used = puzzle
+            .solution
+            .iter()
+            .filter(|chain| is_entry_for(entry, chain))
+            .count() as u32;
+        if used > entry.stock {
+            return Err(format!(
+                "solution uses {} {} chains of length {} but only {} are in stock",
+                used,
+                entry.material.name(),
+                entry.length,
+                entry.stock
+            ));
+        }
+    }
+    if let Some(chain) = puzzle.solution.iter().find(|chain| {
+        !puzzle
+            .inventory
+            .iter()
+            .any(|entry| is_entry_for(entry, chain))
+    }) {
+        return Err(format!(
+            "solution has a {} chain of length {} that is not in the inventory",
+            chain.material.name(),
+            chain.tiles.len()
+        ));
+    }
+
+    let obstacles = puzzle
+        .crates
+        .iter()
+        .copied()
+        .chain(chain_tiles.iter().copied())
+        .collect::<Vec<_>>();
+    if !path_is_open(&obstacles, &puzzle.boarding_points) {
+        return Err("the solution blocks the way to the gold".to_string());
+    }
+
+    let boarding_points = &puzzle.boarding_points;
+    if simulate_wave(&puzzle.crates, &[], boarding_points, &puzzle.wave).is_flawless(&puzzle.wave) {
+        return Err("the wave is beaten without placing any chains".to_string());
+    }
+    let result = simulate_wave(
+        &puzzle.crates,
+        &puzzle.solution,
+        boarding_points,
+        &puzzle.wave,
+    );
+    if !result.is_flawless(&puzzle.wave) {
+        return Err(format!(
+            "the solution only kills {} of {} pirates and loses {} gold",
+            result.pirates_killed, puzzle.wave.pirates, result.gold_lost
+        ));
+    }
+
+    Ok(())
+}
+
+#[derive(Default)]
+pub struct PuzzleLoader;
+
+impl AssetLoader for PuzzleLoader {
+    type Asset = Puzzle;
+    type Settings = ();
+    type Error = PuzzleError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Puzzle, PuzzleError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let contents = String::from_utf8(bytes).map_err(|_| PuzzleError::Parse {
+            line: 0,
+            message: "puzzle files must be utf-8".to_string(),
+        })?;
+
+        let puzzle = parse_puzzle(&contents)?;
+        // running the wave is too slow to hold up loading in a release build, and the
+        // puzzles that ship are checked by the tests anyway
+        if cfg!(debug_assertions) {
+            validate_puzzle(&puzzle).map_err(PuzzleError::Invalid)?;
+        }
+        Ok(puzzle)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["puzzle"]
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct PuzzleLibrary {
+    pub puzzles: Vec<Handle<Puzzle>>,
+}
+
+impl PuzzleLibrary {
+    pub fn get<'a>(&self, puzzles: &'a Assets<Puzzle>, puzzle: usize) -> Option<&'a Puzzle> {
+        puzzles.get(self.puzzles.get(puzzle)?)
+    }
+}
+
+fn load_puzzles(asset_server: Res<AssetServer>, mut library: ResMut<PuzzleLibrary>) {
+    library.puzzles = PUZZLE_FILES
+        .iter()
+        .map(|path| asset_server.load(*path))
+        .collect();
+}
+
+fn apply_puzzle_layout(
+    mut evr_puzzle_started: EventReader<PuzzleStarted>,
+    mut evw_crate_spawned: EventWriter<CrateSpawned>,
+    mut evw_inventory_replaced: EventWriter<InventoryReplaced>,
+    mut evw_boarding_points_replaced: EventWriter<BoardingPointsReplaced>,
+    library: Res<PuzzleLibrary>,
+    puzzles: Res<Assets<Puzzle>>,
+) {
+    for event in evr_puzzle_started.read() {
+        let Some(puzzle) = library.get(&puzzles, event.puzzle) else {
+            continue;
+        };
+        for tile in puzzle.crates.iter() {
+            evw_crate_spawned.write(CrateSpawned { tile: *tile });
+        }
+        evw_inventory_replaced.write(InventoryReplaced {
+            entries: puzzle.inventory.clone(),
+        });
+        evw_boarding_points_replaced.write(BoardingPointsReplaced {
+            points: puzzle.boarding_points.clone(),
+        });
+    }
+}
+
+// a puzzle is lost the moment any gold leaves the hold
+fn fail_on_gold_lost(
+    mut evr_gold_lost: EventReader<GoldBarLost>,
+    game_mode: Res<GameMode>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut evw_puzzle_complete: EventWriter<PuzzleComplete>,
+) {
+    if evr_gold_lost.is_empty() {
+        return;
+    }
+
+    evr_gold_lost.clear();
+
+    if matches!(*game_mode, GameMode::Puzzle(_)) {
+        next_state.set(GameState::PuzzleResult);
+        evw_puzzle_complete.write(PuzzleComplete { solved: false });
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app.init_asset::<Puzzle>();
+    app.init_asset_loader::<PuzzleLoader>();
+    app.init_resource::<PuzzleLibrary>();
+    app.add_systems(Startup, load_puzzles);
+    app.add_systems(Update, apply_puzzle_layout);
+    app.add_systems(
+        Update,
+        fail_on_gold_lost.run_if(in_state(GameState::WaveInProgress)),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CORRIDOR: &str = "\
+# Two bulkheads leave a single gap, and a long chain lines the way through.
+name: The Corridor
+
+crates: 8,0 8,1 8,2 8,3 8,4 8,6 8,7 8,8 8,9 8,10
+crates: 16,0 16,1 16,2 16,3 16,4 16,6 16,7 16,8 16,9 16,10
+
+wave: 4 250 90
+
+chain: 1 9
+chain: 2 4
+
+solution: 9,4 10,4 11,4 12,4 13,4 14,4 15,4 15,3 15,2
+solution: 9,6 10,6 11,6 12,6
+solution: 13,6 14,6 15,6 15,7
+";
+
+    fn corridor_with(line: &str, replacement: &str) -> String {
+        assert!(CORRIDOR.contains(line));
+        CORRIDOR.replacen(line, replacement, 1)
+    }
+
+    #[test]
+    fn every_puzzle_file_is_valid() {
+        for path in PUZZLE_FILES {
+            let file = format!("{}/assets/{}", env!("CARGO_MANIFEST_DIR"), path);
+            let contents = std::fs::read_to_string(&file).unwrap();
+            let puzzle = parse_puzzle(&contents).unwrap_or_else(|err| panic!("{}: {}", path, err));
+            if let Err(err) = validate_puzzle(&puzzle) {
+                panic!("{}: {}", path, err);
+            }
+        }
+    }
+
+    #[test]
+    fn parses_every_key() {
+        let puzzle = parse_puzzle(
+            "name: Test\n\
+             crates: 4,0 4,1\n\
+             crates: 5,5\n\
+             hatch: 12,10\n\
+             wave: 3 200 80\n\
+             chain: 2 5 rusty\n\
+             solution: anchor 6,2 6,3\n",
+        )
+        .unwrap();
+
+        assert_eq!(puzzle.name, "Test");
+        assert_eq!(puzzle.crates.len(), 3);
+        assert_eq!(puzzle.boarding_points.len(), 1);
+        assert_eq!(puzzle.boarding_points[0].kind, BoardingKind::Hatch);
+        assert_eq!(puzzle.wave.pirates, 3);
+        assert_eq!(puzzle.wave.boarding_points, 1);
+        assert_eq!(puzzle.inventory[0].stock, 2);
+        assert_eq!(puzzle.inventory[0].length, 5);
+        assert_eq!(puzzle.inventory[0].material, ChainMaterial::RustyIron);
+        assert_eq!(puzzle.solution[0].material, ChainMaterial::Anchor);
+        assert_eq!(
+            puzzle.solution[0].tiles,
+            vec![Tile { x: 6, y: 2 }, Tile { x: 6, y: 3 }]
+        );
+    }
+
+    #[test]
+    fn boards_from_the_port_ship_by_default() {
+        let puzzle = parse_puzzle(CORRIDOR).unwrap();
+        assert_eq!(puzzle.boarding_points, vec![PORT_SHIP]);
+    }
+
+    #[test]
+    fn reports_the_line_of_a_parse_error() {
+        let error = parse_puzzle(&corridor_with("wave: 4 250 90", "wave: 4 250")).unwrap_err();
+        assert!(matches!(error, PuzzleError::Parse { line: 7, .. }));
+
+        let error = parse_puzzle(&corridor_with("crates: 8,0", "crates: 8;0")).unwrap_err();
+        assert!(matches!(error, PuzzleError::Parse { line: 4, .. }));
+    }
+
+    #[test]
+    fn a_puzzle_needs_a_wave() {
+        let error = parse_puzzle(&corridor_with("wave: 4 250 90\n", "")).unwrap_err();
+        assert!(matches!(error, PuzzleError::Invalid(_)));
+    }
+
+    #[test]
+    fn accepts_a_solvable_puzzle() {
+        let puzzle = parse_puzzle(CORRIDOR).unwrap();
+        assert_eq!(validate_puzzle(&puzzle), Ok(()));
+    }
+
+    #[test]
+    fn rejects_a_solution_the_inventory_cannot_cover() {
+        let puzzle = parse_puzzle(&corridor_with("chain: 2 4", "chain: 1 4")).unwrap();
+        assert!(
+            validate_puzzle(&puzzle)
+                .unwrap_err()
+                .contains("only 1 are in stock")
+        );
+
+        let puzzle = parse_puzzle(&corridor_with("chain: 2 4", "chain: 2 4 anchor")).unwrap();
+        assert!(
+            validate_puzzle(&puzzle)
+                .unwrap_err()
+                .contains("not in the inventory")
+        );
+    }
+
+    #[test]
+    fn rejects_a_solution_that_loses_the_wave() {
+        let puzzle = parse_puzzle(&corridor_with(
+            "solution: 9,6 10,6 11,6 12,6\nsolution: 13,6 14,6 15,6 15,7\n",
+            "",
+        ))
+        .unwrap();
+        assert!(
+            validate_puzzle(&puzzle)
+                .unwrap_err()
+                .starts_with("the solution only kills")
+        );
+    }
+}
@@ -0,0 +1,131 @@
+use bevy::prelude::*;
+use bevy::text::LineBreak;
+
+use crate::game::events::{PuzzleComplete, RunReset, WaveReset, WaveStarted};
+use crate::game::game_state::GameState;
+use crate::game::ui::{ButtonClicked, PANEL_TEXT_COLOR, label, modal, panel, text_button};
+
+const PUZZLE_RESULT_WINDOW_WIDTH: f32 = 1400.0;
+const PUZZLE_RESULT_WINDOW_HEIGHT: f32 = 800.0;
+
+const PUZZLE_RESULT_WINDOW_PADDING: f32 = 20.0;
+
+const PUZZLE_RESULT_TEXT_BOX_WIDTH: f32 =
+    PUZZLE_RESULT_WINDOW_WIDTH - 2.0 * PUZZLE_RESULT_WINDOW_PADDING;
+
+const RESULT_BUTTON_SIZE: Vec2 = Vec2::new(260.0, 100.0);
+const RESULT_BUTTON_SPACING: f32 = 400.0;
+
+#[derive(Component, Debug)]
+pub struct PuzzleResultWindow;
+
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub enum PuzzleResultButton {
+    Retry,
+    Puzzles,
+}
+
+fn spawn_puzzle_result_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut evr_puzzle_complete: EventReader<PuzzleComplete>,
+) {
+    // losing gold and finishing the wave can both report on the same frame
+    if let Some(event) = evr_puzzle_complete.read().last() {
+        let (title, body) = if event.solved {
+            (
+                "Puzzle Solved",
+                "Every pirate ran out of air before they could get away with the gold.",
+            )
+        } else {
+            (
+                "Puzzle Failed",
+                "A pirate made it off the ship with gold. Your chains stay where they are, so \
+                 you can try the wave again straight away.",
+            )
+        };
+
+        commands
+            .spawn((PuzzleResultWindow, modal()))
+            .with_children(|parent| {
+                parent
+                    .spawn(panel(Vec2::new(
+                        PUZZLE_RESULT_WINDOW_WIDTH,
+                        PUZZLE_RESULT_WINDOW_HEIGHT,
+                    )))
+                    .with_children(|parent| {
+                        parent.spawn(label(&asset_server, title, 50.0, PANEL_TEXT_COLOR));
+
+                        parent.spawn((
+                            label(&asset_server, body, 35.0, PANEL_TEXT_COLOR),
+                            TextLayout::new(JustifyText::Center, LineBreak::WordBoundary),
+                            Node {
+                                width: Val::Px(PUZZLE_RESULT_TEXT_BOX_WIDTH),
+                                ..default()
+                            },
+                        ));
+
+                        // spawn retry and back buttons
+                        parent
+                            .spawn(Node {
+                                column_gap: Val::Px(RESULT_BUTTON_SPACING - RESULT_BUTTON_SIZE.x),
+                                ..default()
+                            })
+                            .with_children(|parent| {
+                                for (text, result_button) in [
+                                    ("Retry Wave", PuzzleResultButton::Retry),
+                                    ("Puzzles", PuzzleResultButton::Puzzles),
+                                ] {
+                                    parent.spawn((
+                                        result_button,
+                                        text_button(&asset_server, text, RESULT_BUTTON_SIZE),
+                                    ));
+                                }
+                            });
+                    });
+            });
+    }
+}
+
+fn puzzle_result_buttons(
+    mut commands: Commands,
+    mut evr_clicked: EventReader<ButtonClicked>,
+    mut state: ResMut<NextState<GameState>>,
+    q_result_buttons: Query<&PuzzleResultButton>,
+    q_puzzle_result_window: Query<Entity, With<PuzzleResultWindow>>,
+    mut evw_run_reset: EventWriter<RunReset>,
+    mut evw_wave_reset: EventWriter<WaveReset>,
+    mut evw_wave_started: EventWriter<WaveStarted>,
+) {
+    let Some(result_button) = evr_clicked
+        .read()
+        .find_map(|event| q_result_buttons.get(event.button).ok())
+    else {
+        return;
+    };
+
+    for entity in q_puzzle_result_window.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    // retrying keeps the chains and crates, only the pirates and gold go back
+    evw_wave_reset.write(WaveReset);
+    match result_button {
+        PuzzleResultButton::Retry => {
+            state.set(GameState::WaveInProgress);
+            evw_wave_started.write(WaveStarted);
+        }
+        PuzzleResultButton::Puzzles => {
+            state.set(GameState::PuzzleSelect);
+            evw_run_reset.write(RunReset);
+        }
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(Update, spawn_puzzle_result_screen);
+    app.add_systems(
+        Update,
+        puzzle_result_buttons.run_if(in_state(GameState::PuzzleResult)),
+    );
+}
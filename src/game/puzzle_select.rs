@@ -0,0 +1,133 @@
+use bevy::asset::LoadState;
+use bevy::prelude::*;
+
+use crate::game::events::PuzzleStarted;
+use crate::game::game_state::{GameMode, GameState};
+use crate::game::puzzle::{Puzzle, PuzzleLibrary};
+use crate::game::ui::{ButtonClicked, Disabled, PANEL_TEXT_COLOR, button, label, modal, panel};
+
+const PUZZLE_SELECT_WINDOW_WIDTH: f32 = 1400.0;
+const PUZZLE_SELECT_WINDOW_HEIGHT: f32 = 800.0;
+
+const PUZZLE_BUTTON_SIZE: Vec2 = Vec2::new(360.0, 180.0);
+const PUZZLE_BUTTON_SPACING: f32 = 420.0;
+
+#[derive(Component)]
+pub struct PuzzleSelectWindow;
+
+#[derive(Component)]
+pub struct PuzzleButton {
+    puzzle: usize,
+}
+
+fn spawn_puzzle_select_window(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    library: Res<PuzzleLibrary>,
+    puzzles: Res<Assets<Puzzle>>,
+) {
+    commands
+        .spawn((PuzzleSelectWindow, modal()))
+        .with_children(|parent| {
+            parent
+                .spawn(panel(Vec2::new(
+                    PUZZLE_SELECT_WINDOW_WIDTH,
+                    PUZZLE_SELECT_WINDOW_HEIGHT,
+                )))
+                .with_children(|parent| {
+                    parent.spawn(label(
+                        &asset_server,
+                        "Choose a Puzzle",
+                        50.0,
+                        PANEL_TEXT_COLOR,
+                    ));
+
+                    parent.spawn(label(
+                        &asset_server,
+                        "Stop every pirate with the chains you are given",
+                        30.0,
+                        PANEL_TEXT_COLOR,
+                    ));
+
+                    parent
+                        .spawn(Node {
+                            column_gap: Val::Px(PUZZLE_BUTTON_SPACING - PUZZLE_BUTTON_SIZE.x),
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            for (i, handle) in library.puzzles.iter().enumerate() {
+                                // puzzles that fail to parse or validate stay listed so the
+                                // author can see which file is broken
+                                let (title, status) = match puzzles.get(handle) {
+                                    Some(puzzle) => (
+                                        puzzle.name.clone(),
+                                        format!("{} pirates", puzzle.wave.pirates),
+                                    ),
+                                    None => match asset_server.load_state(handle) {
+                                        LoadState::Failed(_) => {
+                                            ("Invalid".to_string(), "See the log".to_string())
+                                        }
+                                        _ => ("Loading...".to_string(), String::new()),
+                                    },
+                                };
+
+                                let mut puzzle_button = parent.spawn((
+                                    PuzzleButton { puzzle: i },
+                                    button(PUZZLE_BUTTON_SIZE),
+                                    children![
+                                        label(
+                                            &asset_server,
+                                            format!("{}. {}", i + 1, title),
+                                            30.0,
+                                            Color::WHITE,
+                                        ),
+                                        label(
+                                            &asset_server,
+                                            status,
+                                            30.0,
+                                            Color::linear_rgba(1.0, 0.85, 0.0, 1.0),
+                                        ),
+                                    ],
+                                ));
+                                if !puzzles.contains(handle) {
+                                    puzzle_button.insert(Disabled);
+                                }
+                            }
+                        });
+                });
+        });
+}
+
+fn puzzle_button_click(
+    mut commands: Commands,
+    mut evr_clicked: EventReader<ButtonClicked>,
+    mut state: ResMut<NextState<GameState>>,
+    mut game_mode: ResMut<GameMode>,
+    q_puzzle_buttons: Query<&PuzzleButton>,
+    q_puzzle_select_window: Query<Entity, With<PuzzleSelectWindow>>,
+    mut evw_puzzle_started: EventWriter<PuzzleStarted>,
+) {
+    for event in evr_clicked.read() {
+        let Ok(puzzle_button) = q_puzzle_buttons.get(event.button) else {
+            continue;
+        };
+
+        commands
+            .entity(q_puzzle_select_window.single().unwrap())
+            .despawn();
+        *game_mode = GameMode::Puzzle(puzzle_button.puzzle);
+        state.set(GameState::Building);
+        evw_puzzle_started.write(PuzzleStarted {
+            puzzle: puzzle_button.puzzle,
+        });
+        break;
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(GameState::PuzzleSelect), spawn_puzzle_select_window);
+    app.add_systems(
+        Update,
+        puzzle_button_click.run_if(in_state(GameState::PuzzleSelect)),
+    );
+}
@@ -0,0 +1,68 @@
+use bevy::prelude::*;
+
+// Save files are tiny line-based text blobs keyed by name. Native builds keep
+// them in the user's data directory, web builds keep them in local storage.
+
+#[cfg(not(target_arch = "wasm32"))]
+fn save_path(name: &str) -> Option<std::path::PathBuf> {
+    use std::path::PathBuf;
+
+    let data_dir = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| {
+            std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share"))
+        })?;
+
+    Some(data_dir.join("chain-lockers").join(format!("{}.sav", name)))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load(name: &str) -> Option<String> {
+    std::fs::read_to_string(save_path(name)?).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn store(name: &str, contents: &str) {
+    let Some(path) = save_path(name) else {
+        warn!("could not find a data directory, {} was not saved", name);
+        return;
+    };
+    if let Some(dir) = path.parent()
+        && let Err(err) = std::fs::create_dir_all(dir)
+    {
+        warn!("could not create save directory {:?}: {}", dir, err);
+        return;
+    }
+
+    if let Err(err) = std::fs::write(&path, contents) {
+        warn!("could not write save file {:?}: {}", path, err);
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn load(name: &str) -> Option<String> {
+    local_storage()?
+        .get_item(&format!("chain-lockers/{}", name))
+        .ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn store(name: &str, contents: &str) {
+    let Some(storage) = local_storage() else {
+        warn!("local storage is unavailable, {} was not saved", name);
+        return;
+    };
+
+    if storage
+        .set_item(&format!("chain-lockers/{}", name), contents)
+        .is_err()
+    {
+        warn!("could not write {} to local storage", name);
+    }
+}
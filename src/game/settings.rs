@@ -0,0 +1,240 @@
+use bevy::{
+    audio::Volume,
+    prelude::*,
+    window::{MonitorSelection, PresentMode, PrimaryWindow, WindowMode},
+};
+
+use crate::game::save;
+
+// Volumes and display options, which are saved as soon as they're changed. Every
+// sound is spawned with a `Sound` saying which channel it's on and how loud it is
+// on its own, and its volume is worked out from that and the settings, so moving
+// a slider changes the sounds that are already playing as well as new ones.
+
+const SETTINGS_SAVE: &str = "settings";
+
+// everything plays this much quieter than the files themselves
+const OUTPUT_LEVEL: f32 = 0.3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Channel {
+    Master,
+    Music,
+    Sfx,
+    Ambience,
+}
+
+impl Channel {
+    pub const ALL: [Channel; 4] = [
+        Channel::Master,
+        Channel::Music,
+        Channel::Sfx,
+        Channel::Ambience,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Channel::Master => "Master",
+            Channel::Music => "Music",
+            Channel::Sfx => "Sound Effects",
+            Channel::Ambience => "Ambience",
+        }
+    }
+
+    // what the channel is called in the save file, which mustn't change
+    fn name(&self) -> &'static str {
+        match self {
+            Channel::Master => "Master",
+            Channel::Music => "Music",
+            Channel::Sfx => "Sfx",
+            Channel::Ambience => "Ambience",
+        }
+    }
+
+    fn index(&self) -> usize {
+        Channel::ALL
+            .iter()
+            .position(|channel| channel == self)
+            .unwrap()
+    }
+}
+
+// a sound on one of the channels, other than the master, and how loud it is
+// before the settings are taken into account
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Sound {
+    pub channel: Channel,
+    pub volume: f32,
+}
+
+impl Sound {
+    pub fn new(channel: Channel, volume: f32) -> Self {
+        Self { channel, volume }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ChannelSettings {
+    pub volume: f32,
+    pub muted: bool,
+}
+
+impl Default for ChannelSettings {
+    fn default() -> Self {
+        Self {
+            volume: 1.0,
+            muted: false,
+        }
+    }
+}
+
+#[derive(Resource, Clone, Debug)]
+pub struct Settings {
+    channels: [ChannelSettings; Channel::ALL.len()],
+    pub fullscreen: bool,
+    pub vsync: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            channels: [ChannelSettings::default(); Channel::ALL.len()],
+            fullscreen: false,
+            vsync: true,
+        }
+    }
+}
+
+impl Settings {
+    pub fn channel(&self, channel: Channel) -> &ChannelSettings {
+        &self.channels[channel.index()]
+    }
+
+    pub fn channel_mut(&mut self, channel: Channel) -> &mut ChannelSettings {
+        &mut self.channels[channel.index()]
+    }
+
+    fn level(&self, channel: Channel) -> f32 {
+        let settings = self.channel(channel);
+        if settings.muted { 0.0 } else { settings.volume }
+    }
+
+    pub fn volume(&self, sound: &Sound) -> Volume {
+        Volume::Linear(
+            OUTPUT_LEVEL * self.level(Channel::Master) * self.level(sound.channel) * sound.volume,
+        )
+    }
+
+    fn serialize(&self) -> String {
+        let mut lines = Channel::ALL
+            .iter()
+            .map(|channel| {
+                let settings = self.channel(*channel);
+                let muted = if settings.muted { " muted" } else { "" };
+                format!("{}={:.2}{}", channel.name(), settings.volume, muted)
+            })
+            .collect::<Vec<_>>();
+        lines.push(format!("Fullscreen={}", self.fullscreen));
+        lines.push(format!("Vsync={}", self.vsync));
+        lines.join("\n")
+    }
+
+    // anything that isn't in the file, or can't be read, keeps its default
+    fn deserialize(contents: &str) -> Self {
+        let mut settings = Self::default();
+        for line in contents.lines() {
+            let Some((name, value)) = line.split_once('=') else {
+                continue;
+            };
+            let name = name.trim();
+            let value = value.trim();
+
+            match name {
+                "Fullscreen" => {
+                    settings.fullscreen = value.parse().unwrap_or(settings.fullscreen);
+                }
+                "Vsync" => {
+                    settings.vsync = value.parse().unwrap_or(settings.vsync);
+                }
+                _ => {
+                    let Some(channel) = Channel::ALL
+                        .into_iter()
+                        .find(|channel| channel.name() == name)
+                    else {
+                        continue;
+                    };
+                    let mut words = value.split_whitespace();
+                    let Some(volume) = words.next().and_then(|word| word.parse::<f32>().ok())
+                    else {
+                        continue;
+                    };
+                    *settings.channel_mut(channel) = ChannelSettings {
+                        volume: volume.clamp(0.0, 1.0),
+                        muted: words.next() == Some("muted"),
+                    };
+                }
+            }
+        }
+        settings
+    }
+
+    pub fn save(&self) {
+        save::store(SETTINGS_SAVE, &self.serialize());
+    }
+}
+
+fn load_settings(mut commands: Commands) {
+    let settings = save::load(SETTINGS_SAVE)
+        .map(|contents| Settings::deserialize(&contents))
+        .unwrap_or_default();
+    commands.insert_resource(settings);
+}
+
+// New sounds have their volume set before they start, which is usually a few
+// frames after they're spawned while the file loads. Anything that starts before
+// then is caught as soon as it has a sink.
+fn apply_volume(
+    settings: Res<Settings>,
+    mut q_sounds: Query<(Ref<Sound>, &mut PlaybackSettings, Option<Mut<AudioSink>>)>,
+) {
+    for (sound, mut playback, sink) in q_sounds.iter_mut() {
+        let sink_added = sink.as_ref().is_some_and(|sink| sink.is_added());
+        if !settings.is_changed() && !sound.is_added() && !sink_added {
+            continue;
+        }
+
+        let volume = settings.volume(&sound);
+        playback.volume = volume;
+        if let Some(mut sink) = sink {
+            sink.set_volume(volume);
+        }
+    }
+}
+
+fn apply_window_settings(
+    settings: Res<Settings>,
+    mut q_window: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    for mut window in q_window.iter_mut() {
+        window.mode = if settings.fullscreen {
+            WindowMode::BorderlessFullscreen(MonitorSelection::Current)
+        } else {
+            WindowMode::Windowed
+        };
+        window.present_mode = if settings.vsync {
+            PresentMode::AutoVsync
+        } else {
+            PresentMode::AutoNoVsync
+        };
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(Startup, load_settings);
+    app.add_systems(Update, apply_window_settings);
+    app.add_systems(PostUpdate, apply_volume);
+}
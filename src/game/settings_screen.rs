@@ -0,0 +1,309 @@
+use bevy::prelude::*;
+use bevy::ui::RelativeCursorPosition;
+
+use crate::game::events::OpenSettings;
+use crate::game::settings::{Channel, Settings};
+use crate::game::ui::{
+    BUTTON_COLOR, ButtonClicked, ButtonColors, PANEL_TEXT_COLOR, UiButton, label, modal, panel,
+    text_button,
+};
+
+// The settings screen, opened from the title screen or over the pause menu. Each
+// channel has a slider, which can be clicked or dragged along or stepped with the
+// buttons either side of it, and can be muted. Everything takes effect straight
+// away.
+
+const WINDOW_SIZE: Vec2 = Vec2::new(1200.0, 820.0);
+
+const ROW_HEIGHT: f32 = 70.0;
+const ROW_SPACING: f32 = 20.0;
+const LABEL_WIDTH: f32 = 260.0;
+const PERCENT_WIDTH: f32 = 100.0;
+const STEP_BUTTON_SIZE: Vec2 = Vec2::new(60.0, 60.0);
+const MUTE_BUTTON_SIZE: Vec2 = Vec2::new(140.0, 60.0);
+
+// the slider takes the pointer over the whole height of the row, not just the bar
+const TRACK_SIZE: Vec2 = Vec2::new(400.0, 60.0);
+const BAR_HEIGHT: f32 = 16.0;
+
+const VOLUME_STEP: f32 = 0.1;
+
+const TOGGLE_BUTTON_SIZE: Vec2 = Vec2::new(360.0, 70.0);
+const BACK_BUTTON_SIZE: Vec2 = Vec2::new(200.0, 70.0);
+const BUTTON_SPACING: f32 = 80.0;
+
+const BAR_COLOR: Color = Color::linear_rgba(0.3, 0.3, 0.3, 1.0);
+const MUTED_COLOR: Color = Color::linear_rgba(0.8, 0.0, 0.0, 1.0);
+const MUTED_FILL_COLOR: Color = Color::linear_rgba(0.5, 0.5, 0.5, 1.0);
+
+#[derive(Component)]
+pub struct SettingsWindow;
+
+#[derive(Component)]
+struct VolumeTrack(Channel);
+
+#[derive(Component)]
+struct VolumeFill(Channel);
+
+#[derive(Component)]
+struct VolumeText(Channel);
+
+#[derive(Component, Clone, Copy)]
+enum SettingsButton {
+    Step(Channel, f32),
+    Mute(Channel),
+    Fullscreen,
+    Vsync,
+    Back,
+}
+
+fn spawn_settings_window(commands: &mut Commands, asset_server: &AssetServer) {
+    commands
+        .spawn((SettingsWindow, modal()))
+        .with_children(|parent| {
+            parent.spawn(panel(WINDOW_SIZE)).with_children(|parent| {
+                parent.spawn(label(asset_server, "Settings", 50.0, PANEL_TEXT_COLOR));
+
+                parent
+                    .spawn(Node {
+                        flex_direction: FlexDirection::Column,
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        for channel in Channel::ALL {
+                            spawn_volume_row(parent, asset_server, channel);
+                        }
+                    });
+
+                parent
+                    .spawn(Node {
+                        column_gap: Val::Px(BUTTON_SPACING),
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        for settings_button in [SettingsButton::Fullscreen, SettingsButton::Vsync] {
+                            parent.spawn((
+                                settings_button,
+                                text_button(asset_server, "", TOGGLE_BUTTON_SIZE),
+                            ));
+                        }
+                    });
+
+                parent.spawn((
+                    SettingsButton::Back,
+                    text_button(asset_server, "Back", BACK_BUTTON_SIZE),
+                ));
+            });
+        });
+}
+
+fn spawn_volume_row(
+    parent: &mut ChildSpawnerCommands,
+    asset_server: &AssetServer,
+    channel: Channel,
+) {
+    parent
+        .spawn(Node {
+            height: Val::Px(ROW_HEIGHT),
+            align_items: AlignItems::Center,
+            column_gap: Val::Px(ROW_SPACING),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn((
+                label(asset_server, channel.label(), 30.0, PANEL_TEXT_COLOR),
+                Node {
+                    width: Val::Px(LABEL_WIDTH),
+                    ..default()
+                },
+            ));
+
+            parent.spawn((
+                SettingsButton::Step(channel, -VOLUME_STEP),
+                text_button(asset_server, "-", STEP_BUTTON_SIZE),
+            ));
+
+            parent
+                .spawn((
+                    VolumeTrack(channel),
+                    Interaction::default(),
+                    RelativeCursorPosition::default(),
+                    Node {
+                        width: Val::Px(TRACK_SIZE.x),
+                        height: Val::Px(TRACK_SIZE.y),
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                ))
+                .with_children(|parent| {
+                    parent
+                        .spawn((
+                            Node {
+                                width: Val::Percent(100.0),
+                                height: Val::Px(BAR_HEIGHT),
+                                ..default()
+                            },
+                            BackgroundColor(BAR_COLOR),
+                        ))
+                        .with_child((
+                            VolumeFill(channel),
+                            Node {
+                                height: Val::Percent(100.0),
+                                ..default()
+                            },
+                            BackgroundColor(BUTTON_COLOR),
+                        ));
+                });
+
+            parent.spawn((
+                SettingsButton::Step(channel, VOLUME_STEP),
+                text_button(asset_server, "+", STEP_BUTTON_SIZE),
+            ));
+
+            parent.spawn((
+                VolumeText(channel),
+                label(asset_server, "", 30.0, PANEL_TEXT_COLOR),
+                Node {
+                    width: Val::Px(PERCENT_WIDTH),
+                    ..default()
+                },
+            ));
+
+            parent.spawn((
+                SettingsButton::Mute(channel),
+                text_button(asset_server, "", MUTE_BUTTON_SIZE),
+            ));
+        });
+}
+
+fn open_settings_window(
+    mut evr_open: EventReader<OpenSettings>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+) {
+    if evr_open.read().count() > 0 {
+        spawn_settings_window(&mut commands, &asset_server);
+    }
+}
+
+fn settings_click(
+    mut commands: Commands,
+    mut evr_clicked: EventReader<ButtonClicked>,
+    mut settings: ResMut<Settings>,
+    q_settings_buttons: Query<&SettingsButton>,
+    q_window: Query<Entity, With<SettingsWindow>>,
+) {
+    let Some(settings_button) = evr_clicked
+        .read()
+        .find_map(|event| q_settings_buttons.get(event.button).ok())
+    else {
+        return;
+    };
+
+    match *settings_button {
+        SettingsButton::Step(channel, step) => {
+            let channel = settings.channel_mut(channel);
+            // stepping lands on whole steps, wherever a drag left it
+            channel.volume = ((channel.volume + step) / VOLUME_STEP).round() * VOLUME_STEP;
+            channel.volume = channel.volume.clamp(0.0, 1.0);
+        }
+        SettingsButton::Mute(channel) => {
+            let channel = settings.channel_mut(channel);
+            channel.muted = !channel.muted;
+        }
+        SettingsButton::Fullscreen => settings.fullscreen = !settings.fullscreen,
+        SettingsButton::Vsync => settings.vsync = !settings.vsync,
+        SettingsButton::Back => {
+            for entity in q_window.iter() {
+                commands.entity(entity).despawn();
+            }
+            return;
+        }
+    }
+    settings.save();
+}
+
+// the volume follows the pointer while a slider is held, and is saved once it's let go
+fn drag_volume(
+    mut settings: ResMut<Settings>,
+    q_tracks: Query<(&VolumeTrack, Ref<Interaction>, &RelativeCursorPosition)>,
+) {
+    for (track, interaction, cursor) in q_tracks.iter() {
+        match *interaction {
+            Interaction::Pressed => {
+                let Some(position) = cursor.normalized else {
+                    continue;
+                };
+                let volume = position.x.clamp(0.0, 1.0);
+                if settings.channel(track.0).volume != volume {
+                    settings.channel_mut(track.0).volume = volume;
+                }
+            }
+            _ if interaction.is_changed() => settings.save(),
+            _ => {}
+        }
+    }
+}
+
+fn update_settings_window(
+    settings: Res<Settings>,
+    mut q_fills: Query<(&VolumeFill, &mut Node, &mut BackgroundColor)>,
+    mut q_volume_text: Query<(&VolumeText, &mut Text)>,
+    mut q_settings_buttons: Query<(&SettingsButton, &mut UiButton, &Children)>,
+    mut q_button_text: Query<&mut Text, Without<VolumeText>>,
+) {
+    for (fill, mut node, mut background) in q_fills.iter_mut() {
+        let channel = settings.channel(fill.0);
+        node.width = Val::Percent(channel.volume * 100.0);
+        background.0 = if channel.muted {
+            MUTED_FILL_COLOR
+        } else {
+            BUTTON_COLOR
+        };
+    }
+
+    for (volume_text, mut text) in q_volume_text.iter_mut() {
+        let volume = settings.channel(volume_text.0).volume;
+        text.0 = format!("{:.0}%", volume * 100.0);
+    }
+
+    for (settings_button, mut button, children) in q_settings_buttons.iter_mut() {
+        let on_off = |on: bool| if on { "On" } else { "Off" };
+        let (label, colors) = match *settings_button {
+            SettingsButton::Mute(channel) if settings.channel(channel).muted => {
+                ("Muted".to_string(), ButtonColors::filled(MUTED_COLOR))
+            }
+            SettingsButton::Mute(_) => ("Mute".to_string(), ButtonColors::FILLED),
+            SettingsButton::Fullscreen => (
+                format!("Fullscreen: {}", on_off(settings.fullscreen)),
+                ButtonColors::FILLED,
+            ),
+            SettingsButton::Vsync => (
+                format!("VSync: {}", on_off(settings.vsync)),
+                ButtonColors::FILLED,
+            ),
+            SettingsButton::Step(..) | SettingsButton::Back => continue,
+        };
+
+        if button.colors != colors {
+            button.colors = colors;
+        }
+        for child in children.iter() {
+            if let Ok(mut text) = q_button_text.get_mut(child)
+                && text.0 != label
+            {
+                text.0 = label.clone();
+            }
+        }
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(Update, open_settings_window);
+    app.add_systems(
+        Update,
+        (settings_click, drag_volume, update_settings_window)
+            .chain()
+            .run_if(any_with_component::<SettingsWindow>),
+    );
+}
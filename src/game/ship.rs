@@ -1,5 +1,12 @@
-use crate::game::events::{WaveComplete, WaveStarted};
+use crate::game::boarding::{BoardingKind, BoardingPoint, BoardingPoints};
+use crate::game::events::{BoardingPointsReplaced, WaveReset};
+use crate::game::pirate::{
+    BOAT_POINT, Pirate, Spawner, TickPosition, WaveState, grid_coord_to_transform,
+    pirate_spawn_system,
+};
+use crate::game::tile::{GRID_WIDTH, TILE_SIZE};
 use bevy::prelude::*;
+use grid_util::point::Point;
 
 pub const SHIP_SPEED: f32 = 180.0;
 pub const SHIP_MID_Y: f32 = 0.0;
@@ -7,24 +14,168 @@ pub const SHIP_START_Y: f32 = 850.0;
 pub const SHIP_OUT_Y: f32 = -850.0;
 pub const SHIP_X: f32 = -800.0;
 
+const GANGPLANK_WIDTH: f32 = 24.0;
+
+// each ship after the first on the same side docks this much further out, so their
+// hulls don't sit on top of each other
+const SHIP_SPACING: f32 = 120.0;
+
 #[derive(Component)]
 pub struct Ship {
     pub speed: f32,
     pub target_y: f32,
+    // where the ship stops to let its pirates off
+    pub dock_y: f32,
+    pub boarding_point: Point,
+}
+
+impl Ship {
+    pub fn is_docked(&self, transform: &Transform) -> bool {
+        self.target_y == self.dock_y && transform.translation.y <= self.dock_y
+    }
+}
+
+#[derive(Component)]
+pub struct Hatch;
+
+// a ship docks so that its deck lines up with its boarding point, the same way the
+// original ship at SHIP_MID_Y lines up with BOAT_POINT
+fn dock_y(point: &Point) -> f32 {
+    SHIP_MID_Y + grid_coord_to_transform(point).y - grid_coord_to_transform(&BOAT_POINT).y
+}
+
+// Port ships sit at SHIP_X, and starboard ships the same distance out from the far
+// side of the hold
+fn ship_x(boarding_points: &[BoardingPoint], index: usize) -> f32 {
+    let boarding_point = &boarding_points[index];
+    let ships_inside = boarding_points[..index]
+        .iter()
+        .filter(|other| {
+            other.kind == BoardingKind::Ship
+                && other.is_starboard() == boarding_point.is_starboard()
+        })
+        .count();
+    let further_out = ships_inside as f32 * SHIP_SPACING;
+
+    if boarding_point.is_starboard() {
+        let port_edge = grid_coord_to_transform(&Point { x: 0, y: 0 }).x;
+        let starboard_edge = grid_coord_to_transform(&Point {
+            x: GRID_WIDTH - 1,
+            y: 0,
+        })
+        .x;
+        starboard_edge + (port_edge - SHIP_X) + further_out
+    } else {
+        SHIP_X - further_out
+    }
+}
+
+// where pirates step off the ship of `boarding_points[index]`, or drop in through its hatch
+pub fn deck_location(boarding_points: &[BoardingPoint], index: usize) -> Vec2 {
+    let boarding_point = &boarding_points[index];
+    match boarding_point.kind {
+        BoardingKind::Ship => Vec2::new(
+            ship_x(boarding_points, index),
+            dock_y(&boarding_point.point),
+        ),
+        BoardingKind::Hatch => grid_coord_to_transform(&boarding_point.point),
+    }
+}
+
+// how long after the wave starts pirates can start coming aboard
+pub fn docking_time(boarding_point: &BoardingPoint) -> f32 {
+    match boarding_point.kind {
+        BoardingKind::Ship => (SHIP_START_Y - dock_y(&boarding_point.point)) / SHIP_SPEED,
+        BoardingKind::Hatch => 0.0,
+    }
 }
 
-pub fn spawn_ship(mut commands: Commands, asset_server: Res<AssetServer>) {
-    commands.spawn((
-        Ship {
-            speed: SHIP_SPEED,
-            target_y: SHIP_START_Y,
-        },
-        Sprite {
-            image: asset_server.load("images/ship.png"),
-            ..default()
-        },
-        Transform::from_xyz(SHIP_X, SHIP_START_Y, 1.0),
-    ));
+fn spawn_boarding_points(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+    boarding_points: &[BoardingPoint],
+) {
+    for (index, boarding_point) in boarding_points.iter().enumerate() {
+        match boarding_point.kind {
+            BoardingKind::Ship => {
+                // the gangplank runs from the middle of the deck to the boarding point
+                let deck = deck_location(boarding_points, index);
+                let gangplank_end = grid_coord_to_transform(&boarding_point.point) - deck;
+                let gangplank = Rectangle::new(gangplank_end.x.abs(), GANGPLANK_WIDTH);
+
+                commands
+                    .spawn((
+                        Ship {
+                            speed: SHIP_SPEED,
+                            target_y: SHIP_START_Y,
+                            dock_y: dock_y(&boarding_point.point),
+                            boarding_point: boarding_point.point,
+                        },
+                        Sprite {
+                            image: asset_server.load("images/ship.png"),
+                            flip_x: boarding_point.is_starboard(),
+                            ..default()
+                        },
+                        Transform::from_xyz(deck.x, SHIP_START_Y, 1.0),
+                        TickPosition::new(Vec2::new(deck.x, SHIP_START_Y)),
+                    ))
+                    .with_child((
+                        Mesh2d(meshes.add(gangplank)),
+                        MeshMaterial2d(materials.add(Color::linear_rgba(0.45, 0.3, 0.15, 1.0))),
+                        Transform::from_xyz(gangplank_end.x / 2.0, gangplank_end.y, -0.1),
+                    ));
+            }
+            BoardingKind::Hatch => {
+                let location = grid_coord_to_transform(&boarding_point.point);
+                commands.spawn((
+                    Hatch,
+                    Mesh2d(meshes.add(Rectangle::new(TILE_SIZE * 0.8, TILE_SIZE * 0.8))),
+                    MeshMaterial2d(materials.add(Color::linear_rgba(0.2, 0.1, 0.03, 1.0))),
+                    Transform::from_translation(location.extend(2.0)),
+                ));
+            }
+        }
+    }
+}
+
+pub fn spawn_ship(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    boarding_points: Res<BoardingPoints>,
+) {
+    spawn_boarding_points(
+        &mut commands,
+        &asset_server,
+        &mut meshes,
+        &mut materials,
+        &boarding_points.points,
+    );
+}
+
+fn on_boarding_points_replaced(
+    mut commands: Commands,
+    mut evr_boarding_points_replaced: EventReader<BoardingPointsReplaced>,
+    asset_server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    q_ships: Query<Entity, Or<(With<Ship>, With<Hatch>)>>,
+) {
+    for event in evr_boarding_points_replaced.read() {
+        for entity in q_ships.iter() {
+            commands.entity(entity).despawn();
+        }
+        spawn_boarding_points(
+            &mut commands,
+            &asset_server,
+            &mut meshes,
+            &mut materials,
+            &event.points,
+        );
+    }
 }
 
 pub fn move_ship(mut ship_query: Query<(&mut Transform, &Ship)>, time: Res<Time>) {
@@ -39,30 +190,65 @@ pub fn move_ship(mut ship_query: Query<(&mut Transform, &Ship)>, time: Res<Time>
     }
 }
 
+// every spawner belongs to a boarding point, so only the ships that are needed come
+// in. Crew sent for in the middle of a wave come off a ship that's already there
 pub fn move_ship_in(
-    mut ship_query: Query<(&mut Transform, &mut Ship)>,
-    mut evr_wave_started: EventReader<WaveStarted>,
+    mut ship_query: Query<(&mut Transform, &mut TickPosition, &mut Ship)>,
+    q_new_spawners: Query<&WaveState, Added<WaveState>>,
 ) {
-    for _ in evr_wave_started.read() {
-        for (mut transform, mut ship) in ship_query.iter_mut() {
-            transform.translation.y = SHIP_START_Y;
-            ship.target_y = SHIP_MID_Y;
+    for wave_state in q_new_spawners.iter() {
+        for (mut transform, mut position, mut ship) in ship_query.iter_mut() {
+            if ship.boarding_point == wave_state.boarding_point.point
+                && ship.target_y != ship.dock_y
+            {
+                let start = Vec2::new(transform.translation.x, SHIP_START_Y);
+                position.jump_to(&mut transform, start);
+                ship.target_y = ship.dock_y;
+            }
         }
     }
 }
 
+// once every pirate is back aboard or dead, the docked ships sail away
 pub fn move_ship_out(
     mut ship_query: Query<&mut Ship>,
-    mut evr_wave_complete: EventReader<WaveComplete>,
+    q_pirates: Query<(), With<Pirate>>,
+    q_spawners: Query<(), With<Spawner>>,
 ) {
-    for _ in evr_wave_complete.read() {
-        for mut ship in ship_query.iter_mut() {
+    if !q_pirates.is_empty() || !q_spawners.is_empty() {
+        return;
+    }
+
+    // ships that stayed away this wave stay out of sight
+    for mut ship in ship_query.iter_mut() {
+        if ship.target_y == ship.dock_y {
             ship.target_y = SHIP_OUT_Y;
         }
     }
 }
 
+pub fn reset_ship(
+    mut ship_query: Query<(&mut Transform, &mut TickPosition, &mut Ship)>,
+    mut evr_wave_reset: EventReader<WaveReset>,
+) {
+    for _ in evr_wave_reset.read() {
+        for (mut transform, mut position, mut ship) in ship_query.iter_mut() {
+            let start = Vec2::new(transform.translation.x, SHIP_START_Y);
+            position.jump_to(&mut transform, start);
+            ship.target_y = SHIP_START_Y;
+        }
+    }
+}
+
 pub fn plugin(app: &mut App) {
     app.add_systems(Startup, spawn_ship);
-    app.add_systems(Update, (move_ship, move_ship_in, move_ship_out));
+    app.add_systems(Update, (on_boarding_points_replaced, reset_ship).chain());
+    // ships move on the wave's ticks, so pirates start coming off them on the same
+    // tick however fast the game is drawn
+    app.add_systems(
+        FixedUpdate,
+        (move_ship_in, move_ship_out, move_ship)
+            .chain()
+            .before(pirate_spawn_system),
+    );
 }
@@ -0,0 +1,316 @@
+use bevy::prelude::*;
+use grid_util::point::Point;
+
+use crate::game::boarding::{BoardingKind, BoardingPoint, pirates_at_boarding_point};
+use crate::game::drain::{DrainSource, drain_shares};
+use crate::game::goldbar::{gold_room_positions, loose_gold_tile};
+use crate::game::level::WaveSpec;
+use crate::game::network::{PULSE_DAMAGE, PULSE_STUN, PULSE_WINDED, chain_networks, network_sizes};
+use crate::game::oxidation::Oxidation;
+use crate::game::pirate::{
+    CrowdMember, GOLD_PICKUP_DISTANCE, PANIC_HASTE, PirateState, SPAWN_INTERVAL, WAVE_TICK,
+    find_closest, follow_path, grid_coord_to_transform, in_breathing_range, must_give_way,
+    nearest_exit, next_morale, path_target, pathing_grid_from_tiles, step_towards,
+    vec_to_grid_coord,
+};
+use crate::game::puzzle::SolutionChain;
+use crate::game::ship::{deck_location, docking_time};
+use crate::game::status_effects::StatusEffects;
+use crate::game::tile::Tile;
+
+// Headless version of a single wave, built from the same helpers as the pirate
+// systems, so hand-authored content can be checked without running the game.
+// Puzzles have no trees, so panicking pirates always run for an exit, and a
+// single wave never has veterans. Pulses along chain networks land everywhere
+// at once instead of travelling along them. A link that rusts right through stops
+// draining and lets pirates through, just as it does in the game.
+
+// the same ticks the game plays waves in
+const SIMULATION_STEP: f32 = WAVE_TICK;
+
+// pirates that can't reach anything would otherwise keep the wave going forever
+const SIMULATION_TIME_LIMIT: f32 = 600.0;
+
+#[derive(Debug, Default)]
+pub struct SimulationResult {
+    pub pirates_killed: u32,
+    pub gold_lost: u32,
+}
+
+impl SimulationResult {
+    // every pirate suffocated and none of them got away with gold
+    pub fn is_flawless(&self, wave: &WaveSpec) -> bool {
+        self.pirates_killed == wave.pirates && self.gold_lost == 0
+    }
+}
+
+struct SimulatedPirate {
+    location: Vec2,
+    // still walking down the gangplank, out of reach of the chains
+    boarding_at: Option<Vec2>,
+    order: u32,
+    oxygen: f32,
+    state: PirateState,
+    carrying_gold: bool,
+    effects: StatusEffects,
+}
+
+pub fn simulate_wave(
+    crates: &[Tile],
+    chains: &[SolutionChain],
+    boarding_points: &[BoardingPoint],
+    wave: &WaveSpec,
+) -> SimulationResult {
+    let open = wave.boarding_points.min(wave.pirates as usize);
+    let boarding_points = &boarding_points[..open.clamp(1, boarding_points.len())];
+    let obstacles = crates
+        .iter()
+        .copied()
+        .chain(chains.iter().flat_map(|chain| chain.tiles.iter().copied()))
+        .collect::<Vec<_>>();
+    let mut pathing_grid = pathing_grid_from_tiles(obstacles.iter().copied());
+    // solution chains are each drawn on their own, so none of them are branches
+    let networks = chain_networks(
+        &chains
+            .iter()
+            .map(|chain| (chain.tiles[0], chain.tiles[chain.tiles.len() - 1]))
+            .collect::<Vec<_>>(),
+        &[],
+    );
+    let sizes = network_sizes(&networks);
+    // chains start out fresh. `source_networks` has the network each source is
+    // part of, and `source_tiles` the tile it's on
+    let mut source_networks = Vec::new();
+    let mut source_tiles = Vec::new();
+    let mut chain_sources = Vec::new();
+    for (i, chain) in chains.iter().enumerate() {
+        for tile in chain.tiles.iter() {
+            source_networks.push(networks[i]);
+            source_tiles.push(*tile);
+            chain_sources.push(DrainSource {
+                position: tile.grid_coord_to_transform(0.0).translation.xy(),
+                material: chain.material,
+                oxidation: Oxidation::default(),
+                network_size: sizes[i],
+            });
+        }
+    }
+    let mut gold = gold_room_positions()
+        .iter()
+        .map(|pos| {
+            Tile { x: pos.x, y: pos.y }
+                .grid_coord_to_transform(0.0)
+                .translation
+                .xy()
+        })
+        .collect::<Vec<_>>();
+
+    let mut result = SimulationResult::default();
+    let mut pirates: Vec<SimulatedPirate> = Vec::new();
+    // every boarding point runs its own spawner, just like in the game
+    let mut pirates_spawned = vec![0; boarding_points.len()];
+    let mut elapsed = 0.0;
+    let mut rusted_through = 0;
+
+    while elapsed < SIMULATION_TIME_LIMIT {
+        elapsed += SIMULATION_STEP;
+
+        for (i, boarding_point) in boarding_points.iter().enumerate() {
+            let spawned = pirates_spawned[i];
+            if spawned < pirates_at_boarding_point(wave.pirates, boarding_points.len(), i)
+                && elapsed >= docking_time(boarding_point) + SPAWN_INTERVAL * (spawned + 1) as f32
+            {
+                let boarding_at = match boarding_point.kind {
+                    BoardingKind::Ship => Some(grid_coord_to_transform(&boarding_point.point)),
+                    BoardingKind::Hatch => None,
+                };
+                pirates.push(SimulatedPirate {
+                    location: deck_location(boarding_points, i),
+                    boarding_at,
+                    order: pirates_spawned.iter().sum(),
+                    oxygen: wave.oxygen,
+                    state: PirateState::PathingGold,
+                    carrying_gold: false,
+                    effects: StatusEffects::default(),
+                });
+                pirates_spawned[i] += 1;
+            }
+        }
+
+        let crowd = pirates
+            .iter()
+            .filter(|pirate| pirate.boarding_at.is_none())
+            .map(|pirate| CrowdMember {
+                location: pirate.location,
+                exiting: pirate.state != PirateState::PathingGold,
+                order: pirate.order,
+            })
+            .collect::<Vec<_>>();
+
+        let mut suffocated_at = Vec::new();
+        pirates.retain_mut(|pirate| {
+            pirate.effects.tick(SIMULATION_STEP);
+            let mut travel = wave.movement_speed * SIMULATION_STEP;
+            if let Some(boarding_at) = pirate.boarding_at {
+                pirate.location = step_towards(pirate.location, boarding_at, travel);
+                if pirate.location == boarding_at {
+                    pirate.boarding_at = None;
+                }
+                return true;
+            }
+
+            let nearest_gold_point =
+                match find_closest(gold.iter().copied().enumerate(), pirate.location) {
+                    Some((idx, gold_location)) => {
+                        if !pirate.carrying_gold
+                            && pirate.state != PirateState::Panicking
+                            && pirate.location.distance(gold_location) < GOLD_PICKUP_DISTANCE
+                        {
+                            pirate.state = PirateState::PathingExit;
+                            pirate.carrying_gold = true;
+                            gold.swap_remove(idx);
+                        }
+                        vec_to_grid_coord(&gold_location)
+                    }
+                    None => {
+                        if pirate.state == PirateState::PathingGold {
+                            pirate.state = PirateState::PathingExit;
+                        }
+                        boarding_points[0].point
+                    }
+                };
+
+            let end = match pirate.state {
+                PirateState::PathingGold => nearest_gold_point,
+                PirateState::PathingExit | PirateState::Panicking => {
+                    nearest_exit(&pathing_grid, pirate.location, boarding_points)
+                }
+            };
+            let target = path_target(&pathing_grid, pirate.location, end);
+            let me = CrowdMember {
+                location: pirate.location,
+                exiting: pirate.state != PirateState::PathingGold,
+                order: pirate.order,
+            };
+            travel *= pirate.effects.modifiers().speed;
+            if !must_give_way(&me, target, &crowd) {
+                pirate.location = follow_path(&pathing_grid, pirate.location, end, travel);
+            }
+
+            if pirate.state != PirateState::PathingGold
+                && vec_to_grid_coord(&pirate.location) == end
+            {
+                if pirate.carrying_gold {
+                    result.gold_lost += 1;
+                }
+                return false;
+            }
+
+            let multiplier = pirate.effects.modifiers().oxygen_drain * SIMULATION_STEP;
+            let shares = drain_shares(pirate.location, &chain_sources);
+            for (source, share) in chain_sources.iter_mut().zip(shares) {
+                if share > 0.0 {
+                    pirate.oxygen -= share * multiplier;
+                    source.oxidation.absorb(share * multiplier, source.material);
+                }
+            }
+
+            let suffocated = pirate.oxygen <= 0.0;
+            let panicked = !suffocated
+                && next_morale(pirate.state, pirate.oxygen, wave.oxygen)
+                    == Some(PirateState::Panicking);
+            if panicked {
+                pirate.state = PirateState::Panicking;
+                pirate.effects.apply(PANIC_HASTE);
+            }
+
+            if suffocated || panicked {
+                if pirate.carrying_gold {
+                    pirate.carrying_gold = false;
+                    let point = vec_to_grid_coord(&pirate.location);
+                    let tile = loose_gold_tile(
+                        Tile {
+                            x: point.x,
+                            y: point.y,
+                        },
+                        &pathing_grid,
+                        |tile| {
+                            gold.iter().any(|gold_location| {
+                                vec_to_grid_coord(gold_location)
+                                    == Point {
+                                        x: tile.x,
+                                        y: tile.y,
+                                    }
+                            })
+                        },
+                    );
+                    gold.push(tile.grid_coord_to_transform(0.0).translation.xy());
+                }
+                if suffocated {
+                    suffocated_at.push(pirate.location);
+                    result.pirates_killed += 1;
+                    return false;
+                }
+            }
+
+            true
+        });
+
+        // rusted-through links stop being obstacles, like they do in the game
+        let rusted = chain_sources
+            .iter()
+            .zip(source_tiles.iter())
+            .filter(|(source, _)| source.oxidation.is_rusted_through())
+            .map(|(_, tile)| *tile)
+            .collect::<Vec<_>>();
+        if rusted.len() != rusted_through {
+            rusted_through = rusted.len();
+            pathing_grid = pathing_grid_from_tiles(
+                obstacles
+                    .iter()
+                    .copied()
+                    .filter(|tile| !rusted.contains(tile)),
+            );
+        }
+
+        for location in suffocated_at {
+            let Some(network) = chain_sources
+                .iter()
+                .zip(source_networks.iter())
+                .filter(|(source, _)| {
+                    !source.oxidation.is_rusted_through()
+                        && in_breathing_range(location, source.position)
+                })
+                .min_by(|(a, _), (b, _)| {
+                    let a = a.position.distance(location);
+                    let b = b.position.distance(location);
+                    a.total_cmp(&b)
+                })
+                .map(|(_, network)| *network)
+            else {
+                continue;
+            };
+
+            for pirate in pirates.iter_mut() {
+                let beside_network = chain_sources.iter().zip(source_networks.iter()).any(
+                    |(source, source_network)| {
+                        *source_network == network
+                            && !source.oxidation.is_rusted_through()
+                            && in_breathing_range(pirate.location, source.position)
+                    },
+                );
+                if pirate.boarding_at.is_none() && beside_network {
+                    pirate.oxygen -= PULSE_DAMAGE;
+                    pirate.effects.apply(PULSE_STUN);
+                    pirate.effects.apply(PULSE_WINDED);
+                }
+            }
+        }
+
+        if pirates_spawned.iter().sum::<u32>() == wave.pirates && pirates.is_empty() {
+            break;
+        }
+    }
+
+    result
+}
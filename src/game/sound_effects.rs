@@ -1,10 +1,13 @@
-use bevy::{audio::Volume, prelude::*};
+use bevy::prelude::*;
+use rand::Rng;
 
 use crate::game::events::*;
+use crate::game::settings::{Channel, Sound};
 
 pub fn plugin(app: &mut App) {
     app.add_systems(Startup, wind_ocean_noise);
     app.add_systems(Update, on_pirate_death);
+    app.add_systems(Update, on_pirate_footstep);
     app.add_systems(Update, on_gold_pickup);
     app.add_systems(Update, on_gold_drop);
     app.add_systems(Update, on_gold_lost);
@@ -12,6 +15,8 @@ pub fn plugin(app: &mut App) {
     app.add_systems(Update, on_wave_start);
     app.add_systems(Update, on_play_click);
     app.add_systems(Update, on_play_long_click);
+    app.add_systems(Update, on_button_click);
+    app.add_systems(Update, on_button_hover);
     app.add_systems(Update, on_chain_placed);
     app.add_systems(Update, on_chain_finished);
 }
@@ -22,8 +27,26 @@ fn on_pirate_death(
     mut evr_pirate_death: EventReader<PirateDeath>,
 ) {
     for _ in evr_pirate_death.read() {
-        commands.spawn(AudioPlayer::new(
-            asset_server.load("audio/sound_effects/death.ogg"),
+        commands.spawn((
+            Sound::new(Channel::Sfx, 1.0),
+            AudioPlayer::new(asset_server.load("audio/sound_effects/death.ogg")),
+        ));
+    }
+}
+
+// there are a lot of feet about, so keep them quiet and mix up which step plays
+fn on_pirate_footstep(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut evr_footstep: EventReader<PirateFootstep>,
+) {
+    let mut rng = rand::thread_rng();
+    for _ in evr_footstep.read() {
+        let step = rng.gen_range(1..=4);
+        commands.spawn((
+            Sound::new(Channel::Sfx, 0.3),
+            AudioPlayer::new(asset_server.load(format!("audio/sound_effects/step{step}.ogg"))),
+            PlaybackSettings::DESPAWN,
         ));
     }
 }
@@ -34,8 +57,9 @@ fn on_gold_pickup(
     mut evr_gold_pickup: EventReader<GoldBarCollected>,
 ) {
     for _ in evr_gold_pickup.read() {
-        commands.spawn(AudioPlayer::new(
-            asset_server.load("audio/sound_effects/gold_pickup.ogg"),
+        commands.spawn((
+            Sound::new(Channel::Sfx, 1.0),
+            AudioPlayer::new(asset_server.load("audio/sound_effects/gold_pickup.ogg")),
         ));
     }
 }
@@ -46,8 +70,9 @@ fn on_gold_drop(
     mut evr_gold_drop: EventReader<GoldBarDropped>,
 ) {
     for _ in evr_gold_drop.read() {
-        commands.spawn(AudioPlayer::new(
-            asset_server.load("audio/sound_effects/gold_drop.ogg"),
+        commands.spawn((
+            Sound::new(Channel::Sfx, 1.0),
+            AudioPlayer::new(asset_server.load("audio/sound_effects/gold_drop.ogg")),
         ));
     }
 }
@@ -58,8 +83,9 @@ fn on_gold_lost(
     mut evr_gold_lost: EventReader<GoldBarLost>,
 ) {
     for _ in evr_gold_lost.read() {
-        commands.spawn(AudioPlayer::new(
-            asset_server.load("audio/sound_effects/gold_lost.ogg"),
+        commands.spawn((
+            Sound::new(Channel::Sfx, 1.0),
+            AudioPlayer::new(asset_server.load("audio/sound_effects/gold_lost.ogg")),
         ));
     }
 }
@@ -79,8 +105,9 @@ fn on_play_click(
     mut evr: EventReader<PlayClickSFX>,
 ) {
     for _ in evr.read() {
-        commands.spawn(AudioPlayer::new(
-            asset_server.load("audio/sound_effects/click_reverb_hover.ogg"),
+        commands.spawn((
+            Sound::new(Channel::Sfx, 1.0),
+            AudioPlayer::new(asset_server.load("audio/sound_effects/click_reverb_hover.ogg")),
         ));
     }
 }
@@ -91,8 +118,37 @@ fn on_play_long_click(
     mut evr: EventReader<PlayLongClickSFX>,
 ) {
     for _ in evr.read() {
-        commands.spawn(AudioPlayer::new(
-            asset_server.load("audio/sound_effects/click_reverb.ogg"),
+        commands.spawn((
+            Sound::new(Channel::Sfx, 1.0),
+            AudioPlayer::new(asset_server.load("audio/sound_effects/click_reverb.ogg")),
+        ));
+    }
+}
+
+fn on_button_click(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut evr: EventReader<PlayButtonClickSFX>,
+) {
+    for _ in evr.read() {
+        commands.spawn((
+            Sound::new(Channel::Sfx, 1.0),
+            AudioPlayer::new(asset_server.load("audio/sound_effects/button_click.ogg")),
+        ));
+    }
+}
+
+// brushing past a button shouldn't be as loud as pressing it
+fn on_button_hover(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut evr: EventReader<PlayButtonHoverSFX>,
+) {
+    for _ in evr.read() {
+        commands.spawn((
+            Sound::new(Channel::Sfx, 0.5),
+            AudioPlayer::new(asset_server.load("audio/sound_effects/button_hover.ogg")),
+            PlaybackSettings::DESPAWN,
         ));
     }
 }
@@ -103,8 +159,9 @@ fn on_prize_collect(
     mut evr_prize: EventReader<PrizeCollected>,
 ) {
     for _ in evr_prize.read() {
-        commands.spawn(AudioPlayer::new(
-            asset_server.load("audio/sound_effects/power_up_jingle.ogg"),
+        commands.spawn((
+            Sound::new(Channel::Sfx, 1.0),
+            AudioPlayer::new(asset_server.load("audio/sound_effects/power_up_jingle.ogg")),
         ));
     }
 }
@@ -115,8 +172,9 @@ fn on_chain_placed(
     mut evr: EventReader<ChainPlaced>,
 ) {
     for _ in evr.read() {
-        commands.spawn(AudioPlayer::new(
-            asset_server.load("audio/sound_effects/chain_placed.ogg"),
+        commands.spawn((
+            Sound::new(Channel::Sfx, 1.0),
+            AudioPlayer::new(asset_server.load("audio/sound_effects/chain_placed.ogg")),
         ));
     }
 }
@@ -127,19 +185,17 @@ fn on_chain_finished(
     mut evr: EventReader<ChainFinished>,
 ) {
     for _ in evr.read() {
-        commands.spawn(AudioPlayer::new(
-            asset_server.load("audio/sound_effects/chain_finished.ogg"),
+        commands.spawn((
+            Sound::new(Channel::Sfx, 1.0),
+            AudioPlayer::new(asset_server.load("audio/sound_effects/chain_finished.ogg")),
         ));
     }
 }
 
 fn wind_ocean_noise(mut commands: Commands, asset_server: Res<AssetServer>) {
     commands.spawn((
+        Sound::new(Channel::Ambience, 0.5),
         AudioPlayer::new(asset_server.load("audio/sound_effects/ocean_wind_noise.ogg")),
-        PlaybackSettings {
-            mode: bevy::audio::PlaybackMode::Loop,
-            volume: Volume::Linear(0.5),
-            ..default()
-        },
+        PlaybackSettings::LOOP,
     ));
 }
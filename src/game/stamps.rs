@@ -0,0 +1,539 @@
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+
+use crate::game::boarding::BoardingPoints;
+use crate::game::chain::{
+    ChainButton, ChainButtonStock, ChainSegment, MainInventoryChainButton, Obstacle, SelectedChain,
+    spawn_chain,
+};
+use crate::game::events::{ChainFinished, PlayClickSFX};
+use crate::game::game_state::GameState;
+use crate::game::goldbar::LooseGold;
+use crate::game::input::{GridCursor, InputAction, update_grid_cursor};
+use crate::game::pirate::{hold_reachable, pathing_grid_from_tiles};
+use crate::game::save;
+use crate::game::tile::{BackgroundTile, GRID_HEIGHT, GRID_WIDTH, TILE_SIZE, Tile};
+use crate::game::ui::{
+    BUTTON_COLOR, ButtonClicked, ButtonColors, HUD_MARGIN, Placed, UiButton, button, label,
+};
+
+// Stamps are chain shapes saved for laying down again in one click. Right-click
+// a chain while building to save its shape, pick a stamp from the bar in the top
+// left corner, turn it with R, flip it with F and click to put a chain from the
+// inventory down in that shape. All of those keys can be rebound.
+
+const STAMPS_SAVE: &str = "stamps";
+
+// saving another one pushes the oldest out
+const MAX_STAMPS: usize = 4;
+
+const STAMP_BUTTON_SIZE: Vec2 = Vec2::new(110.0, 80.0);
+const STAMP_BUTTON_SPACING: f32 = 130.0;
+// how big a tile of the shape is drawn on its button
+const STAMP_PREVIEW_TILE: f32 = 9.0;
+
+const SELECTED_BUTTON_COLOR: Color = Color::linear_rgba(0.0, 1.0, 0.0, 1.0);
+const GHOST_COLOR: Color = Color::linear_rgba(0.0, 1.0, 0.0, 0.4);
+const BLOCKED_GHOST_COLOR: Color = Color::linear_rgba(1.0, 0.0, 0.0, 0.4);
+
+// every stamp is the tiles of a chain in the order it was drawn, relative to its
+// first tile
+#[derive(Resource, Default)]
+struct Stamps {
+    shapes: Vec<Vec<Tile>>,
+}
+
+impl Stamps {
+    // one stamp per line, as space separated x,y pairs
+    fn serialize(&self) -> String {
+        self.shapes
+            .iter()
+            .map(|shape| {
+                shape
+                    .iter()
+                    .map(|tile| format!("{},{}", tile.x, tile.y))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn deserialize(contents: &str) -> Self {
+        let shapes = contents
+            .lines()
+            .filter_map(|line| {
+                line.split_whitespace()
+                    .map(|pair| {
+                        let (x, y) = pair.split_once(',')?;
+                        Some(Tile {
+                            x: x.parse().ok()?,
+                            y: y.parse().ok()?,
+                        })
+                    })
+                    .collect::<Option<Vec<_>>>()
+                    .filter(|shape| !shape.is_empty())
+            })
+            .take(MAX_STAMPS)
+            .collect();
+        Self { shapes }
+    }
+}
+
+// the stamp being placed, if there is one, and which way round it is
+#[derive(Resource, Default)]
+struct StampTool {
+    selected: Option<usize>,
+    // quarter turns anticlockwise
+    turns: u8,
+    mirrored: bool,
+}
+
+#[derive(Component)]
+struct StampBar;
+
+#[derive(Component)]
+struct StampButton(usize);
+
+#[derive(Component)]
+struct StampGhost;
+
+// the tiles a stamp covers with its first tile on `anchor`
+fn stamp_tiles(shape: &[Tile], anchor: Tile, turns: u8, mirrored: bool) -> Vec<Tile> {
+    shape
+        .iter()
+        .map(|offset| {
+            let (mut x, mut y) = (offset.x, offset.y);
+            if mirrored {
+                x = -x;
+            }
+            for _ in 0..turns % 4 {
+                (x, y) = (-y, x);
+            }
+            Tile {
+                x: anchor.x + x,
+                y: anchor.y + y,
+            }
+        })
+        .collect()
+}
+
+// a stamp can go down if every tile is free hold and pirates can still get to
+// the back of the hold around it
+fn can_place(tiles: &[Tile], occupied: &[Tile], boarding_points: &BoardingPoints) -> bool {
+    let in_hold =
+        |tile: &Tile| (1..GRID_WIDTH).contains(&tile.x) && (0..GRID_HEIGHT).contains(&tile.y);
+    if !tiles
+        .iter()
+        .all(|tile| in_hold(tile) && !occupied.contains(tile))
+    {
+        return false;
+    }
+
+    let pathing_grid = pathing_grid_from_tiles(occupied.iter().chain(tiles.iter()).copied());
+    hold_reachable(&pathing_grid, &boarding_points.points)
+}
+
+// the grid cursor while it's being moved about, otherwise whatever the mouse is over
+fn pointed_tile(cursor: &GridCursor, q_tiles: &Query<(&Tile, &BackgroundTile)>) -> Option<Tile> {
+    if cursor.shown {
+        return Some(cursor.tile);
+    }
+    q_tiles
+        .iter()
+        .find(|(_, background_tile)| background_tile.is_hovered)
+        .map(|(tile, _)| *tile)
+}
+
+fn load_stamps(mut commands: Commands) {
+    let stamps = save::load(STAMPS_SAVE)
+        .map(|contents| Stamps::deserialize(&contents))
+        .unwrap_or_default();
+    commands.insert_resource(stamps);
+}
+
+fn setup(mut commands: Commands) {
+    commands.spawn((
+        StampBar,
+        Placed {
+            anchor: Anchor::TopLeft,
+            offset: Vec2::splat(HUD_MARGIN),
+        },
+        Node {
+            align_items: AlignItems::Center,
+            column_gap: Val::Px(STAMP_BUTTON_SPACING - STAMP_BUTTON_SIZE.x),
+            ..default()
+        },
+        Visibility::Hidden,
+    ));
+}
+
+fn show_stamp_bar(mut q_bar: Query<&mut Visibility, With<StampBar>>) {
+    for mut visibility in q_bar.iter_mut() {
+        *visibility = Visibility::Visible;
+    }
+}
+
+fn hide_stamp_bar(
+    mut commands: Commands,
+    mut tool: ResMut<StampTool>,
+    mut q_bar: Query<&mut Visibility, With<StampBar>>,
+    q_ghosts: Query<Entity, With<StampGhost>>,
+) {
+    tool.selected = None;
+    for mut visibility in q_bar.iter_mut() {
+        *visibility = Visibility::Hidden;
+    }
+    for entity in q_ghosts.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn stamp_button_colors(tool: &StampTool, stamp: usize) -> ButtonColors {
+    if tool.selected == Some(stamp) {
+        ButtonColors::filled(SELECTED_BUTTON_COLOR)
+    } else {
+        ButtonColors::filled(BUTTON_COLOR)
+    }
+}
+
+// the bar is rebuilt whenever a stamp is saved, and recoloured whenever one is picked
+fn update_stamp_bar(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    stamps: Res<Stamps>,
+    tool: Res<StampTool>,
+    q_bar: Query<(Entity, Option<&Children>), With<StampBar>>,
+    mut q_buttons: Query<(&StampButton, &mut UiButton)>,
+) {
+    if tool.is_changed() {
+        for (stamp_button, mut button) in q_buttons.iter_mut() {
+            button.colors = stamp_button_colors(&tool, stamp_button.0);
+        }
+    }
+
+    if !stamps.is_changed() {
+        return;
+    }
+
+    let Ok((e_bar, children)) = q_bar.single() else {
+        return;
+    };
+    for child in children.iter().flat_map(|children| children.iter()) {
+        commands.entity(child).despawn();
+    }
+
+    if stamps.shapes.is_empty() {
+        commands.entity(e_bar).with_child((
+            label(
+                &asset_server,
+                "Right-click a chain to save it as a stamp",
+                20.0,
+                Color::linear_rgba(1.0, 1.0, 1.0, 1.0),
+            ),
+            TextShadow::default(),
+        ));
+        return;
+    }
+
+    for (i, shape) in stamps.shapes.iter().enumerate() {
+        // draw the shape in the middle of the button
+        let min = shape
+            .iter()
+            .fold(IVec2::MAX, |min, tile| min.min(IVec2::new(tile.x, tile.y)));
+        let max = shape
+            .iter()
+            .fold(IVec2::MIN, |max, tile| max.max(IVec2::new(tile.x, tile.y)));
+        let extent = (max - min + IVec2::ONE).as_vec2() * STAMP_PREVIEW_TILE;
+
+        commands.entity(e_bar).with_children(|parent| {
+            parent
+                .spawn((StampButton(i), button(STAMP_BUTTON_SIZE)))
+                .insert(UiButton {
+                    colors: stamp_button_colors(&tool, i),
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn(Node {
+                            width: Val::Px(extent.x),
+                            height: Val::Px(extent.y),
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            for tile in shape.iter() {
+                                // ui is measured down from the top
+                                let pos =
+                                    Vec2::new((tile.x - min.x) as f32, (max.y - tile.y) as f32)
+                                        * STAMP_PREVIEW_TILE;
+                                parent.spawn((
+                                    Node {
+                                        position_type: PositionType::Absolute,
+                                        left: Val::Px(pos.x),
+                                        top: Val::Px(pos.y),
+                                        width: Val::Px(STAMP_PREVIEW_TILE - 1.0),
+                                        height: Val::Px(STAMP_PREVIEW_TILE - 1.0),
+                                        ..default()
+                                    },
+                                    BackgroundColor(Color::linear_rgba(1.0, 1.0, 1.0, 1.0)),
+                                ));
+                            }
+                        });
+                });
+        });
+    }
+}
+
+// right-clicking a chain saves its shape, or puts down the stamp being held
+fn save_stamp(
+    mut evr_action: EventReader<InputAction>,
+    cursor: Res<GridCursor>,
+    mut stamps: ResMut<Stamps>,
+    mut tool: ResMut<StampTool>,
+    q_tiles: Query<(&Tile, &BackgroundTile)>,
+    q_segments: Query<(&ChainSegment, &ChildOf)>,
+    q_children: Query<&Children>,
+    mut evw_sfx: EventWriter<PlayClickSFX>,
+) {
+    if !evr_action
+        .read()
+        .any(|action| *action == InputAction::SaveStamp)
+    {
+        return;
+    }
+
+    if tool.selected.is_some() {
+        tool.selected = None;
+        return;
+    }
+
+    let Some(tile) = pointed_tile(&cursor, &q_tiles) else {
+        return;
+    };
+    let Some((_, child_of)) = q_segments.iter().find(|(segment, _)| segment.tile == tile) else {
+        return;
+    };
+    let Ok(links) = q_children.get(child_of.parent()) else {
+        return;
+    };
+
+    let tiles = links
+        .iter()
+        .filter_map(|link| q_segments.get(link).ok())
+        .map(|(segment, _)| segment.tile)
+        .collect::<Vec<_>>();
+    let first = tiles[0];
+    let shape = tiles
+        .iter()
+        .map(|tile| Tile {
+            x: tile.x - first.x,
+            y: tile.y - first.y,
+        })
+        .collect::<Vec<_>>();
+
+    evw_sfx.write(PlayClickSFX);
+    if stamps.shapes.contains(&shape) {
+        return;
+    }
+    if stamps.shapes.len() == MAX_STAMPS {
+        stamps.shapes.remove(0);
+    }
+    stamps.shapes.push(shape);
+    save::store(STAMPS_SAVE, &stamps.serialize());
+}
+
+fn stamp_buttons(
+    mut commands: Commands,
+    mut evr_clicked: EventReader<ButtonClicked>,
+    mut tool: ResMut<StampTool>,
+    q_buttons: Query<&StampButton>,
+    mut q_selected_chain: Query<(Entity, &mut Sprite), With<SelectedChain>>,
+) {
+    let Some(button) = evr_clicked
+        .read()
+        .find_map(|event| q_buttons.get(event.button).ok())
+    else {
+        return;
+    };
+
+    if tool.selected == Some(button.0) {
+        tool.selected = None;
+        return;
+    }
+
+    *tool = StampTool {
+        selected: Some(button.0),
+        ..default()
+    };
+
+    // holding a stamp puts down the chain in hand, so a click on the hold places
+    // the stamp rather than starting a chain
+    for (entity, mut sprite) in q_selected_chain.iter_mut() {
+        sprite.color = Color::linear_rgba(1.0, 1.0, 1.0, 1.0);
+        commands.entity(entity).remove::<SelectedChain>();
+    }
+}
+
+// and picking up a chain puts the stamp down
+fn drop_stamp_on_chain_selected(
+    mut tool: ResMut<StampTool>,
+    q_selected_chain: Query<(), Added<SelectedChain>>,
+) {
+    if !q_selected_chain.is_empty() && tool.selected.is_some() {
+        tool.selected = None;
+    }
+}
+
+fn turn_stamp(mut evr_action: EventReader<InputAction>, mut tool: ResMut<StampTool>) {
+    if tool.selected.is_none() {
+        evr_action.clear();
+        return;
+    }
+
+    for action in evr_action.read() {
+        match action {
+            InputAction::TurnStamp => tool.turns = (tool.turns + 1) % 4,
+            InputAction::MirrorStamp => tool.mirrored = !tool.mirrored,
+            InputAction::Cancel => tool.selected = None,
+            _ => {}
+        }
+    }
+}
+
+// a ghost of the stamp follows the cursor around the hold, red where it can't go
+fn update_stamp_ghost(
+    mut commands: Commands,
+    stamps: Res<Stamps>,
+    tool: Res<StampTool>,
+    cursor: Res<GridCursor>,
+    boarding_points: Res<BoardingPoints>,
+    q_tiles: Query<(&Tile, &BackgroundTile)>,
+    q_obstacles: Query<&Obstacle>,
+    q_segments: Query<&ChainSegment>,
+    q_loose_gold: Query<&Tile, With<LooseGold>>,
+    q_ghosts: Query<Entity, With<StampGhost>>,
+    mut drawn: Local<Option<(Vec<Tile>, bool)>>,
+) {
+    let shape = tool
+        .selected
+        .and_then(|selected| stamps.shapes.get(selected));
+    let ghost = shape
+        .zip(pointed_tile(&cursor, &q_tiles))
+        .map(|(shape, anchor)| {
+            let tiles = stamp_tiles(shape, anchor, tool.turns, tool.mirrored);
+            let occupied = occupied_tiles(&q_obstacles, &q_segments, &q_loose_gold);
+            let placeable = can_place(&tiles, &occupied, &boarding_points);
+            (tiles, placeable)
+        });
+    if *drawn == ghost {
+        return;
+    }
+
+    for entity in q_ghosts.iter() {
+        commands.entity(entity).despawn();
+    }
+    if let Some((tiles, placeable)) = &ghost {
+        let color = if *placeable {
+            GHOST_COLOR
+        } else {
+            BLOCKED_GHOST_COLOR
+        };
+        for tile in tiles.iter() {
+            commands.spawn((
+                StampGhost,
+                Sprite::from_color(color, Vec2::splat(TILE_SIZE)),
+                tile.grid_coord_to_transform(4.0),
+            ));
+        }
+    }
+    *drawn = ghost;
+}
+
+fn occupied_tiles(
+    q_obstacles: &Query<&Obstacle>,
+    q_segments: &Query<&ChainSegment>,
+    q_loose_gold: &Query<&Tile, With<LooseGold>>,
+) -> Vec<Tile> {
+    q_obstacles
+        .iter()
+        .map(|obstacle| obstacle.tile)
+        .chain(q_segments.iter().map(|segment| segment.tile))
+        .chain(q_loose_gold.iter().copied())
+        .collect()
+}
+
+// clicking the hold with a stamp lays a chain of the same length from the
+// inventory down in its shape
+fn place_stamp(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut evr_action: EventReader<InputAction>,
+    cursor: Res<GridCursor>,
+    stamps: Res<Stamps>,
+    tool: Res<StampTool>,
+    boarding_points: Res<BoardingPoints>,
+    q_obstacles: Query<&Obstacle>,
+    q_segments: Query<&ChainSegment>,
+    q_loose_gold: Query<&Tile, With<LooseGold>>,
+    mut q_buttons: Query<(&mut ChainButton, &Children, &Transform), With<MainInventoryChainButton>>,
+    mut q_stock_text: Query<&mut Text2d, With<ChainButtonStock>>,
+    mut evw_chain_finished: EventWriter<ChainFinished>,
+) {
+    if !evr_action
+        .read()
+        .any(|action| *action == InputAction::BeginChain)
+    {
+        return;
+    }
+    let Some(shape) = tool
+        .selected
+        .and_then(|selected| stamps.shapes.get(selected))
+    else {
+        return;
+    };
+
+    let tiles = stamp_tiles(shape, cursor.tile, tool.turns, tool.mirrored);
+    let occupied = occupied_tiles(&q_obstacles, &q_segments, &q_loose_gold);
+    if !can_place(&tiles, &occupied, &boarding_points) {
+        return;
+    }
+
+    // take the leftmost chain in the inventory that's long enough
+    let Some((mut button, children, _)) = q_buttons
+        .iter_mut()
+        .filter(|(button, _, _)| button.stock > 0 && button.length == tiles.len() as u32)
+        .min_by(|(_, _, a), (_, _, b)| a.translation.x.total_cmp(&b.translation.x))
+    else {
+        return;
+    };
+    button.stock -= 1;
+    for child in children.iter() {
+        if let Ok(mut text) = q_stock_text.get_mut(child) {
+            text.0 = format!("{}", button.stock);
+        }
+    }
+
+    spawn_chain(&mut commands, &asset_server, &tiles, button.material);
+    evw_chain_finished.write(ChainFinished);
+}
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<StampTool>();
+    app.add_systems(Startup, (load_stamps, setup));
+    app.add_systems(OnEnter(GameState::Building), show_stamp_bar);
+    app.add_systems(OnExit(GameState::Building), hide_stamp_bar);
+    app.add_systems(
+        Update,
+        (
+            save_stamp,
+            stamp_buttons,
+            drop_stamp_on_chain_selected,
+            turn_stamp,
+            place_stamp,
+            update_stamp_ghost,
+        )
+            .chain()
+            .after(update_grid_cursor)
+            .run_if(in_state(GameState::Building)),
+    );
+    app.add_systems(Update, update_stamp_bar);
+}
@@ -0,0 +1,224 @@
+use bevy::prelude::*;
+
+use crate::game::game_state::GameState;
+use crate::game::pirate::pirate_movement_system;
+
+// Timed effects on pirates. Each source keeps its own instance of an effect, and
+// the pirate systems only ever look at the combined `EffectModifiers`.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EffectKind {
+    // moves slower, by `magnitude` of its speed
+    Slow,
+    // can't move at all
+    Stun,
+    // chains take `magnitude` more of its oxygen
+    OxygenDrain,
+    // turned around, heading for gold when it should be leaving and the other way round
+    Confusion,
+    // moves faster, by `magnitude` of its speed
+    Haste,
+}
+
+// how several instances of the same kind, from different sources, combine
+enum Stacking {
+    // only the strongest counts
+    Strongest,
+    // all of them add up
+    Additive,
+    // it's either on or off
+    Flag,
+}
+
+impl EffectKind {
+    fn stacking(&self) -> Stacking {
+        match self {
+            EffectKind::Slow | EffectKind::Haste => Stacking::Strongest,
+            EffectKind::OxygenDrain => Stacking::Additive,
+            EffectKind::Stun | EffectKind::Confusion => Stacking::Flag,
+        }
+    }
+
+    fn indicator_color(&self) -> Color {
+        match self {
+            EffectKind::Slow => Color::srgb(0.9, 0.85, 0.3),
+            EffectKind::Stun => Color::srgb(1.0, 1.0, 1.0),
+            EffectKind::OxygenDrain => Color::srgb(0.3, 0.5, 1.0),
+            EffectKind::Confusion => Color::srgb(0.8, 0.3, 0.9),
+            EffectKind::Haste => Color::srgb(1.0, 0.45, 0.1),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EffectSource {
+    GluePuddle,
+    Panic,
+    NetworkPulse,
+    FoolsGold,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct StatusEffect {
+    pub kind: EffectKind,
+    pub source: EffectSource,
+    pub magnitude: f32,
+    // seconds left
+    pub remaining: f32,
+}
+
+#[derive(Component, Default, Debug)]
+pub struct StatusEffects {
+    effects: Vec<StatusEffect>,
+}
+
+// everything the active effects add up to
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EffectModifiers {
+    pub speed: f32,
+    pub oxygen_drain: f32,
+    pub confused: bool,
+}
+
+impl StatusEffects {
+    // reapplying an effect from the same source tops it up instead of adding another
+    pub fn apply(&mut self, effect: StatusEffect) {
+        match self
+            .effects
+            .iter_mut()
+            .find(|existing| existing.kind == effect.kind && existing.source == effect.source)
+        {
+            Some(existing) => {
+                existing.remaining = existing.remaining.max(effect.remaining);
+                existing.magnitude = existing.magnitude.max(effect.magnitude);
+            }
+            None => self.effects.push(effect),
+        }
+    }
+
+    fn has_from(&self, kind: EffectKind, source: EffectSource) -> bool {
+        self.effects
+            .iter()
+            .any(|effect| effect.kind == kind && effect.source == source)
+    }
+
+    // for effects that last as long as their source does, rather than timing out
+    pub fn remove_source(&mut self, source: EffectSource) {
+        self.effects.retain(|effect| effect.source != source);
+    }
+
+    pub fn has(&self, kind: EffectKind) -> bool {
+        self.effects.iter().any(|effect| effect.kind == kind)
+    }
+
+    fn combined(&self, kind: EffectKind) -> f32 {
+        let magnitudes = self
+            .effects
+            .iter()
+            .filter(|effect| effect.kind == kind)
+            .map(|effect| effect.magnitude);
+        match kind.stacking() {
+            Stacking::Strongest => magnitudes.fold(0.0, f32::max),
+            Stacking::Additive => magnitudes.sum(),
+            Stacking::Flag => {
+                if self.has(kind) {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+
+    pub fn modifiers(&self) -> EffectModifiers {
+        let speed = if self.has(EffectKind::Stun) {
+            0.0
+        } else {
+            (1.0 - self.combined(EffectKind::Slow)).max(0.0)
+                * (1.0 + self.combined(EffectKind::Haste))
+        };
+
+        EffectModifiers {
+            speed,
+            oxygen_drain: 1.0 + self.combined(EffectKind::OxygenDrain),
+            confused: self.has(EffectKind::Confusion),
+        }
+    }
+
+    pub fn tick(&mut self, delta: f32) {
+        for effect in self.effects.iter_mut() {
+            effect.remaining -= delta;
+        }
+        self.effects.retain(|effect| effect.remaining > 0.0);
+    }
+}
+
+// Sources tend to reapply their effect every frame, so only a brand new effect
+// counts as a change
+pub fn apply_effect(effects: &mut Mut<StatusEffects>, effect: StatusEffect) {
+    let is_new = !effects.has_from(effect.kind, effect.source);
+    effects.bypass_change_detection().apply(effect);
+    if is_new {
+        effects.set_changed();
+    }
+}
+
+// a coloured pip over the pirate for each kind of effect it's under
+#[derive(Component)]
+struct EffectIndicator;
+
+const INDICATOR_SIZE: f32 = 16.0;
+const INDICATOR_Y: f32 = 95.0;
+
+pub fn tick_status_effects(time: Res<Time>, mut q_effects: Query<&mut StatusEffects>) {
+    for mut effects in q_effects.iter_mut() {
+        // only flag a change when something actually wears off, so indicators update sparingly
+        let active = effects.effects.len();
+        effects
+            .bypass_change_detection()
+            .tick(time.delta().as_secs_f32());
+        if effects.effects.len() != active {
+            effects.set_changed();
+        }
+    }
+}
+
+fn update_effect_indicators(
+    mut commands: Commands,
+    q_effects: Query<(Entity, &StatusEffects, Option<&Children>), Changed<StatusEffects>>,
+    q_indicators: Query<(), With<EffectIndicator>>,
+) {
+    for (entity, effects, children) in q_effects.iter() {
+        for child in children.into_iter().flatten() {
+            if q_indicators.contains(*child) {
+                commands.entity(*child).despawn();
+            }
+        }
+
+        let mut kinds: Vec<EffectKind> = Vec::new();
+        for effect in effects.effects.iter() {
+            if !kinds.contains(&effect.kind) {
+                kinds.push(effect.kind);
+            }
+        }
+
+        let start_x = -(kinds.len() as f32 - 1.0) * INDICATOR_SIZE * 0.6;
+        for (i, kind) in kinds.iter().enumerate() {
+            commands.entity(entity).with_child((
+                EffectIndicator,
+                Sprite::from_color(kind.indicator_color(), Vec2::splat(INDICATOR_SIZE)),
+                Transform::from_xyz(start_x + i as f32 * INDICATOR_SIZE * 1.2, INDICATOR_Y, 3.0),
+            ));
+        }
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(
+        FixedUpdate,
+        tick_status_effects
+            .before(pirate_movement_system)
+            .run_if(in_state(GameState::WaveInProgress)),
+    );
+    app.add_systems(Update, update_effect_indicators);
+}
@@ -22,7 +22,7 @@ pub struct BackgroundTile {
     pub is_hovered: bool,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Direction {
     Up,
     Down,
@@ -114,7 +114,7 @@ fn setup(
     }
 }
 
-fn mouse_events(
+pub fn mouse_events(
     mut q_tile: Query<
         (
             Entity,
@@ -1,84 +1,133 @@
 use bevy::prelude::*;
 
-use crate::game::{game_state::GameState, mouse::MousePos};
+use crate::game::{
+    events::OpenSettings,
+    game_state::{GameMode, GameState},
+    ui::{ButtonClicked, ButtonColors, UiButton, label, modal},
+};
 
-const TITLE_SCREEN_NEW_GAME_TEXT_SIZE: Vec2 = Vec2::new(400.0, 80.0);
-const TITLE_SCREEN_NEW_GAME_TEXT_POS: Vec2 = Vec2::new(480.0, -200.0);
+const TITLE_SCREEN_SIZE: Vec2 = Vec2::new(1920.0, 1080.0);
 
-#[derive(Component)]
-pub struct TitleScreen;
+// the menu sits in the bottom right of the picture, measured from its top left
+const TITLE_SCREEN_MENU_POS: Vec2 = Vec2::new(1240.0, 580.0);
+const TITLE_SCREEN_MENU_ITEM_SIZE: Vec2 = Vec2::new(400.0, 90.0);
+const TITLE_SCREEN_MENU_TEXT_SIZE: f32 = 80.0;
 
 #[derive(Component)]
-pub struct TitleScreenNewGameText;
-
-fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let e_title_screen = commands
-        .spawn((
-            TitleScreen,
-            Sprite::from_image(asset_server.load("images/title-screen.png")),
-            Transform::from_xyz(0.0, 0.0, 30.0),
-        ))
-        .id();
+pub struct TitleScreen;
 
-    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
-    let text_font = TextFont {
-        font: font.clone(),
-        font_size: TITLE_SCREEN_NEW_GAME_TEXT_SIZE.y,
-        ..default()
-    };
+#[derive(Clone, Copy)]
+enum MenuTarget {
+    Play(GameMode),
+    Controls,
+    Settings,
+}
 
-    commands.entity(e_title_screen).with_child((
-        TitleScreenNewGameText,
-        Text2d::new("NEW GAME"),
-        text_font,
-        TextColor(Color::WHITE),
-        Transform::from_xyz(
-            TITLE_SCREEN_NEW_GAME_TEXT_POS.x,
-            TITLE_SCREEN_NEW_GAME_TEXT_POS.y,
-            5.0,
-        ),
-    ));
+#[derive(Component)]
+pub struct TitleScreenMenuItem {
+    target: MenuTarget,
 }
 
-fn new_game_text_hover(
-    mouse_pos: Res<MousePos>,
-    mut q_new_game_text: Query<&mut TextColor, With<TitleScreenNewGameText>>,
+// shown when the game starts and when a game is quit, but not again when coming
+// back from the controls screen, which opens over it like the settings screen
+fn spawn_title_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    q_title_screen: Query<(), With<TitleScreen>>,
 ) {
-    if mouse_pos.is_in(
-        TITLE_SCREEN_NEW_GAME_TEXT_POS,
-        TITLE_SCREEN_NEW_GAME_TEXT_SIZE,
-    ) {
-        for mut text_color in q_new_game_text.iter_mut() {
-            *text_color = TextColor(Color::linear_rgba(0.7, 0.7, 1.0, 1.0));
-        }
-    } else {
-        for mut text_color in q_new_game_text.iter_mut() {
-            *text_color = TextColor(Color::WHITE);
-        }
+    if !q_title_screen.is_empty() {
+        return;
     }
+
+    let menu_items = [
+        ("SETTINGS", MenuTarget::Settings),
+        ("CONTROLS", MenuTarget::Controls),
+        ("CAMPAIGN", MenuTarget::Play(GameMode::Campaign(0))),
+        ("ENDLESS", MenuTarget::Play(GameMode::Endless)),
+        ("PUZZLES", MenuTarget::Play(GameMode::Puzzle(0))),
+    ];
+
+    commands
+        .spawn((TitleScreen, modal()))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    ImageNode::new(asset_server.load("images/title-screen.png")),
+                    Node {
+                        width: Val::Px(TITLE_SCREEN_SIZE.x),
+                        height: Val::Px(TITLE_SCREEN_SIZE.y),
+                        ..default()
+                    },
+                ))
+                .with_children(|parent| {
+                    parent
+                        .spawn(Node {
+                            position_type: PositionType::Absolute,
+                            left: Val::Px(TITLE_SCREEN_MENU_POS.x),
+                            top: Val::Px(TITLE_SCREEN_MENU_POS.y),
+                            flex_direction: FlexDirection::Column,
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            for (text, target) in menu_items {
+                                parent.spawn((
+                                    TitleScreenMenuItem { target },
+                                    UiButton {
+                                        colors: ButtonColors::TEXT,
+                                    },
+                                    label(
+                                        &asset_server,
+                                        text,
+                                        TITLE_SCREEN_MENU_TEXT_SIZE,
+                                        Color::WHITE,
+                                    ),
+                                    TextLayout::new_with_justify(JustifyText::Center),
+                                    Node {
+                                        width: Val::Px(TITLE_SCREEN_MENU_ITEM_SIZE.x),
+                                        height: Val::Px(TITLE_SCREEN_MENU_ITEM_SIZE.y),
+                                        ..default()
+                                    },
+                                ));
+                            }
+                        });
+                });
+        });
 }
 
-fn new_game_text_click(
+fn menu_item_click(
     mut commands: Commands,
-    mouse_pos: Res<MousePos>,
-    mouse_button: Res<ButtonInput<MouseButton>>,
+    mut evr_clicked: EventReader<ButtonClicked>,
     mut state: ResMut<NextState<GameState>>,
+    mut game_mode: ResMut<GameMode>,
+    mut evw_open_settings: EventWriter<OpenSettings>,
+    q_menu_items: Query<&TitleScreenMenuItem>,
     q_title_screen: Query<Entity, With<TitleScreen>>,
 ) {
-    if mouse_pos.is_in(
-        TITLE_SCREEN_NEW_GAME_TEXT_POS,
-        TITLE_SCREEN_NEW_GAME_TEXT_SIZE,
-    ) && mouse_button.just_pressed(MouseButton::Left)
-    {
-        commands.entity(q_title_screen.single().unwrap()).despawn();
-        state.set(GameState::Tutorial);
+    for event in evr_clicked.read() {
+        let Ok(menu_item) = q_menu_items.get(event.button) else {
+            continue;
+        };
+
+        match menu_item.target {
+            // the controls screen opens over the title screen and goes back to it
+            MenuTarget::Controls => state.set(GameState::InputSettings),
+            MenuTarget::Settings => {
+                evw_open_settings.write(OpenSettings);
+            }
+            MenuTarget::Play(mode) => {
+                commands.entity(q_title_screen.single().unwrap()).despawn();
+                *game_mode = mode;
+                state.set(GameState::Tutorial);
+            }
+        }
+        break;
     }
 }
 
 pub fn plugin(app: &mut App) {
-    app.add_systems(Startup, setup);
+    app.add_systems(OnEnter(GameState::TitleScreen), spawn_title_screen);
     app.add_systems(
         Update,
-        (new_game_text_hover, new_game_text_click).run_if(in_state(GameState::TitleScreen)),
+        menu_item_click.run_if(in_state(GameState::TitleScreen)),
     );
 }
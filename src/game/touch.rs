@@ -0,0 +1,250 @@
+use bevy::input::InputSystem;
+use bevy::input::touch::Touch;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::game::camera::MainCamera;
+use crate::game::chain::ChainSegment;
+use crate::game::events::RemoveChain;
+use crate::game::game_state::GameState;
+use crate::game::input::InputAction;
+use crate::game::mouse::MousePos;
+use crate::game::tile::{BackgroundTile, Tile};
+
+// Phones and tablets. A finger stands in for the left mouse button, so anything
+// that can be clicked can be tapped and chains are drawn by dragging. Holding a
+// finger still on a chain takes it back into the inventory, and pinching zooms in
+// on the hold.
+
+// how long a finger has to be held still on a chain to take it back
+const LONG_PRESS_TIME: f32 = 0.6;
+// how far a finger can wander, in screen pixels, and still count as held still
+const LONG_PRESS_SLOP: f32 = 12.0;
+
+// buttons are at least this big to a finger, however small they're drawn
+const MIN_TOUCH_TARGET: f32 = 140.0;
+
+const MAX_ZOOM: f32 = 2.5;
+
+// whether the game is being played by touch, so hit targets can grow to fit fingers
+#[derive(Resource, Default)]
+pub struct TouchScreen {
+    pub in_use: bool,
+}
+
+impl TouchScreen {
+    // how big a button is to whatever is pressing it
+    pub fn hit_size(&self, size: Vec2) -> Vec2 {
+        if self.in_use {
+            size.max(Vec2::splat(MIN_TOUCH_TARGET))
+        } else {
+            size
+        }
+    }
+}
+
+#[derive(Resource)]
+struct TouchState {
+    // the finger standing in for the mouse, if there is one
+    pointer: Option<u64>,
+    // how long the pointer has been held still
+    held_for: f32,
+    long_pressed: bool,
+    // the distance between the fingers of a pinch, and where they are, last frame
+    pinch: Option<(f32, Vec2)>,
+    zoom: f32,
+}
+
+impl Default for TouchState {
+    fn default() -> Self {
+        Self {
+            pointer: None,
+            held_for: 0.0,
+            long_pressed: false,
+            pinch: None,
+            zoom: 1.0,
+        }
+    }
+}
+
+fn world_pos(
+    screen_pos: Vec2,
+    q_camera: &Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+) -> Option<Vec2> {
+    let (camera, transform) = q_camera.single().ok()?;
+    camera.viewport_to_world_2d(transform, screen_pos).ok()
+}
+
+// runs straight after bevy reads the mouse, so the rest of the frame sees the
+// finger as a held left button
+fn touch_pointer(
+    touches: Res<Touches>,
+    mut touch_screen: ResMut<TouchScreen>,
+    mut state: ResMut<TouchState>,
+    mut mouse_pos: ResMut<MousePos>,
+    mut mouse_button: ResMut<ButtonInput<MouseButton>>,
+    q_camera: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    mut evw_action: EventWriter<InputAction>,
+) {
+    if touches.any_just_pressed() {
+        touch_screen.in_use = true;
+    }
+
+    // a second finger starts a pinch, dropping whatever the first was drawing
+    if touches.iter().count() > 1 {
+        if state.pointer.take().is_some() {
+            evw_action.write(InputAction::Cancel);
+            mouse_button.release(MouseButton::Left);
+        }
+        return;
+    }
+
+    if let Some(id) = state.pointer {
+        let Some(touch) = touches
+            .get_pressed(id)
+            .or_else(|| touches.iter_just_released().find(|touch| touch.id() == id))
+        else {
+            // the system took the finger away, so let go without finishing anything
+            state.pointer = None;
+            evw_action.write(InputAction::Cancel);
+            mouse_button.release(MouseButton::Left);
+            return;
+        };
+
+        if let Some(pos) = world_pos(touch.position(), &q_camera) {
+            mouse_pos.0 = pos;
+        }
+        if touches.just_released(id) {
+            state.pointer = None;
+            mouse_button.release(MouseButton::Left);
+        }
+        return;
+    }
+
+    // only a finger that lands on its own becomes the pointer, not the last one of a pinch
+    let Some(touch) = touches.iter_just_pressed().next() else {
+        return;
+    };
+    if let Some(pos) = world_pos(touch.position(), &q_camera) {
+        mouse_pos.0 = pos;
+    }
+    state.pointer = Some(touch.id());
+    state.held_for = 0.0;
+    state.long_pressed = false;
+    mouse_button.press(MouseButton::Left);
+}
+
+fn held_still(touch: &Touch) -> bool {
+    touch.distance().length() <= LONG_PRESS_SLOP
+}
+
+fn long_press_chain(
+    time: Res<Time>,
+    touches: Res<Touches>,
+    mut state: ResMut<TouchState>,
+    q_tiles: Query<(&Tile, &BackgroundTile)>,
+    q_segments: Query<(&ChainSegment, &ChildOf)>,
+    mut evw_remove_chain: EventWriter<RemoveChain>,
+    mut evw_action: EventWriter<InputAction>,
+) {
+    let Some(touch) = state.pointer.and_then(|id| touches.get_pressed(id)) else {
+        return;
+    };
+    if state.long_pressed || !held_still(touch) {
+        state.held_for = 0.0;
+        return;
+    }
+
+    state.held_for += time.delta_secs();
+    if state.held_for < LONG_PRESS_TIME {
+        return;
+    }
+    state.long_pressed = true;
+
+    let Some((tile, _)) = q_tiles.iter().find(|(_, background)| background.is_hovered) else {
+        return;
+    };
+    let Some((_, child_of)) = q_segments.iter().find(|(segment, _)| segment.tile == *tile) else {
+        return;
+    };
+
+    // pressing on a chain with one picked starts a branch out of it, which goes too
+    evw_action.write(InputAction::Cancel);
+    evw_remove_chain.write(RemoveChain {
+        chain: child_of.parent(),
+    });
+}
+
+fn pinch_zoom(
+    touches: Res<Touches>,
+    mut state: ResMut<TouchState>,
+    q_window: Query<&Window, With<PrimaryWindow>>,
+    mut q_camera: Query<(&mut Transform, &Projection), With<MainCamera>>,
+) {
+    let fingers = touches
+        .iter()
+        .take(2)
+        .map(|touch| touch.position())
+        .collect::<Vec<_>>();
+    let [a, b] = fingers[..] else {
+        state.pinch = None;
+        return;
+    };
+    let (Ok(window), Ok((mut transform, projection))) = (q_window.single(), q_camera.single_mut())
+    else {
+        return;
+    };
+    // how much of the world is on screen when not zoomed in
+    let Projection::Orthographic(orthographic) = projection else {
+        return;
+    };
+    let view = orthographic.area.size();
+
+    let distance = a.distance(b).max(1.0);
+    let centre = (a + b) / 2.0;
+    let Some((last_distance, last_centre)) = state.pinch.replace((distance, centre)) else {
+        return;
+    };
+
+    state.zoom = (state.zoom * distance / last_distance).clamp(1.0, MAX_ZOOM);
+    let scale = 1.0 / state.zoom;
+
+    // drag the view along with the fingers, but never past the edges of the screen
+    let world_per_pixel = view.y * scale / window.height();
+    let moved = (centre - last_centre) * world_per_pixel;
+    let slack = view / 2.0 * (1.0 - scale);
+    let pos = transform.translation.xy() + Vec2::new(-moved.x, moved.y);
+    let pos = pos.clamp(-slack, slack);
+
+    transform.translation.x = pos.x;
+    transform.translation.y = pos.y;
+    transform.scale = Vec3::new(scale, scale, 1.0);
+}
+
+// a zoom left over from a wave would hide the inventory, or the menus
+fn reset_zoom(
+    mut state: ResMut<TouchState>,
+    mut q_camera: Query<&mut Transform, With<MainCamera>>,
+) {
+    state.pinch = None;
+    state.zoom = 1.0;
+    for mut transform in q_camera.iter_mut() {
+        *transform = Transform::IDENTITY;
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app.init_resource::<TouchScreen>();
+    app.init_resource::<TouchState>();
+    app.add_systems(PreUpdate, touch_pointer.after(InputSystem));
+    app.add_systems(
+        Update,
+        long_press_chain.run_if(in_state(GameState::Building)),
+    );
+    app.add_systems(
+        Update,
+        pinch_zoom.run_if(in_state(GameState::Building).or(in_state(GameState::WaveInProgress))),
+    );
+    app.add_systems(OnExit(GameState::WaveInProgress), reset_zoom);
+    app.add_systems(OnEnter(GameState::TitleScreen), reset_zoom);
+}
@@ -1,9 +1,8 @@
 use bevy::prelude::*;
-use bevy::text::{LineBreak, TextBounds};
+use bevy::text::LineBreak;
 
-use crate::game::events::PlayLongClickSFX;
-use crate::game::game_state::GameState;
-use crate::game::mouse::MousePos;
+use crate::game::game_state::{GameMode, GameState};
+use crate::game::ui::{ButtonClicked, PANEL_TEXT_COLOR, label, modal, panel, text_button};
 
 const TUTORIAL_WINDOW_WIDTH: f32 = 1400.0;
 const TUTORIAL_WINDOW_HEIGHT: f32 = 940.0;
@@ -11,10 +10,8 @@ const TUTORIAL_WINDOW_HEIGHT: f32 = 940.0;
 const TUTORIAL_WINDOW_PADDING: f32 = 20.0;
 
 const TUTORIAL_TEXT_BOX_WIDTH: f32 = TUTORIAL_WINDOW_WIDTH - 2.0 * TUTORIAL_WINDOW_PADDING;
-const TUTORIAL_TEXT_BOX_HEIGHT: f32 = TUTORIAL_WINDOW_HEIGHT - 2.0 * TUTORIAL_WINDOW_PADDING;
 
 const OK_BUTTON_SIZE: Vec2 = Vec2::new(150.0, 100.0);
-const OK_BUTTON_POS: Vec2 = Vec2::new(0.0, -400.0);
 
 const TUTORIAL_TEXT: &str = "
 Welcome to Chain Lockers!
@@ -23,7 +20,7 @@ Chain Lockers are holds within ships to hold massive chains. These chains go thr
 
 In this game, you will place chains to asphyxiate pirates trying to steal your gold.
 
-To build a chain, click on a chain button in the main inventory to select it, then drag inside the hold to construct a maze.
+To build a chain, click on a chain button in the main inventory to select it, then drag inside the hold to construct a maze. Drag back to take links off again, or hold Shift as you let go to keep a shorter chain and get the rest back. You can also pick a chain with 1-9, move around the hold with the arrow keys and lay it with Space, or use a gamepad. These can all be changed under Controls on the title screen.
 
 The number in the top right of each chain type is your stock, and the bottom left is the length of that chain.
 
@@ -36,98 +33,64 @@ pub struct TutorialWindow;
 #[derive(Component, Debug)]
 pub struct OkButton;
 
-#[derive(Component, Debug)]
-pub struct OkButtonText;
-
-fn setup(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-) {
-    let rect = Rectangle::new(TUTORIAL_WINDOW_WIDTH, TUTORIAL_WINDOW_HEIGHT);
-    let color = Color::linear_rgba(0.8, 0.8, 0.8, 1.0);
-
-    let e_tutorial_window = commands
-        .spawn((
-            TutorialWindow,
-            Mesh2d(meshes.add(rect)),
-            MeshMaterial2d(materials.add(color)),
-            Transform::from_xyz(0.0, 0.0, 15.0),
-        ))
-        .id();
-
-    commands.entity(e_tutorial_window).with_children(|parent| {
-        let font = asset_server.load("fonts/FiraSans-Bold.ttf");
-        let title_font = TextFont {
-            font: font.clone(),
-            font_size: 50.0,
-            ..default()
-        };
-        let body_text_font = TextFont {
-            font: font.clone(),
-            font_size: 35.0,
-            ..default()
-        };
-        parent.spawn((
-            Text2d::new("Chain Lockers"),
-            title_font,
-            TextColor(Color::linear_rgb(0.0, 0.0, 0.0)),
-            Transform::from_xyz(0.0, 400.0, 0.5),
-        ));
-        parent.spawn((
-            Text2d::new(TUTORIAL_TEXT),
-            body_text_font,
-            TextLayout::new(JustifyText::Left, LineBreak::WordBoundary),
-            TextColor(Color::linear_rgb(0.0, 0.0, 0.0)),
-            Transform::from_xyz(0.0, -100.0, 0.5),
-            TextBounds::from(Vec2::new(TUTORIAL_TEXT_BOX_WIDTH, TUTORIAL_TEXT_BOX_HEIGHT)),
-        ));
-
-        // spawn ok button
-        let rect = Rectangle::new(OK_BUTTON_SIZE.x, OK_BUTTON_SIZE.y);
-        let color = Color::linear_rgba(0.0, 0.0, 1.0, 1.0);
-        let text_font = TextFont {
-            font: font.clone(),
-            font_size: 30.0,
-            ..default()
-        };
-
-        parent
-            .spawn((
-                OkButton,
-                Transform::from_xyz(OK_BUTTON_POS.x, OK_BUTTON_POS.y, 5.0),
-                Mesh2d(meshes.add(rect)),
-                MeshMaterial2d(materials.add(color)),
-            ))
-            .with_child((
-                OkButtonText,
-                Text2d::new("OK"),
-                text_font.clone(),
-                TextColor(Color::linear_rgba(1.0, 1.0, 1.0, 1.0)),
-            ));
-    });
+fn spawn_tutorial_window(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn((TutorialWindow, modal()))
+        .with_children(|parent| {
+            parent
+                .spawn(panel(Vec2::new(
+                    TUTORIAL_WINDOW_WIDTH,
+                    TUTORIAL_WINDOW_HEIGHT,
+                )))
+                .with_children(|parent| {
+                    parent.spawn(label(
+                        &asset_server,
+                        "Chain Lockers",
+                        50.0,
+                        PANEL_TEXT_COLOR,
+                    ));
+                    parent.spawn((
+                        label(&asset_server, TUTORIAL_TEXT.trim(), 35.0, PANEL_TEXT_COLOR),
+                        TextLayout::new(JustifyText::Left, LineBreak::WordBoundary),
+                        Node {
+                            width: Val::Px(TUTORIAL_TEXT_BOX_WIDTH),
+                            ..default()
+                        },
+                    ));
+                    parent.spawn((OkButton, text_button(&asset_server, "OK", OK_BUTTON_SIZE)));
+                });
+        });
 }
 
 fn ok_button(
-    mut commands: Commands,
-    mouse_pos: Res<MousePos>,
-    mouse_button: Res<ButtonInput<MouseButton>>,
+    mut evr_clicked: EventReader<ButtonClicked>,
     mut state: ResMut<NextState<GameState>>,
-    q_tutorial_window: Query<(Entity, &TutorialWindow)>,
-    mut evw: EventWriter<PlayLongClickSFX>,
+    game_mode: Res<GameMode>,
+    q_ok_button: Query<(), With<OkButton>>,
 ) {
-    if mouse_button.just_pressed(MouseButton::Left)
-        && mouse_pos.is_in(OK_BUTTON_POS, OK_BUTTON_SIZE)
+    if evr_clicked
+        .read()
+        .any(|event| q_ok_button.contains(event.button))
     {
-        state.set(GameState::Building);
-        let (e_tutorial_window, _) = q_tutorial_window.single().unwrap();
-        commands.entity(e_tutorial_window).despawn();
-        evw.write(PlayLongClickSFX);
+        match *game_mode {
+            GameMode::Endless => state.set(GameState::Building),
+            GameMode::Campaign(_) => state.set(GameState::LevelSelect),
+            GameMode::Puzzle(_) => state.set(GameState::PuzzleSelect),
+        }
+    }
+}
+
+fn despawn_tutorial_window(
+    mut commands: Commands,
+    q_tutorial_window: Query<Entity, With<TutorialWindow>>,
+) {
+    for entity in q_tutorial_window.iter() {
+        commands.entity(entity).despawn();
     }
 }
 
 pub fn plugin(app: &mut App) {
-    app.add_systems(Startup, setup);
-    app.add_systems(Update, (ok_button).run_if(in_state(GameState::Tutorial)));
+    app.add_systems(OnEnter(GameState::Tutorial), spawn_tutorial_window);
+    app.add_systems(OnExit(GameState::Tutorial), despawn_tutorial_window);
+    app.add_systems(Update, ok_button.run_if(in_state(GameState::Tutorial)));
 }
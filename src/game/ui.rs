@@ -0,0 +1,517 @@
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use bevy::ui::{FocusPolicy, UiSystem};
+use bevy::window::PrimaryWindow;
+
+use crate::game::camera::PLAY_AREA;
+use crate::game::events::{PlayButtonClickSFX, PlayButtonHoverSFX};
+use crate::game::game_state::GameState;
+use crate::game::input_map::{BoundAction, InputDevices, InputMap};
+use crate::game::tile::Direction;
+use crate::game::touch::TouchScreen;
+
+// Buttons, panels and modal windows, built on bevy_ui. Screens spawn them with
+// the helpers here and read `ButtonClicked` rather than hit-testing the mouse
+// themselves. Buttons light up when hovered, make a sound when hovered and when
+// clicked, and can be greyed out with `Disabled`. The buttons of the topmost
+// modal can also be stepped between with the cursor bindings and pressed with
+// Place, so every screen can be got through with a keyboard or a gamepad.
+//
+// The UI is scaled the same way as the world at its normal zoom, so a window
+// laid out in the play area's units always fits on screen. Hud things are pinned
+// to the edges of the window with `Placed` rather than to the hold, so they move
+// out of the way when the window is wider or taller than the play area.
+
+pub const PANEL_COLOR: Color = Color::linear_rgba(0.8, 0.8, 0.8, 1.0);
+pub const PANEL_TEXT_COLOR: Color = Color::linear_rgb(0.0, 0.0, 0.0);
+pub const BUTTON_COLOR: Color = Color::linear_rgba(0.0, 0.0, 1.0, 1.0);
+const DISABLED_COLOR: Color = Color::linear_rgba(0.4, 0.4, 0.4, 1.0);
+const FOCUS_COLOR: Color = Color::linear_rgba(1.0, 0.85, 0.0, 1.0);
+
+// how far hud things sit in from the edges of the screen
+pub const HUD_MARGIN: f32 = 40.0;
+
+const PANEL_PADDING: f32 = 20.0;
+const FOCUS_OUTLINE_WIDTH: f32 = 4.0;
+
+// modals go over everything else, and each new one over the last
+const MODAL_Z_INDEX: i32 = 10;
+
+// How a button is coloured, depending on what's happening to it. Buttons with an
+// image have it tinted, buttons that are only text have the text recoloured, and
+// anything else has its background filled in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ButtonColors {
+    pub idle: Color,
+    pub hovered: Color,
+    pub pressed: Color,
+}
+
+impl ButtonColors {
+    pub const FILLED: ButtonColors = ButtonColors {
+        idle: BUTTON_COLOR,
+        hovered: Color::linear_rgba(0.25, 0.25, 1.0, 1.0),
+        pressed: Color::linear_rgba(0.0, 0.0, 0.6, 1.0),
+    };
+
+    pub const TEXT: ButtonColors = ButtonColors {
+        idle: Color::WHITE,
+        hovered: Color::linear_rgba(0.7, 0.7, 1.0, 1.0),
+        pressed: Color::linear_rgba(0.5, 0.5, 1.0, 1.0),
+    };
+
+    pub const IMAGE: ButtonColors = ButtonColors {
+        idle: Color::WHITE,
+        hovered: BUTTON_COLOR,
+        pressed: Color::linear_rgba(0.0, 0.0, 0.6, 1.0),
+    };
+
+    pub fn filled(color: Color) -> Self {
+        Self {
+            idle: color,
+            hovered: color.lighter(0.1),
+            pressed: color.darker(0.1),
+        }
+    }
+}
+
+#[derive(Component, Clone, Copy, Debug)]
+#[require(Button, Outline, ButtonFeedback)]
+pub struct UiButton {
+    pub colors: ButtonColors,
+}
+
+// a button that can't be pressed right now, which is greyed out and kept out of focus
+#[derive(Component, Debug)]
+pub struct Disabled;
+
+// what the button was doing last frame, so only moving onto it makes a sound
+#[derive(Component, Default)]
+struct ButtonFeedback {
+    last: Interaction,
+}
+
+// sent when a button is clicked, tapped, or pressed with Place while it has focus
+#[derive(Event, Debug)]
+pub struct ButtonClicked {
+    pub button: Entity,
+}
+
+// a window that takes the pointer and the keyboard from everything under it
+#[derive(Component, Debug)]
+pub struct Modal;
+
+// things that sit over the hold while playing. Their buttons can only be pressed
+// in `usable_in`
+#[derive(Component, Debug)]
+pub struct Hud {
+    pub usable_in: GameState,
+}
+
+// a node pinned to a corner or edge of its parent, usually the whole screen,
+// `offset` in from it. Along an axis it's centred on, it's moved right or up by
+// `offset` instead.
+#[derive(Component, Clone, Copy, Debug)]
+#[require(Node)]
+pub struct Placed {
+    pub anchor: Anchor,
+    pub offset: Vec2,
+}
+
+// the button that Place presses
+#[derive(Resource, Default)]
+pub struct UiFocus {
+    pub button: Option<Entity>,
+    // only outlined once it's been moved without the mouse
+    shown: bool,
+    // set by screens that want the navigation keys for themselves
+    pub paused: bool,
+}
+
+pub fn label(
+    asset_server: &AssetServer,
+    text: impl Into<String>,
+    font_size: f32,
+    color: Color,
+) -> impl Bundle {
+    (
+        Text::new(text),
+        TextFont {
+            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+            font_size,
+            ..default()
+        },
+        TextColor(color),
+    )
+}
+
+pub fn button(size: Vec2) -> impl Bundle {
+    (
+        UiButton {
+            colors: ButtonColors::FILLED,
+        },
+        Node {
+            width: Val::Px(size.x),
+            height: Val::Px(size.y),
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        BackgroundColor(BUTTON_COLOR),
+    )
+}
+
+pub fn text_button(asset_server: &AssetServer, text: impl Into<String>, size: Vec2) -> impl Bundle {
+    (
+        button(size),
+        children![label(asset_server, text, 30.0, Color::WHITE)],
+    )
+}
+
+// a grey window, with its contents spread out top to bottom
+pub fn panel(size: Vec2) -> impl Bundle {
+    (
+        Node {
+            width: Val::Px(size.x),
+            height: Val::Px(size.y),
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::SpaceEvenly,
+            align_items: AlignItems::Center,
+            padding: UiRect::all(Val::Px(PANEL_PADDING)),
+            ..default()
+        },
+        BackgroundColor(PANEL_COLOR),
+    )
+}
+
+// covers the screen and centres whatever is spawned in it, usually a panel
+pub fn modal() -> impl Bundle {
+    (
+        Modal,
+        full_screen(),
+        FocusPolicy::Block,
+        GlobalZIndex(MODAL_Z_INDEX),
+    )
+}
+
+pub fn hud(usable_in: GameState) -> impl Bundle {
+    (Hud { usable_in }, full_screen())
+}
+
+fn full_screen() -> Node {
+    Node {
+        position_type: PositionType::Absolute,
+        width: Val::Percent(100.0),
+        height: Val::Percent(100.0),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    }
+}
+
+fn fit_ui_to_window(
+    mut ui_scale: ResMut<UiScale>,
+    q_window: Query<&Window, (With<PrimaryWindow>, Changed<Window>)>,
+) {
+    for window in q_window.iter() {
+        let scale = window.size() / PLAY_AREA;
+        ui_scale.0 = scale.min_element();
+    }
+}
+
+// placed buttons grow to fit a finger, the same as the chain buttons do
+fn place_nodes(
+    touch_screen: Res<TouchScreen>,
+    mut q_placed: Query<(Ref<Placed>, &mut Node, Has<UiButton>)>,
+) {
+    for (placed, mut node, is_button) in q_placed.iter_mut() {
+        if !placed.is_changed() && !touch_screen.is_changed() {
+            continue;
+        }
+
+        let side = placed.anchor.as_vec();
+        let offset = placed.offset;
+        node.position_type = PositionType::Absolute;
+        (node.left, node.right, node.margin.left, node.margin.right) = match side.x {
+            x if x < 0.0 => (Val::Px(offset.x), Val::Auto, Val::ZERO, Val::ZERO),
+            x if x > 0.0 => (Val::Auto, Val::Px(offset.x), Val::ZERO, Val::ZERO),
+            _ => (Val::Px(offset.x), Val::Px(-offset.x), Val::Auto, Val::Auto),
+        };
+        (node.top, node.bottom, node.margin.top, node.margin.bottom) = match side.y {
+            y if y > 0.0 => (Val::Px(offset.y), Val::Auto, Val::ZERO, Val::ZERO),
+            y if y < 0.0 => (Val::Auto, Val::Px(offset.y), Val::ZERO, Val::ZERO),
+            _ => (Val::Px(-offset.y), Val::Px(offset.y), Val::Auto, Val::Auto),
+        };
+
+        if is_button {
+            let min_size = touch_screen.hit_size(Vec2::ZERO);
+            node.min_width = Val::Px(min_size.x);
+            node.min_height = Val::Px(min_size.y);
+        }
+    }
+}
+
+fn stack_modals(mut q_modals: Query<(Ref<Modal>, &mut GlobalZIndex)>) {
+    let mut next = q_modals
+        .iter()
+        .filter(|(modal, _)| !modal.is_added())
+        .map(|(_, z_index)| z_index.0 + 1)
+        .max()
+        .unwrap_or(MODAL_Z_INDEX);
+
+    for (modal, mut z_index) in q_modals.iter_mut() {
+        if modal.is_added() {
+            z_index.0 = next;
+            next += 1;
+        }
+    }
+}
+
+// the hud is hidden on the title screen, and its buttons only work when they're meant to
+fn update_hud(
+    mut commands: Commands,
+    game_state: Res<State<GameState>>,
+    mut q_hud: Query<(&Hud, &mut Visibility)>,
+    q_buttons: Query<Entity, With<UiButton>>,
+    q_parents: Query<&ChildOf>,
+) {
+    if !game_state.is_changed() {
+        return;
+    }
+
+    let state = *game_state.get();
+    let shown = !matches!(state, GameState::TitleScreen | GameState::InputSettings);
+    for (_, mut visibility) in q_hud.iter_mut() {
+        *visibility = if shown {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+
+    for button in q_buttons.iter() {
+        let Some((hud, _)) = q_parents
+            .iter_ancestors(button)
+            .find_map(|ancestor| q_hud.get(ancestor).ok())
+        else {
+            continue;
+        };
+        if state == hud.usable_in {
+            commands.entity(button).remove::<Disabled>();
+        } else {
+            commands.entity(button).insert(Disabled);
+        }
+    }
+}
+
+// runs straight after bevy_ui works out what the pointer is over, so every
+// system in the frame sees the clicks
+fn button_feedback(
+    mut focus: ResMut<UiFocus>,
+    mut q_buttons: Query<
+        (Entity, &Interaction, &mut ButtonFeedback, Has<Disabled>),
+        (With<UiButton>, Changed<Interaction>),
+    >,
+    mut evw_clicked: EventWriter<ButtonClicked>,
+    mut evw_click_sfx: EventWriter<PlayButtonClickSFX>,
+    mut evw_hover_sfx: EventWriter<PlayButtonHoverSFX>,
+) {
+    for (entity, interaction, mut feedback, disabled) in q_buttons.iter_mut() {
+        let last = std::mem::replace(&mut feedback.last, *interaction);
+        if disabled {
+            continue;
+        }
+
+        match interaction {
+            Interaction::Pressed => {
+                evw_clicked.write(ButtonClicked { button: entity });
+                evw_click_sfx.write(PlayButtonClickSFX);
+            }
+            Interaction::Hovered if last == Interaction::None => {
+                focus.button = Some(entity);
+                focus.shown = false;
+                evw_hover_sfx.write(PlayButtonHoverSFX);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn navigate_focus(
+    input_map: Res<InputMap>,
+    devices: InputDevices,
+    mut focus: ResMut<UiFocus>,
+    q_modals: Query<(Entity, &GlobalZIndex, &InheritedVisibility), With<Modal>>,
+    q_buttons: Query<
+        (Entity, &GlobalTransform, &InheritedVisibility),
+        (With<UiButton>, Without<Disabled>),
+    >,
+    q_parents: Query<&ChildOf>,
+    mut evw_clicked: EventWriter<ButtonClicked>,
+    mut evw_click_sfx: EventWriter<PlayButtonClickSFX>,
+) {
+    // only the buttons of the window on top can be reached
+    let top_modal = q_modals
+        .iter()
+        .filter(|(_, _, visibility)| visibility.get())
+        .max_by_key(|(entity, z_index, _)| (z_index.0, *entity))
+        .map(|(entity, _, _)| entity);
+    let mut buttons = q_buttons
+        .iter()
+        .filter(|(entity, _, visibility)| {
+            visibility.get()
+                && top_modal.is_some_and(|modal| {
+                    q_parents
+                        .iter_ancestors(*entity)
+                        .any(|ancestor| ancestor == modal)
+                })
+        })
+        // ui positions are measured down from the top of the window
+        .map(|(entity, transform, _)| {
+            (
+                entity,
+                transform.translation().truncate() * Vec2::new(1.0, -1.0),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let current = focus.button.and_then(|focused| {
+        buttons
+            .iter()
+            .find(|(entity, _)| *entity == focused)
+            .copied()
+    });
+    if current.is_none() {
+        focus.button = None;
+    }
+    if focus.paused || buttons.is_empty() {
+        return;
+    }
+
+    if input_map.just_pressed(BoundAction::Place, &devices) {
+        match current {
+            Some((entity, _)) if focus.shown => {
+                evw_clicked.write(ButtonClicked { button: entity });
+                evw_click_sfx.write(PlayButtonClickSFX);
+            }
+            _ => {
+                // the first press shows where the focus is, rather than pressing something unseen
+                focus.shown = true;
+                if current.is_none() {
+                    buttons.sort_by(|(_, a), (_, b)| b.y.total_cmp(&a.y).then(a.x.total_cmp(&b.x)));
+                    focus.button = Some(buttons[0].0);
+                }
+            }
+        }
+        return;
+    }
+
+    let moves = [
+        (BoundAction::CursorUp, Direction::Up),
+        (BoundAction::CursorDown, Direction::Down),
+        (BoundAction::CursorLeft, Direction::Left),
+        (BoundAction::CursorRight, Direction::Right),
+    ];
+    let Some(direction) = moves
+        .into_iter()
+        .find(|(action, _)| input_map.just_pressed(*action, &devices))
+        .map(|(_, direction)| direction)
+    else {
+        return;
+    };
+
+    focus.shown = true;
+    let Some((focused, from)) = current else {
+        buttons.sort_by(|(_, a), (_, b)| b.y.total_cmp(&a.y).then(a.x.total_cmp(&b.x)));
+        focus.button = Some(buttons[0].0);
+        return;
+    };
+
+    // the nearest button that way, preferring ones in line with this one
+    let towards = match direction {
+        Direction::Up => Vec2::Y,
+        Direction::Down => Vec2::NEG_Y,
+        Direction::Left => Vec2::NEG_X,
+        Direction::Right => Vec2::X,
+    };
+    let next = buttons
+        .iter()
+        .filter(|(entity, _)| *entity != focused)
+        .filter_map(|(entity, pos)| {
+            let offset = *pos - from;
+            let along = offset.dot(towards);
+            let across = (offset - towards * along).length();
+            (along > 1.0).then_some((*entity, along + 2.0 * across))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b));
+    if let Some((entity, _)) = next {
+        focus.button = Some(entity);
+    }
+}
+
+fn paint_buttons(
+    focus: Res<UiFocus>,
+    mut q_buttons: Query<(
+        Entity,
+        &UiButton,
+        &Interaction,
+        Has<Disabled>,
+        &mut Outline,
+        Option<&mut ImageNode>,
+        Option<&mut TextColor>,
+        Option<&mut BackgroundColor>,
+    )>,
+) {
+    for (entity, button, interaction, disabled, mut outline, image, text_color, background) in
+        q_buttons.iter_mut()
+    {
+        let focused = focus.button == Some(entity);
+        let color = if disabled {
+            DISABLED_COLOR
+        } else {
+            match interaction {
+                Interaction::Pressed => button.colors.pressed,
+                Interaction::Hovered => button.colors.hovered,
+                Interaction::None if focused => button.colors.hovered,
+                Interaction::None => button.colors.idle,
+            }
+        };
+
+        if let Some(mut image) = image {
+            image.color = color;
+        } else if let Some(mut text_color) = text_color {
+            text_color.0 = color;
+        } else if let Some(mut background) = background {
+            background.0 = color;
+        }
+
+        let outline_color = if focused && focus.shown && !disabled {
+            FOCUS_COLOR
+        } else {
+            Color::NONE
+        };
+        *outline = Outline::new(Val::Px(FOCUS_OUTLINE_WIDTH), Val::Px(2.0), outline_color);
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app.add_event::<ButtonClicked>();
+    app.init_resource::<UiFocus>();
+    app.add_systems(
+        PreUpdate,
+        (button_feedback, navigate_focus)
+            .chain()
+            .after(UiSystem::Focus),
+    );
+    app.add_systems(
+        Update,
+        (
+            fit_ui_to_window,
+            place_nodes,
+            stack_modals,
+            update_hud,
+            paint_buttons,
+        ),
+    );
+}
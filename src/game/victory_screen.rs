@@ -0,0 +1,94 @@
+use bevy::prelude::*;
+use bevy::text::LineBreak;
+
+use crate::game::events::{LevelComplete, RunReset, WaveReset};
+use crate::game::game_state::GameState;
+use crate::game::level::LEVELS;
+use crate::game::ui::{ButtonClicked, PANEL_TEXT_COLOR, label, modal, panel, text_button};
+
+const VICTORY_WINDOW_WIDTH: f32 = 1400.0;
+const VICTORY_WINDOW_HEIGHT: f32 = 800.0;
+
+const VICTORY_WINDOW_PADDING: f32 = 20.0;
+
+const VICTORY_TEXT_BOX_WIDTH: f32 = VICTORY_WINDOW_WIDTH - 2.0 * VICTORY_WINDOW_PADDING;
+
+const CONTINUE_BUTTON_SIZE: Vec2 = Vec2::new(220.0, 100.0);
+
+#[derive(Component, Debug)]
+pub struct VictoryWindow;
+
+#[derive(Component, Debug)]
+pub struct ContinueButton;
+
+fn spawn_victory_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut evr_level_complete: EventReader<LevelComplete>,
+) {
+    for event in evr_level_complete.read() {
+        let victory_text = format!(
+            "You held off every wave of {}!\n\nStars: {} / 3",
+            LEVELS[event.level].name, event.stars
+        );
+
+        commands
+            .spawn((VictoryWindow, modal()))
+            .with_children(|parent| {
+                parent
+                    .spawn(panel(Vec2::new(
+                        VICTORY_WINDOW_WIDTH,
+                        VICTORY_WINDOW_HEIGHT,
+                    )))
+                    .with_children(|parent| {
+                        parent.spawn(label(
+                            &asset_server,
+                            "Level Complete",
+                            50.0,
+                            PANEL_TEXT_COLOR,
+                        ));
+
+                        parent.spawn((
+                            label(&asset_server, victory_text, 35.0, PANEL_TEXT_COLOR),
+                            TextLayout::new(JustifyText::Center, LineBreak::WordBoundary),
+                            Node {
+                                width: Val::Px(VICTORY_TEXT_BOX_WIDTH),
+                                ..default()
+                            },
+                        ));
+
+                        parent.spawn((
+                            ContinueButton,
+                            text_button(&asset_server, "Continue", CONTINUE_BUTTON_SIZE),
+                        ));
+                    });
+            });
+    }
+}
+
+fn continue_button(
+    mut commands: Commands,
+    mut evr_clicked: EventReader<ButtonClicked>,
+    mut state: ResMut<NextState<GameState>>,
+    q_continue_button: Query<(), With<ContinueButton>>,
+    q_victory_window: Query<Entity, With<VictoryWindow>>,
+    mut evw_run_reset: EventWriter<RunReset>,
+    mut evw_wave_reset: EventWriter<WaveReset>,
+) {
+    if evr_clicked
+        .read()
+        .any(|event| q_continue_button.contains(event.button))
+    {
+        for entity in q_victory_window.iter() {
+            commands.entity(entity).despawn();
+        }
+        state.set(GameState::LevelSelect);
+        evw_run_reset.write(RunReset);
+        evw_wave_reset.write(WaveReset);
+    }
+}
+
+pub fn plugin(app: &mut App) {
+    app.add_systems(Update, spawn_victory_screen);
+    app.add_systems(Update, continue_button.run_if(in_state(GameState::Victory)));
+}